@@ -9,15 +9,17 @@ extern crate ndarray;
 ///! where L is a laplacian matrix on a 2 dimensional grid, and rhs is
 ///! zero everywhere except for values corresponding to borders, where
 ///! a constant heat value is imposed.
-///! Since the L matrix is diagonally dominant, we can use a Gauss-Seidel
-///! iterative scheme to solve the system.
+///! Since the L matrix is diagonally dominant, we can use the Gauss-Seidel
+///! iterative scheme from `sprs::linalg::relaxation` to solve the system.
 ///!
 ///! This shows how a laplacian matrix can be constructed by directly
 ///! constructing the compressed structure, and how the resulting linear
 ///! system can be solved using an iterative method.
 extern crate sprs;
 
-type VecView<'a, T> = ndarray::ArrayView<'a, T, ndarray::Ix1>;
+use sprs::linalg::iterative::{stationary, StoppingCriteria};
+use sprs::linalg::relaxation::{Relaxation, SweepDirection};
+
 type VecViewMut<'a, T> = ndarray::ArrayViewMut<'a, T, ndarray::Ix1>;
 type OwnedVec<T> = ndarray::Array<T, ndarray::Ix1>;
 
@@ -100,46 +102,6 @@ fn set_boundary_condition<F>(
     }
 }
 
-/// Gauss-Seidel method to solve the system
-/// see https://en.wikipedia.org/wiki/Gauss%E2%80%93Seidel_method#Algorithm
-fn gauss_seidel(
-    mat: sprs::CsMatView<f64>,
-    mut x: VecViewMut<f64>,
-    rhs: VecView<f64>,
-    max_iter: usize,
-    eps: f64,
-) -> Result<(usize, f64), f64> {
-    assert!(mat.rows() == mat.cols());
-    assert!(mat.rows() == x.shape()[0]);
-    let mut error = (&mat * &x - rhs).scalar_sum().sqrt();
-    for it in 0..max_iter {
-        for (row_ind, vec) in mat.outer_iterator().enumerate() {
-            let mut sigma = 0.;
-            let mut diag = None;
-            for (col_ind, &val) in vec.iter() {
-                if row_ind != col_ind {
-                    sigma += val * x[[col_ind]];
-                } else {
-                    diag = Some(val);
-                }
-            }
-            // Gauss-Seidel requires a non-zero diagonal, which
-            // is satisfied for a laplacian matrix
-            let diag = diag.unwrap();
-            let cur_rhs = rhs[[row_ind]];
-            x[[row_ind]] = (cur_rhs - sigma) / diag;
-        }
-
-        error = (&mat * &x - rhs).scalar_sum().sqrt();
-        // error corresponds to the state before iteration, but
-        // that shouldn't be a problem
-        if error < eps {
-            return Ok((it, error));
-        }
-    }
-    Err(error)
-}
-
 fn main() {
     let (rows, cols) = (10, 10);
     let lap = grid_laplacian((rows, cols));
@@ -150,23 +112,30 @@ fn main() {
 
     let mut x = OwnedVec::zeros(rows * cols);
 
-    match gauss_seidel(lap.view(), x.view_mut(), rhs.view(), 300, 1e-8) {
-        Ok((iters, error)) => {
-            println!(
-                "Solved system in {} iterations with residual error {}",
-                iters, error
-            );
-            let grid = x.view().into_shape((rows, cols)).unwrap();
-            for i in 0..rows {
-                for j in 0..cols {
-                    print!("{} ", grid[[i, j]]);
-                }
-                println!("");
+    let gauss_seidel = Relaxation::gauss_seidel(SweepDirection::Forward);
+    let criteria = StoppingCriteria::new(1e-8, 300);
+    let report = stationary(
+        lap.view(),
+        rhs.view(),
+        x.view_mut(),
+        &gauss_seidel,
+        &criteria,
+    );
+    if report.converged() {
+        println!(
+            "Solved system in {} iterations with residual error {}",
+            report.iterations(),
+            report.residual_norm()
+        );
+        let grid = x.view().into_shape((rows, cols)).unwrap();
+        for i in 0..rows {
+            for j in 0..cols {
+                print!("{} ", grid[[i, j]]);
             }
+            println!("");
         }
-        Err(error) => {
-            println!("Solving the system failed to converge fast enough");
-            println!("Residual error was {}", error);
-        }
+    } else {
+        println!("Solving the system failed to converge fast enough");
+        println!("Residual error was {}", report.residual_norm());
     }
 }
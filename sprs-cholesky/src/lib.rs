@@ -0,0 +1,755 @@
+extern crate ndarray;
+extern crate num_traits;
+///! Supernodal sparse Cholesky factorization module.
+///!
+///! Contains a multifrontal Cholesky factorization `L L^T = P A P^T` of
+///! symmetric positive definite matrices, where `L` is lower triangular and
+///! `P` is a fill-reducing permutation.
+///!
+///! The easiest way to use this API is to create a `CholeskyNumeric`
+///! instance from a matrix, then use the `CholeskyNumeric::solve` method.
+///! As with `sprs-ldl`, a decomposition can be updated with a matrix having
+///! the same sparsity structure using `CholeskyNumeric::update`, and the
+///! symbolic analysis, held by `CholeskySymbolic`, can be computed once the
+///! sparsity structure is known.
+///!
+///! The columns of `L` are grouped into supernodes, sets of contiguous
+///! columns sharing the same non-zero structure below their diagonal block,
+///! which are detected from the elimination tree and the column counts of
+///! `L`. Each supernode is factored as a dense block of an ndarray frontal
+///! matrix, and the updates to the rest of the matrix are computed by dense
+///! matrix products, which is much faster than a column by column
+///! factorization when the factor has large dense blocks, as for 3D
+///! problems.
+///!
+///! The elimination tree is renumbered in postorder, which does not change
+///! the fill-in of the factor but makes the columns of each supernode
+///! contiguous.
+///!
+///! Reference: J. W. H. Liu, The multifrontal method for sparse matrix
+///! solution: theory and practice, SIAM Review 34(1), 82-109 (1992).
+extern crate sprs;
+
+use std::cmp;
+use std::ops::Deref;
+
+use ndarray::{
+    Array2, ArrayView1, ArrayView2, ArrayViewMut1, ArrayViewMut2, Axis, Slice,
+};
+use num_traits::Float;
+
+use sprs::errors::SprsError;
+use sprs::indexing::SpIndex;
+use sprs::linalg::etree::{symmetric_etree, CholeskyCounts};
+use sprs::linalg::iterative::Preconditioner;
+use sprs::{
+    is_symmetric, transform_mat_papt, CsMatI, CsMatViewI, PermOwnedI,
+    PermViewI, Permutation,
+};
+
+/// Structure to hold the symbolic analysis of a supernodal Cholesky
+/// decomposition: the ordering, the supernodes and their non-zero
+/// structure.
+#[derive(Debug, Clone)]
+pub struct CholeskySymbolic<I> {
+    perm: PermOwnedI<I>,
+    /// the first column of each supernode, followed by the dimension
+    sn_start: Vec<usize>,
+    /// the rows of each supernode, in `sn_rows[sn_rows_ptr[s]..]`, starting
+    /// with the columns of the supernode
+    sn_rows_ptr: Vec<usize>,
+    sn_rows: Vec<usize>,
+    /// the supernodes whose parent in the supernodal tree is each supernode
+    sn_children: Vec<Vec<usize>>,
+}
+
+/// Structure to hold a numeric supernodal Cholesky decomposition
+#[derive(Debug, Clone)]
+pub struct CholeskyNumeric<N, I> {
+    symbolic: CholeskySymbolic<I>,
+    /// the columns of `L` belonging to each supernode, as a dense block
+    /// whose rows are the rows of the supernode
+    blocks: Vec<Array2<N>>,
+}
+
+impl<I: SpIndex> CholeskySymbolic<I> {
+    /// Compute the symbolic Cholesky decomposition of the given matrix
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    pub fn new<N>(mat: CsMatViewI<N, I>) -> CholeskySymbolic<I>
+    where
+        N: Copy,
+    {
+        let perm = Permutation::identity(mat.rows());
+        CholeskySymbolic::new_perm(mat, perm)
+    }
+
+    /// Compute the symbolic decomposition `L L^T = P A P^T` where `P` is
+    /// the given permutation, followed by a postorder of the elimination
+    /// tree of the permuted matrix.
+    ///
+    /// Only the non-zero pattern of the matrix is used, which should be
+    /// symmetric.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    /// * if the dimension of `perm` does not match the matrix
+    pub fn new_perm<N>(
+        mat: CsMatViewI<N, I>,
+        perm: PermOwnedI<I>,
+    ) -> CholeskySymbolic<I>
+    where
+        N: Copy,
+    {
+        let n = mat.rows();
+        assert!(mat.cols() == n, "matrix should be square");
+        assert!(perm.dim() == n, "Dimension mismatch");
+        let permuted = transform_mat_papt(mat.view(), perm.view());
+        let postorder = symmetric_etree(permuted.view()).postorder();
+        let perm = Permutation::new(
+            postorder
+                .iter()
+                .map(|&k| I::from_usize(perm.at(k)))
+                .collect(),
+        );
+        // the natural order is a postorder of the renumbered tree
+        let mat = transform_mat_papt(mat.view(), perm.view());
+        let parents = symmetric_etree(mat.view());
+        let natural_order: Vec<usize> = (0..n).collect();
+        let counts = CholeskyCounts::new(mat.view(), &parents, &natural_order);
+        let col_counts = counts.col_counts();
+
+        // fundamental supernodes: a column joins the supernode of the
+        // previous one if it is its only child and has the same structure
+        let mut nb_children = vec![0; n];
+        for j in 0..n {
+            if let Some(parent) = parents.get_parent(j) {
+                nb_children[parent] += 1;
+            }
+        }
+        let mut sn_start = vec![0];
+        for j in 1..n {
+            let merge = parents.get_parent(j - 1) == Some(j)
+                && nb_children[j] == 1
+                && col_counts[j - 1] == col_counts[j] + 1;
+            if !merge {
+                sn_start.push(j);
+            }
+        }
+        if n > 0 {
+            sn_start.push(n);
+        }
+        let nb_supernodes = sn_start.len() - 1;
+        let mut supernode_of = vec![0; n];
+        for s in 0..nb_supernodes {
+            for j in sn_start[s]..sn_start[s + 1] {
+                supernode_of[j] = s;
+            }
+        }
+        let mut sn_children = vec![Vec::new(); nb_supernodes];
+        for s in 0..nb_supernodes {
+            let last = sn_start[s + 1] - 1;
+            if let Some(parent) = parents.get_parent(last) {
+                sn_children[supernode_of[parent]].push(s);
+            }
+        }
+
+        // the structure of a supernode is the union of the structure of
+        // its columns in A and of the structures of its children
+        let mut sn_rows_ptr = Vec::with_capacity(nb_supernodes + 1);
+        let mut sn_rows = Vec::with_capacity(counts.nnz());
+        sn_rows_ptr.push(0);
+        let mut marks: Vec<Option<usize>> = vec![None; n];
+        for s in 0..nb_supernodes {
+            let (first, last) = (sn_start[s], sn_start[s + 1]);
+            let start = sn_rows.len();
+            for j in first..last {
+                marks[j] = Some(s);
+                sn_rows.push(j);
+            }
+            for j in first..last {
+                let col = mat.outer_view(j).expect("column in bounds");
+                for &ind in col.indices() {
+                    let i = ind.index();
+                    if i >= last && marks[i] != Some(s) {
+                        marks[i] = Some(s);
+                        sn_rows.push(i);
+                    }
+                }
+            }
+            for &child in &sn_children[s] {
+                let child_cols = sn_start[child + 1] - sn_start[child];
+                let child_start = sn_rows_ptr[child] + child_cols;
+                for k in child_start..sn_rows_ptr[child + 1] {
+                    let i = sn_rows[k];
+                    if marks[i] != Some(s) {
+                        marks[i] = Some(s);
+                        sn_rows.push(i);
+                    }
+                }
+            }
+            sn_rows[start..].sort();
+            debug_assert_eq!(sn_rows.len() - start, col_counts[first]);
+            sn_rows_ptr.push(sn_rows.len());
+        }
+
+        CholeskySymbolic {
+            perm: perm,
+            sn_start: sn_start,
+            sn_rows_ptr: sn_rows_ptr,
+            sn_rows: sn_rows,
+            sn_children: sn_children,
+        }
+    }
+
+    /// The size of the linear system associated with this decomposition
+    #[inline]
+    pub fn problem_size(&self) -> usize {
+        self.perm.dim()
+    }
+
+    /// The number of non-zero entries in L, including its diagonal
+    pub fn nnz(&self) -> usize {
+        (0..self.nb_supernodes())
+            .map(|s| {
+                let ncols = self.sn_start[s + 1] - self.sn_start[s];
+                let nrows = self.sn_rows_ptr[s + 1] - self.sn_rows_ptr[s];
+                ncols * nrows - ncols * (ncols - 1) / 2
+            })
+            .sum()
+    }
+
+    /// The number of supernodes of the decomposition
+    pub fn nb_supernodes(&self) -> usize {
+        self.sn_start.len().saturating_sub(1)
+    }
+
+    /// The permutation `P` of the decomposition `L L^T = P A P^T`: the
+    /// `k`-th row and column of `P A P^T` are the row and column
+    /// `perm().at(k)` of `A`.
+    pub fn perm(&self) -> PermViewI<I> {
+        self.perm.view()
+    }
+
+    /// The columns of a supernode, and its rows
+    fn supernode(&self, s: usize) -> (usize, usize, &[usize]) {
+        let rows = &self.sn_rows[self.sn_rows_ptr[s]..self.sn_rows_ptr[s + 1]];
+        (self.sn_start[s], self.sn_start[s + 1], rows)
+    }
+
+    /// Compute the numerical decomposition of the given matrix.
+    ///
+    /// # Errors
+    ///
+    /// * `NonSymmetricMatrix` if the matrix is not symmetric
+    /// * `NotPositiveDefinite` if the matrix is not positive definite
+    ///
+    /// # Panics
+    ///
+    /// * if the non-zero pattern of the matrix is not included in the one
+    ///   of the symbolic analysis
+    pub fn factor<N>(
+        self,
+        mat: CsMatViewI<N, I>,
+    ) -> Result<CholeskyNumeric<N, I>, SprsError>
+    where
+        N: 'static + Float,
+    {
+        let mut numeric = CholeskyNumeric {
+            symbolic: self,
+            blocks: Vec::new(),
+        };
+        numeric.update(mat)?;
+        Ok(numeric)
+    }
+}
+
+impl<N, I> CholeskyNumeric<N, I>
+where
+    N: 'static + Float,
+    I: SpIndex,
+{
+    /// Compute the Cholesky decomposition of the given matrix.
+    ///
+    /// # Errors
+    ///
+    /// * `NonSymmetricMatrix` if the matrix is not symmetric
+    /// * `NotPositiveDefinite` if the matrix is not positive definite
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    pub fn new(mat: CsMatViewI<N, I>) -> Result<Self, SprsError> {
+        let symbolic = CholeskySymbolic::new(mat.view());
+        symbolic.factor(mat)
+    }
+
+    /// Compute the Cholesky decomposition `L L^T = P A P^T` where `P` is
+    /// the given permutation, followed by a postorder of the elimination
+    /// tree.
+    ///
+    /// Using a good permutation matrix can reduce the non-zero count in L,
+    /// thus making the decomposition and the solves faster.
+    ///
+    /// # Errors
+    ///
+    /// * `NonSymmetricMatrix` if the matrix is not symmetric
+    /// * `NotPositiveDefinite` if the matrix is not positive definite
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    /// * if the dimension of `perm` does not match the matrix
+    pub fn new_perm(
+        mat: CsMatViewI<N, I>,
+        perm: PermOwnedI<I>,
+    ) -> Result<Self, SprsError> {
+        let symbolic = CholeskySymbolic::new_perm(mat.view(), perm);
+        symbolic.factor(mat)
+    }
+
+    /// Update the decomposition with the given matrix. The matrix must
+    /// have the same non-zero pattern as the original matrix.
+    ///
+    /// # Errors
+    ///
+    /// * `NonSymmetricMatrix` if the matrix is not symmetric
+    /// * `NotPositiveDefinite` if the matrix is not positive definite
+    ///
+    /// # Panics
+    ///
+    /// * if the dimension of the matrix does not match the decomposition
+    /// * if the non-zero pattern of the matrix is not included in the one
+    ///   of the symbolic analysis
+    pub fn update(&mut self, mat: CsMatViewI<N, I>) -> Result<(), SprsError> {
+        let n = self.problem_size();
+        assert!(mat.rows() == n && mat.cols() == n, "Dimension mismatch");
+        if !is_symmetric(&mat) {
+            return Err(SprsError::NonSymmetricMatrix);
+        }
+        let symbolic = &self.symbolic;
+        let mat = transform_mat_papt(mat, symbolic.perm.view());
+        let nb_supernodes = symbolic.nb_supernodes();
+        // the position of each row in the current frontal matrix
+        let mut positions = vec![0; n];
+        // the contribution of each supernode to the frontal matrix of its
+        // parent, waiting to be assembled
+        let mut contributions: Vec<Option<Array2<N>>> =
+            (0..nb_supernodes).map(|_| None).collect();
+        let mut blocks = Vec::with_capacity(nb_supernodes);
+        for s in 0..nb_supernodes {
+            let (first, last, rows) = symbolic.supernode(s);
+            let ncols = last - first;
+            let nrows = rows.len();
+            for (pos, &row) in rows.iter().enumerate() {
+                positions[row] = pos;
+            }
+            // the lower triangle of the frontal matrix
+            let mut front = Array2::zeros((nrows, nrows));
+            for j in first..last {
+                let col = mat.outer_view(j).expect("column in bounds");
+                for (i, &val) in col.iter().filter(|&(i, _)| i >= j) {
+                    let pos = positions[i];
+                    assert!(
+                        pos < nrows && rows[pos] == i,
+                        "the non-zero pattern differs from the analysis"
+                    );
+                    front[[pos, j - first]] = front[[pos, j - first]] + val;
+                }
+            }
+            for &child in &symbolic.sn_children[s] {
+                let contribution = match contributions[child].take() {
+                    Some(contribution) => contribution,
+                    None => continue,
+                };
+                let (child_first, child_last, child_rows) =
+                    symbolic.supernode(child);
+                let child_rows = &child_rows[child_last - child_first..];
+                for (a, &row_a) in child_rows.iter().enumerate() {
+                    for (b, &row_b) in child_rows.iter().enumerate().take(a + 1)
+                    {
+                        let (pa, pb) = (positions[row_a], positions[row_b]);
+                        front[[pa, pb]] =
+                            front[[pa, pb]] + contribution[[a, b]];
+                    }
+                }
+            }
+            factor_front(&mut front, ncols)?;
+            if nrows > ncols {
+                let lower =
+                    front.slice_axis(Axis(0), Slice::from(ncols..nrows));
+                let l21 = lower.slice_axis(Axis(1), Slice::from(0..ncols));
+                let schur = lower
+                    .slice_axis(Axis(1), Slice::from(ncols..nrows))
+                    .to_owned();
+                contributions[s] = Some(schur - &l21.dot(&l21.t()));
+            }
+            blocks.push(
+                front.slice_axis(Axis(1), Slice::from(0..ncols)).to_owned(),
+            );
+        }
+        self.blocks = blocks;
+        Ok(())
+    }
+
+    /// Solve the system A x = rhs
+    ///
+    /// # Panics
+    ///
+    /// * if the dimension of rhs does not match the decomposition
+    pub fn solve<'a, V>(&self, rhs: &V) -> Vec<N>
+    where
+        N: 'a,
+        V: Deref<Target = [N]>,
+    {
+        let symbolic = &self.symbolic;
+        let mut x = &symbolic.perm * &rhs[..];
+        for (s, block) in self.blocks.iter().enumerate() {
+            let (first, last, rows) = symbolic.supernode(s);
+            for jj in 0..last - first {
+                let j = first + jj;
+                x[j] = x[j] / block[[jj, jj]];
+                let xj = x[j];
+                for ii in jj + 1..rows.len() {
+                    x[rows[ii]] = x[rows[ii]] - block[[ii, jj]] * xj;
+                }
+            }
+        }
+        for (s, block) in self.blocks.iter().enumerate().rev() {
+            let (first, last, rows) = symbolic.supernode(s);
+            for jj in (0..last - first).rev() {
+                let j = first + jj;
+                let mut val = x[j];
+                for ii in jj + 1..rows.len() {
+                    val = val - block[[ii, jj]] * x[rows[ii]];
+                }
+                x[j] = val / block[[jj, jj]];
+            }
+        }
+        let pinv = symbolic.perm.inv();
+        &pinv * &x
+    }
+
+    /// The Cholesky factor L of `P A P^T`, in CSC format
+    pub fn l(&self) -> CsMatI<N, I> {
+        let symbolic = &self.symbolic;
+        let n = self.problem_size();
+        let mut indptr = Vec::with_capacity(n + 1);
+        let mut indices = Vec::with_capacity(self.nnz());
+        let mut data = Vec::with_capacity(self.nnz());
+        indptr.push(I::zero());
+        for (s, block) in self.blocks.iter().enumerate() {
+            let (first, last, rows) = symbolic.supernode(s);
+            for jj in 0..last - first {
+                for ii in jj..rows.len() {
+                    indices.push(I::from_usize(rows[ii]));
+                    data.push(block[[ii, jj]]);
+                }
+                indptr.push(I::from_usize(indices.len()));
+            }
+        }
+        CsMatI::new_csc((n, n), indptr, indices, data)
+    }
+
+    /// The permutation `P` of the decomposition `L L^T = P A P^T`
+    pub fn perm(&self) -> PermViewI<I> {
+        self.symbolic.perm()
+    }
+
+    /// The size of the linear system associated with this decomposition
+    #[inline]
+    pub fn problem_size(&self) -> usize {
+        self.symbolic.problem_size()
+    }
+
+    /// The number of non-zero entries in L, including its diagonal
+    #[inline]
+    pub fn nnz(&self) -> usize {
+        self.symbolic.nnz()
+    }
+
+    /// The number of supernodes of the decomposition
+    pub fn nb_supernodes(&self) -> usize {
+        self.symbolic.nb_supernodes()
+    }
+}
+
+impl<N, I> Preconditioner<N> for CholeskyNumeric<N, I>
+where
+    N: 'static + Float,
+    I: SpIndex,
+{
+    fn apply(&self, rhs: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        assert_eq!(out.len(), self.problem_size(), "Dimension mismatch");
+        let x = self.solve(&rhs.to_vec());
+        for (o, xi) in out.iter_mut().zip(x) {
+            *o = xi;
+        }
+    }
+}
+
+/// The number of columns of a frontal matrix factored at once
+const BLOCK_SIZE: usize = 32;
+
+/// Factor the first `ncols` columns of the lower triangle of a frontal
+/// matrix in place, leaving the columns of `L` in the first columns.
+///
+/// The columns are factored by blocks of `BLOCK_SIZE`: the diagonal block
+/// is factored, the rows of `L` below it are computed by a triangular
+/// solve, and the remaining columns of the block are updated by a matrix
+/// product.
+fn factor_front<N: 'static + Float>(
+    front: &mut Array2<N>,
+    ncols: usize,
+) -> Result<(), SprsError> {
+    let nrows = front.rows();
+    let mut start = 0;
+    while start < ncols {
+        let end = cmp::min(start + BLOCK_SIZE, ncols);
+        {
+            let mut panel =
+                front.slice_axis_mut(Axis(1), Slice::from(start..end));
+            let (mut diag, below) = panel.view_mut().split_at(Axis(0), end);
+            diag.slice_axis_inplace(Axis(0), Slice::from(start..end));
+            factor_diagonal_block(diag.view_mut())?;
+            solve_lower_transpose(diag.view(), below);
+        }
+        if end < ncols {
+            let update = {
+                let panel = front.slice_axis(Axis(1), Slice::from(start..end));
+                let below = panel.slice_axis(Axis(0), Slice::from(end..nrows));
+                let pivots = panel.slice_axis(Axis(0), Slice::from(end..ncols));
+                below.dot(&pivots.t())
+            };
+            let mut trailing =
+                front.slice_axis_mut(Axis(0), Slice::from(end..nrows));
+            trailing.slice_axis_inplace(Axis(1), Slice::from(end..ncols));
+            trailing.zip_mut_with(&update, |t, &u| *t = *t - u);
+        }
+        start = end;
+    }
+    // the updates also reached the upper triangle of the pivot block
+    for j in 1..ncols {
+        for i in 0..j {
+            front[[i, j]] = N::zero();
+        }
+    }
+    Ok(())
+}
+
+/// Dense Cholesky factorization of the lower triangle of a diagonal block,
+/// in place.
+fn factor_diagonal_block<N: Float>(
+    mut block: ArrayViewMut2<N>,
+) -> Result<(), SprsError> {
+    let n = block.rows();
+    for j in 0..n {
+        let pivot = block[[j, j]];
+        if !(pivot > N::zero()) {
+            return Err(SprsError::NotPositiveDefinite);
+        }
+        let pivot = pivot.sqrt();
+        block[[j, j]] = pivot;
+        for i in j + 1..n {
+            block[[i, j]] = block[[i, j]] / pivot;
+        }
+        for k in j + 1..n {
+            let l_kj = block[[k, j]];
+            for i in k..n {
+                block[[i, k]] = block[[i, k]] - block[[i, j]] * l_kj;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Solve `X L^T = B` in place, where `L` is lower triangular.
+fn solve_lower_transpose<N: 'static + Float>(
+    lower: ArrayView2<N>,
+    mut rhs: ArrayViewMut2<N>,
+) {
+    for j in 0..lower.rows() {
+        let (solved, mut rest) = rhs.view_mut().split_at(Axis(1), j);
+        let row = lower.row(j);
+        let row = row.slice_axis(Axis(0), Slice::from(0..j));
+        let update = solved.dot(&row);
+        let pivot = lower[[j, j]];
+        rest.column_mut(0)
+            .zip_mut_with(&update, |x, &u| *x = (*x - u) / pivot);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CholeskyNumeric, CholeskySymbolic};
+    use sprs::errors::SprsError;
+    use sprs::linalg::etree::{symmetric_etree, CholeskyCounts};
+    use sprs::linalg::ordering::reverse_cuthill_mckee;
+    use sprs::{grid_laplacian, transform_mat_papt, CsMat, TriMat};
+
+    /// 7-point laplacian on a cubic grid, in CSR format
+    fn laplacian_3d(size: usize) -> CsMat<f64> {
+        grid_laplacian(&[size, size, size])
+    }
+
+    /// Check that L L^T = P A P^T
+    fn check_factor(mat: &CsMat<f64>, chol: &CholeskyNumeric<f64, usize>) {
+        let l = chol.l();
+        assert_eq!(l.nnz(), chol.nnz());
+        let llt = &l * &l.transpose_view();
+        let papt = transform_mat_papt(mat.view(), chol.perm());
+        let diff = &llt.to_csr() - &papt.to_csr();
+        assert!(diff.data().iter().all(|d| d.abs() < 1e-12));
+    }
+
+    #[test]
+    fn cholesky_small() {
+        // the arrow matrix has a dense last row
+        let n = 6;
+        let mut tri = TriMat::new((n, n));
+        for i in 0..n - 1 {
+            tri.add_triplet(i, i, 4. + i as f64);
+            tri.add_triplet(i, n - 1, 1.);
+            tri.add_triplet(n - 1, i, 1.);
+        }
+        tri.add_triplet(n - 1, n - 1, 10.);
+        let mat = tri.to_csc();
+        let chol = CholeskyNumeric::new(mat.view()).unwrap();
+        check_factor(&mat, &chol);
+        assert_eq!(chol.nnz(), 2 * n - 1);
+        let x0 = vec![1., -2., 3., 0.5, 0., 1.5];
+        let b = &mat * &::ndarray::Array::from_vec(x0.clone());
+        let x = chol.solve(&b.to_vec());
+        for (xi, x0i) in x.iter().zip(&x0) {
+            assert!((xi - x0i).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn cholesky_laplacian_3d() {
+        let mat = laplacian_3d(6);
+        let n = mat.rows();
+        let perm = reverse_cuthill_mckee(mat.view());
+        let mut chol = CholeskyNumeric::new_perm(mat.view(), perm).unwrap();
+        check_factor(&mat, &chol);
+        // the structure of L is the one predicted by the elimination tree
+        let papt = transform_mat_papt(mat.view(), chol.perm());
+        let parents = symmetric_etree(papt.view());
+        let postorder = parents.postorder();
+        let counts = CholeskyCounts::new(papt.view(), &parents, &postorder);
+        assert_eq!(chol.nnz(), counts.nnz());
+        assert!(chol.nb_supernodes() < n);
+        let x0: Vec<f64> = (0..n).map(|i| (i as f64 * 0.37).sin()).collect();
+        let b = &mat * &::ndarray::Array::from_vec(x0.clone());
+        let x = chol.solve(&b.to_vec());
+        for (xi, x0i) in x.iter().zip(&x0) {
+            assert!((xi - x0i).abs() < 1e-10);
+        }
+
+        // the same pattern with scaled values
+        let mut scaled = mat.to_owned();
+        scaled.map_inplace(|&v| 2. * v);
+        chol.update(scaled.view()).unwrap();
+        check_factor(&scaled, &chol);
+        let x = chol.solve(&b.to_vec());
+        for (xi, x0i) in x.iter().zip(&x0) {
+            assert!((2. * xi - x0i).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn cholesky_large_supernodes() {
+        // two dense blocks coupled by a dense separator, which gives
+        // supernodes larger than the blocks used to factor them
+        let (m, sep) = (40, 5);
+        let n = 2 * m + sep;
+        let mut tri = TriMat::new((n, n));
+        for i in 0..n {
+            for j in 0..n {
+                let same_block = i / m == j / m;
+                if i == j {
+                    tri.add_triplet(i, j, n as f64);
+                } else if same_block || i >= 2 * m || j >= 2 * m {
+                    let dist = (i as f64 - j as f64).abs();
+                    tri.add_triplet(i, j, 1. / (1. + dist));
+                }
+            }
+        }
+        let mat = tri.to_csc();
+        let chol = CholeskyNumeric::new(mat.view()).unwrap();
+        assert_eq!(chol.nb_supernodes(), 3);
+        check_factor(&mat, &chol);
+        let x0: Vec<f64> = (0..n).map(|i| (i as f64 * 0.21).cos()).collect();
+        let b = &mat * &::ndarray::Array::from_vec(x0.clone());
+        let x = chol.solve(&b.to_vec());
+        for (xi, x0i) in x.iter().zip(&x0) {
+            assert!((xi - x0i).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "the non-zero pattern differs from the analysis")]
+    fn update_pattern_mismatch() {
+        // the first supernode has the rows 0, 1, 2 and 5, and the column 3
+        // the rows 3 and 4, so that the position of the row 5 in the first
+        // frontal matrix exceeds the dimension of the frontal matrix of
+        // the column 3
+        let n = 6;
+        let mut tri = TriMat::new((n, n));
+        for i in 0..n {
+            tri.add_triplet(i, i, 10.);
+        }
+        for &i in &[0, 1, 2, 5] {
+            for &j in &[0, 1, 2, 5] {
+                if i != j {
+                    tri.add_triplet(i, j, 1.);
+                }
+            }
+        }
+        for &(i, j) in &[(3, 4), (4, 5)] {
+            tri.add_triplet(i, j, 1.);
+            tri.add_triplet(j, i, 1.);
+        }
+        let mat: CsMat<f64> = tri.to_csr();
+        let mut chol = CholeskyNumeric::new(mat.view()).unwrap();
+        tri.add_triplet(3, 5, 1.);
+        tri.add_triplet(5, 3, 1.);
+        let other: CsMat<f64> = tri.to_csr();
+        let _ = chol.update(other.view());
+    }
+
+    #[test]
+    fn symbolic_reuse() {
+        let mat = laplacian_3d(4);
+        let symbolic = CholeskySymbolic::new(mat.view());
+        assert_eq!(symbolic.problem_size(), mat.rows());
+        let nnz = symbolic.nnz();
+        let chol = symbolic.clone().factor(mat.view()).unwrap();
+        assert_eq!(chol.nnz(), nnz);
+        check_factor(&mat, &chol);
+        let empty: CsMat<f64> = CsMat::zero((0, 0));
+        let chol = CholeskyNumeric::new(empty.view()).unwrap();
+        assert_eq!(chol.nb_supernodes(), 0);
+        assert!(chol.solve(&Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn cholesky_errors() {
+        let mat = laplacian_3d(3);
+        let mut indefinite = mat.to_owned();
+        indefinite.map_inplace(|&v| if v > 0. { 1. } else { v });
+        let res = CholeskyNumeric::new(indefinite.view());
+        assert_eq!(res.unwrap_err(), SprsError::NotPositiveDefinite);
+
+        let mut tri = TriMat::new((2, 2));
+        tri.add_triplet(0, 0, 2.);
+        tri.add_triplet(0, 1, 1.);
+        tri.add_triplet(1, 1, 2.);
+        let non_symmetric: CsMat<f64> = tri.to_csr();
+        let res = CholeskyNumeric::new(non_symmetric.view());
+        assert_eq!(res.unwrap_err(), SprsError::NonSymmetricMatrix);
+    }
+}
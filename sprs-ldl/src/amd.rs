@@ -0,0 +1,558 @@
+///! Approximate Minimum Degree ordering.
+///!
+///! The AMD algorithm computes a fill-reducing ordering for the Cholesky
+///! factorization of a symmetric matrix, by greedily eliminating the node of
+///! the graph of the matrix with the smallest approximate degree. The
+///! elimination is simulated on a quotient graph, which never requires more
+///! memory than the graph of the matrix.
+// This method is adapted from the CSparse library by Tim Davis:
+//
+// CSparse Copyright (c) 2006, Timothy A. Davis.  All Rights Reserved.
+//
+// CSparse License:
+//
+//     CSparse is free software; you can redistribute it and/or
+//     modify it under the terms of the GNU Lesser General Public
+//     License as published by the Free Software Foundation; either
+//     version 2.1 of the License, or (at your option) any later version.
+//
+//     CSparse is distributed in the hope that it will be useful,
+//     but WITHOUT ANY WARRANTY; without even the implied warranty of
+//     MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+//     Lesser General Public License for more details.
+//
+//     You should have received a copy of the GNU Lesser General Public
+//     License along with this Module; if not, write to the Free Software
+//     Foundation, Inc., 51 Franklin St, Fifth Floor, Boston, MA  02110-1301
+//     USA
+use std::cmp;
+
+use sprs::indexing::SpIndex;
+use sprs::{CsMatViewI, PermOwnedI, Permutation};
+
+/// Encode a node or element index as a negative value, used to mark
+/// absorbed objects and to store their parent in the assembly tree.
+#[inline]
+fn flip(i: isize) -> isize {
+    -i - 2
+}
+
+/// Reset the flag workspace if the marker would overflow.
+fn clear_flags(mark: isize, lemax: isize, w: &mut [isize]) -> isize {
+    if mark < 2 || mark.checked_add(lemax).is_none() {
+        for wk in w.iter_mut() {
+            if *wk != 0 {
+                *wk = 1;
+            }
+        }
+        2
+    } else {
+        mark
+    }
+}
+
+/// Build the adjacency structure of `A + A^T`, without the diagonal, in
+/// compressed form. The returned index storage has some elbow room to hold
+/// the quotient graph.
+fn symmetric_graph<N, I>(mat: CsMatViewI<N, I>) -> (Vec<isize>, Vec<isize>)
+where
+    I: SpIndex,
+{
+    let n = mat.rows();
+    let mut adjacency = vec![Vec::new(); n];
+    for (outer, vec) in mat.outer_iterator().enumerate() {
+        for (inner, _) in vec.iter() {
+            if inner != outer {
+                adjacency[outer].push(inner as isize);
+                adjacency[inner].push(outer as isize);
+            }
+        }
+    }
+    let mut ptr = Vec::with_capacity(n + 1);
+    let mut indices = Vec::new();
+    ptr.push(0);
+    for neighbours in &mut adjacency {
+        neighbours.sort();
+        neighbours.dedup();
+        indices.extend_from_slice(neighbours);
+        ptr.push(indices.len() as isize);
+    }
+    let nnz = indices.len();
+    indices.resize(nnz + nnz / 5 + 2 * n, 0);
+    (ptr, indices)
+}
+
+/// Compute an Approximate Minimum Degree ordering of the given symmetric
+/// matrix.
+///
+/// Only the non-zero pattern of the matrix is used, and the pattern of
+/// `A + A^T` is considered if the matrix is not structurally symmetric.
+/// Nodes with a very high degree are considered dense and are ordered last.
+///
+/// The returned permutation `P` is such that factoring `P A P^T` should
+/// produce less fill-in than factoring `A`, and can be passed directly to
+/// `LdlSymbolic::new_perm` or `LdlNumeric::new_perm`.
+///
+/// # Panics
+///
+/// * if mat is not square
+pub fn amd<N, I>(mat: CsMatViewI<N, I>) -> PermOwnedI<I>
+where
+    I: SpIndex,
+{
+    let n = mat.rows();
+    assert!(mat.cols() == n, "matrix should be square");
+    if n == 0 {
+        return Permutation::new(Vec::new());
+    }
+    let ni = n as isize;
+    let dense =
+        cmp::min(ni - 2, cmp::max(16, (10. * (n as f64).sqrt()) as isize));
+    let (mut cp, mut ci) = symmetric_graph(mat);
+    let mut cnz = cp[n];
+    let nzmax = ci.len() as isize;
+
+    let mut len = vec![0isize; n + 1];
+    let mut nv = vec![1isize; n + 1];
+    let mut next = vec![-1isize; n + 1];
+    let mut head = vec![-1isize; n + 1];
+    let mut elen = vec![0isize; n + 1];
+    let mut degree = vec![0isize; n + 1];
+    let mut w = vec![1isize; n + 1];
+    let mut hhead = vec![-1isize; n + 1];
+    let mut last = vec![-1isize; n + 1];
+
+    // --- Initialize quotient graph
+    for k in 0..n {
+        len[k] = cp[k + 1] - cp[k];
+        degree[k] = len[k];
+    }
+    let mut lemax = 0;
+    let mut mark = clear_flags(0, 0, &mut w[..n]);
+    let mut nel = 0;
+    let mut mindeg = 0;
+    // node n is a placeholder element absorbing the dense nodes
+    elen[n] = -2;
+    cp[n] = -1;
+    w[n] = 0;
+
+    // --- Initialize degree lists
+    for i in 0..n {
+        let d = degree[i];
+        if d == 0 {
+            // empty node, eliminated right away
+            elen[i] = -2;
+            nel += 1;
+            cp[i] = -1;
+            w[i] = 0;
+        } else if d > dense {
+            // dense node, absorbed into element n
+            nv[i] = 0;
+            elen[i] = -1;
+            nel += 1;
+            cp[i] = flip(ni);
+            nv[n] += 1;
+        } else {
+            let d = d as usize;
+            if head[d] != -1 {
+                last[head[d] as usize] = i as isize;
+            }
+            next[i] = head[d];
+            head[d] = i as isize;
+        }
+    }
+
+    while nel < n {
+        // --- Select node of minimum approximate degree
+        let mut k = -1;
+        while mindeg < n {
+            k = head[mindeg];
+            if k != -1 {
+                break;
+            }
+            mindeg += 1;
+        }
+        let ku = k as usize;
+        if next[ku] != -1 {
+            last[next[ku] as usize] = -1;
+        }
+        head[mindeg] = next[ku];
+        let elenk = elen[ku];
+        let mut nvk = nv[ku];
+        nel += nvk as usize;
+
+        // --- Garbage collection
+        if elenk > 0 && cnz + mindeg as isize >= nzmax {
+            for j in 0..n {
+                let p = cp[j];
+                if p >= 0 {
+                    cp[j] = ci[p as usize];
+                    ci[p as usize] = flip(j as isize);
+                }
+            }
+            let mut q = 0;
+            let mut p = 0;
+            while p < cnz as usize {
+                let j = flip(ci[p]);
+                p += 1;
+                if j >= 0 {
+                    let j = j as usize;
+                    ci[q] = cp[j];
+                    cp[j] = q as isize;
+                    q += 1;
+                    for _ in 0..(len[j] - 1) {
+                        ci[q] = ci[p];
+                        q += 1;
+                        p += 1;
+                    }
+                }
+            }
+            cnz = q as isize;
+        }
+
+        // --- Construct new element
+        let mut dk = 0;
+        nv[ku] = -nvk;
+        let mut p = cp[ku] as usize;
+        let pk1 = if elenk == 0 { p } else { cnz as usize };
+        let mut pk2 = pk1;
+        for k1 in 1..(elenk + 2) {
+            let (e, mut pj, ln) = if k1 > elenk {
+                // search the nodes in k
+                (k, p, len[ku] - elenk)
+            } else {
+                // search the nodes in e
+                let e = ci[p];
+                p += 1;
+                (e, cp[e as usize] as usize, len[e as usize])
+            };
+            for _ in 0..ln {
+                let i = ci[pj] as usize;
+                pj += 1;
+                let nvi = nv[i];
+                if nvi <= 0 {
+                    // node i dead, or already seen
+                    continue;
+                }
+                dk += nvi;
+                nv[i] = -nvi;
+                ci[pk2] = i as isize;
+                pk2 += 1;
+                // remove i from degree list
+                if next[i] != -1 {
+                    last[next[i] as usize] = last[i];
+                }
+                if last[i] != -1 {
+                    next[last[i] as usize] = next[i];
+                } else {
+                    head[degree[i] as usize] = next[i];
+                }
+            }
+            if e != k {
+                // absorb e into k
+                cp[e as usize] = flip(k);
+                w[e as usize] = 0;
+            }
+        }
+        if elenk != 0 {
+            cnz = pk2 as isize;
+        }
+        degree[ku] = dk;
+        cp[ku] = pk1 as isize;
+        len[ku] = (pk2 - pk1) as isize;
+        elen[ku] = -2;
+
+        // --- Find set differences
+        mark = clear_flags(mark, lemax, &mut w[..n]);
+        for pk in pk1..pk2 {
+            let i = ci[pk] as usize;
+            let eln = elen[i];
+            if eln <= 0 {
+                continue;
+            }
+            let nvi = -nv[i];
+            let wnvi = mark - nvi;
+            let start = cp[i] as usize;
+            for p in start..(start + eln as usize) {
+                let e = ci[p] as usize;
+                if w[e] >= mark {
+                    w[e] -= nvi;
+                } else if w[e] != 0 {
+                    // first time e is seen in this scan
+                    w[e] = degree[e] + wnvi;
+                }
+            }
+        }
+
+        // --- Degree update
+        for pk in pk1..pk2 {
+            let i = ci[pk] as usize;
+            let p1 = cp[i] as usize;
+            let p2 = p1 + elen[i] as usize;
+            let mut pn = p1;
+            let mut h: isize = 0;
+            let mut d = 0;
+            for p in p1..p2 {
+                let e = ci[p] as usize;
+                if w[e] != 0 {
+                    let dext = w[e] - mark;
+                    if dext > 0 {
+                        d += dext;
+                        ci[pn] = e as isize;
+                        pn += 1;
+                        h = h.wrapping_add(e as isize);
+                    } else {
+                        // aggressive absorption of e into k
+                        cp[e] = flip(k);
+                        w[e] = 0;
+                    }
+                }
+            }
+            elen[i] = (pn - p1 + 1) as isize;
+            let p3 = pn;
+            let p4 = p1 + len[i] as usize;
+            // prune edges in Ai
+            for p in p2..p4 {
+                let j = ci[p] as usize;
+                let nvj = nv[j];
+                if nvj <= 0 {
+                    continue;
+                }
+                d += nvj;
+                ci[pn] = j as isize;
+                pn += 1;
+                h = h.wrapping_add(j as isize);
+            }
+            if d == 0 {
+                // mass elimination of i into k
+                cp[i] = flip(k);
+                let nvi = -nv[i];
+                dk -= nvi;
+                nvk += nvi;
+                nel += nvi as usize;
+                nv[i] = 0;
+                elen[i] = -1;
+            } else {
+                degree[i] = cmp::min(degree[i], d);
+                ci[pn] = ci[p3];
+                ci[p3] = ci[p1];
+                ci[p1] = k;
+                len[i] = (pn - p1 + 1) as isize;
+                let h = (h.wrapping_abs() % ni) as usize;
+                next[i] = hhead[h];
+                hhead[h] = i as isize;
+                last[i] = h as isize;
+            }
+        }
+        degree[ku] = dk;
+        lemax = cmp::max(lemax, dk);
+        mark = clear_flags(mark.saturating_add(lemax), lemax, &mut w[..n]);
+
+        // --- Supernode detection
+        for pk in pk1..pk2 {
+            let i = ci[pk] as usize;
+            if nv[i] >= 0 {
+                continue;
+            }
+            let h = last[i] as usize;
+            let mut i = hhead[h];
+            hhead[h] = -1;
+            while i != -1 && next[i as usize] != -1 {
+                let iu = i as usize;
+                let ln = len[iu];
+                let eln = elen[iu];
+                let start = cp[iu] as usize;
+                for p in (start + 1)..(start + ln as usize) {
+                    w[ci[p] as usize] = mark;
+                }
+                let mut jlast = iu;
+                let mut j = next[iu];
+                while j != -1 {
+                    let ju = j as usize;
+                    let mut ok = len[ju] == ln && elen[ju] == eln;
+                    let start = cp[ju] as usize;
+                    if ok {
+                        for p in (start + 1)..(start + ln as usize) {
+                            if w[ci[p] as usize] != mark {
+                                ok = false;
+                                break;
+                            }
+                        }
+                    }
+                    if ok {
+                        // i and j are indistinguishable, absorb j into i
+                        cp[ju] = flip(i);
+                        nv[iu] += nv[ju];
+                        nv[ju] = 0;
+                        elen[ju] = -1;
+                        j = next[ju];
+                        next[jlast] = j;
+                    } else {
+                        jlast = ju;
+                        j = next[ju];
+                    }
+                }
+                i = next[iu];
+                mark += 1;
+            }
+        }
+
+        // --- Finalize new element
+        let mut p = pk1;
+        for pk in pk1..pk2 {
+            let i = ci[pk] as usize;
+            let nvi = -nv[i];
+            if nvi <= 0 {
+                continue;
+            }
+            nv[i] = nvi;
+            let d = degree[i] + dk - nvi;
+            let d = cmp::min(d, ni - nel as isize - nvi);
+            let du = d as usize;
+            if head[du] != -1 {
+                last[head[du] as usize] = i as isize;
+            }
+            next[i] = head[du];
+            last[i] = -1;
+            head[du] = i as isize;
+            mindeg = cmp::min(mindeg, du);
+            degree[i] = d;
+            ci[p] = i as isize;
+            p += 1;
+        }
+        nv[ku] = nvk;
+        len[ku] = (p - pk1) as isize;
+        if len[ku] == 0 {
+            // k is a root of the assembly tree
+            cp[ku] = -1;
+            w[ku] = 0;
+        }
+        if elenk != 0 {
+            cnz = p as isize;
+        }
+    }
+
+    // --- Postordering of the assembly tree
+    for i in 0..n {
+        cp[i] = flip(cp[i]);
+    }
+    for j in 0..(n + 1) {
+        head[j] = -1;
+    }
+    // place the absorbed nodes in the list of their parent
+    for j in (0..(n + 1)).rev() {
+        if nv[j] > 0 {
+            continue;
+        }
+        let parent = cp[j] as usize;
+        next[j] = head[parent];
+        head[parent] = j as isize;
+    }
+    // place the elements in the list of their parent
+    for e in (0..(n + 1)).rev() {
+        if nv[e] <= 0 {
+            continue;
+        }
+        if cp[e] != -1 {
+            let parent = cp[e] as usize;
+            next[e] = head[parent];
+            head[parent] = e as isize;
+        }
+    }
+    let mut order = Vec::with_capacity(n + 1);
+    let mut stack = Vec::with_capacity(n + 1);
+    for i in 0..(n + 1) {
+        if cp[i] != -1 {
+            continue;
+        }
+        // depth first search of the tree rooted at i
+        stack.push(i);
+        while let Some(&node) = stack.last() {
+            let child = head[node];
+            if child == -1 {
+                stack.pop();
+                order.push(node);
+            } else {
+                head[node] = next[child as usize];
+                stack.push(child as usize);
+            }
+        }
+    }
+    // the placeholder element n is always a root, and thus ordered last
+    debug_assert_eq!(order.len(), n + 1);
+    debug_assert_eq!(order[n], n);
+    order.truncate(n);
+    Permutation::new(order.into_iter().map(I::from_usize).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use sprs::{CsMat, TriMat};
+    use test::laplacian_2d;
+    use {LdlNumeric, LdlSymbolic};
+
+    fn is_permutation(perm: &[usize]) -> bool {
+        let mut seen = vec![false; perm.len()];
+        for &i in perm {
+            if i >= perm.len() || seen[i] {
+                return false;
+            }
+            seen[i] = true;
+        }
+        true
+    }
+
+    /// Arrow matrix, where node 0 is connected to all others
+    fn arrow(n: usize) -> CsMat<f64> {
+        let mut tri = TriMat::new((n, n));
+        tri.add_triplet(0, 0, n as f64);
+        for i in 1..n {
+            tri.add_triplet(i, i, 1.);
+            tri.add_triplet(0, i, 0.1);
+            tri.add_triplet(i, 0, 0.1);
+        }
+        tri.to_csc()
+    }
+
+    #[test]
+    fn amd_arrow() {
+        // the hub should be eliminated last, giving no fill-in
+        for &n in &[10, 50] {
+            let mat = arrow(n);
+            let perm = super::amd(mat.view());
+            let p = perm.vec();
+            assert!(is_permutation(&p));
+            assert_eq!(p[n - 1], 0);
+            let symbolic = LdlSymbolic::new_amd(mat.view());
+            assert_eq!(symbolic.nnz(), n - 1);
+            let natural = LdlSymbolic::new(mat.view());
+            assert_eq!(natural.nnz(), (n - 1) * n / 2);
+        }
+    }
+
+    #[test]
+    fn amd_reduces_fill() {
+        let mat = laplacian_2d(20);
+        let perm = super::amd(mat.view());
+        assert!(is_permutation(&perm.vec()));
+        let natural = LdlSymbolic::new(mat.view());
+        let symbolic = LdlSymbolic::new_amd(mat.view());
+        assert!(2 * symbolic.nnz() < natural.nnz());
+
+        let b: Vec<f64> = (0..400).map(|i| (i % 7) as f64).collect();
+        let x = LdlNumeric::new_amd(mat.view()).solve(&b);
+        let expected = LdlNumeric::new(mat.view()).solve(&b);
+        for (xi, ei) in x.iter().zip(&expected) {
+            assert!((xi - ei).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn amd_degenerate() {
+        let mat: CsMat<f64> = CsMat::eye(5);
+        let perm = super::amd(mat.view());
+        assert!(is_permutation(&perm.vec()));
+        let mat: CsMat<f64> = CsMat::eye(0);
+        assert_eq!(super::amd(mat.view()).vec().len(), 0);
+    }
+}
@@ -0,0 +1,397 @@
+///! Incomplete LDLT factorization.
+///!
+///! The zero fill-in incomplete factorization, often called IC(0), computes
+///! `L D L^T ~= P A P^T` where `L` is restricted to the non-zero pattern of
+///! the lower triangular part of `P A P^T`. It is much cheaper than the
+///! complete factorization, and is typically used to precondition the
+///! conjugate gradient method.
+use std::ops::Deref;
+
+use ndarray::{ArrayView1, ArrayViewMut1};
+use num_traits::Num;
+
+use sprs::errors::SprsError;
+use sprs::indexing::SpIndex;
+use sprs::linalg;
+use sprs::linalg::iterative::Preconditioner;
+use sprs::{CsMatI, CsMatViewI, PermOwnedI, Permutation};
+
+use {csr_view, ldl_lsolve, ldl_ltsolve};
+
+/// Maximum number of times the diagonal shift is doubled before giving up
+const MAX_SHIFT_DOUBLINGS: usize = 64;
+
+/// Structure to hold an incomplete LDLT decomposition
+#[derive(Debug)]
+pub struct IncompleteLdl<N, I: SpIndex> {
+    l: CsMatI<N, I>,
+    diag: Vec<N>,
+    perm: PermOwnedI<I>,
+    shift: N,
+}
+
+impl<N, I> IncompleteLdl<N, I>
+where
+    N: Copy + Num + PartialOrd + Default,
+    I: SpIndex,
+{
+    /// Compute the incomplete LDLT decomposition of the given symmetric
+    /// matrix.
+    ///
+    /// This decomposition is meant for symmetric positive definite matrices,
+    /// a non-positive pivot is thus considered as a breakdown and results
+    /// in `SprsError::SingularMatrix`. Breakdowns can happen for positive
+    /// definite matrices as well, `new_shifted` can then be used instead.
+    ///
+    /// Symmetry of the matrix is not checked, only its lower triangular part
+    /// is used.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    pub fn new(mat: CsMatViewI<N, I>) -> Result<Self, SprsError> {
+        let perm = Permutation::identity(mat.rows());
+        IncompleteLdl::new_perm(mat, perm)
+    }
+
+    /// Compute the incomplete decomposition `L D L^T ~= P A P^T` where P
+    /// is a permutation matrix.
+    ///
+    /// The permutation changes the non-zero pattern of the factor, and thus
+    /// the quality of the approximation.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    pub fn new_perm(
+        mat: CsMatViewI<N, I>,
+        perm: PermOwnedI<I>,
+    ) -> Result<Self, SprsError> {
+        let lower = permuted_lower_rows(mat, &perm);
+        let (l, diag) = incomplete_ldl_rows(&lower, N::zero())?;
+        Ok(IncompleteLdl {
+            l: l,
+            diag: diag,
+            perm: perm,
+            shift: N::zero(),
+        })
+    }
+
+    /// Compute the incomplete decomposition `L D L^T ~= P (A + s D_A) P^T`
+    /// where `D_A` is the diagonal of `A`, using the smallest shift `s` of the
+    /// sequence `0, initial_shift, 2 * initial_shift, 4 * initial_shift, ...`
+    /// avoiding a breakdown.
+    ///
+    /// Returns `SprsError::SingularMatrix` if a diagonal element of the
+    /// matrix is not positive, as no shift can then avoid a breakdown.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    /// * if `initial_shift` is not positive
+    pub fn new_shifted(
+        mat: CsMatViewI<N, I>,
+        perm: PermOwnedI<I>,
+        initial_shift: N,
+    ) -> Result<Self, SprsError> {
+        assert!(initial_shift > N::zero(), "shift should be positive");
+        let lower = permuted_lower_rows(mat, &perm);
+        for row in &lower {
+            match row.last() {
+                Some(&(_, diag)) if diag > N::zero() => (),
+                _ => return Err(SprsError::SingularMatrix),
+            }
+        }
+        let mut shift = N::zero();
+        for _ in 0..MAX_SHIFT_DOUBLINGS {
+            if let Ok((l, diag)) = incomplete_ldl_rows(&lower, shift) {
+                return Ok(IncompleteLdl {
+                    l: l,
+                    diag: diag,
+                    perm: perm,
+                    shift: shift,
+                });
+            }
+            shift = if shift == N::zero() {
+                initial_shift
+            } else {
+                shift + shift
+            };
+        }
+        Err(SprsError::SingularMatrix)
+    }
+
+    /// Solve the system A x = rhs, where A is approximated by this
+    /// decomposition
+    pub fn solve<'a, V>(&self, rhs: &V) -> Vec<N>
+    where
+        N: 'a,
+        V: Deref<Target = [N]>,
+    {
+        let mut x = &self.perm * &rhs[..];
+        let l = self.l.view();
+        ldl_lsolve(&l, &mut x);
+        linalg::diag_solve(&self.diag, &mut x);
+        ldl_ltsolve(&l, &mut x);
+        let pinv = self.perm.inv();
+        &pinv * &x
+    }
+
+    /// The strictly lower triangular factor L, in CSC format
+    pub fn l(&self) -> CsMatViewI<N, I> {
+        self.l.view()
+    }
+
+    /// The diagonal factor D
+    pub fn diag(&self) -> &[N] {
+        &self.diag
+    }
+
+    /// The diagonal shift that was needed to avoid a breakdown
+    pub fn shift(&self) -> N {
+        self.shift
+    }
+
+    /// The size of the linear system associated with this decomposition
+    #[inline]
+    pub fn problem_size(&self) -> usize {
+        self.diag.len()
+    }
+
+    /// The number of non-zero entries in L
+    #[inline]
+    pub fn nnz(&self) -> usize {
+        self.l.nnz()
+    }
+}
+
+impl<N, I> Preconditioner<N> for IncompleteLdl<N, I>
+where
+    N: Copy + Num + PartialOrd + Default,
+    I: SpIndex,
+{
+    fn apply(&self, rhs: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        assert_eq!(out.len(), self.problem_size(), "Dimension mismatch");
+        let x = self.solve(&rhs.to_vec());
+        for (o, xi) in out.iter_mut().zip(x) {
+            *o = xi;
+        }
+    }
+}
+
+/// Extract the rows of the lower triangular part of `P A P^T`, with sorted
+/// column indices. The diagonal element, if present, is the last of each row.
+fn permuted_lower_rows<N, I, PStorage>(
+    mat: CsMatViewI<N, I>,
+    perm: &Permutation<I, PStorage>,
+) -> Vec<Vec<(usize, N)>>
+where
+    N: Copy,
+    I: SpIndex,
+    PStorage: Deref<Target = [I]>,
+{
+    let n = mat.rows();
+    assert!(mat.cols() == n, "matrix should be square");
+    let mat = csr_view(mat);
+    let mut rows = Vec::with_capacity(n);
+    for (k, (_, vec)) in mat.outer_iterator_perm(perm.view()).enumerate() {
+        let mut row: Vec<_> = vec
+            .iter_perm(perm.inv())
+            .filter(|&(i, _)| i <= k)
+            .map(|(i, &val)| (i, val))
+            .collect();
+        row.sort_by_key(|&(i, _)| i);
+        rows.push(row);
+    }
+    rows
+}
+
+/// Zero fill-in LDLT factorization, computed row by row. The diagonal
+/// of the matrix is multiplied by `1 + shift`.
+///
+/// Returns the strictly lower triangular factor in CSC format, along with
+/// the diagonal factor.
+fn incomplete_ldl_rows<N, I>(
+    lower: &[Vec<(usize, N)>],
+    shift: N,
+) -> Result<(CsMatI<N, I>, Vec<N>), SprsError>
+where
+    N: Copy + Num + PartialOrd + Default,
+    I: SpIndex,
+{
+    let n = lower.len();
+    let mut diag = Vec::with_capacity(n);
+    let mut indptr = Vec::with_capacity(n + 1);
+    let mut indices = Vec::new();
+    let mut data: Vec<N> = Vec::new();
+    indptr.push(I::zero());
+    // position of the non-zeros of the current row in data
+    let mut position = vec![None; n];
+
+    for (i, row) in lower.iter().enumerate() {
+        let row_start = data.len();
+        let mut d = N::zero();
+        for &(j, val) in row {
+            if j == i {
+                d = val + shift * val;
+            } else {
+                position[j] = Some(data.len());
+                indices.push(I::from_usize(j));
+                data.push(val);
+            }
+        }
+        // l_ij = (a_ij - sum_k l_ik d_k l_jk) / d_j, with the sum restricted
+        // to the pattern of row i. The columns are visited in increasing
+        // order, and the row stores l_ik d_k until it is complete.
+        for p in row_start..data.len() {
+            let j = indices[p].index();
+            let mut acc = data[p];
+            for q in indptr[j].index()..indptr[j + 1].index() {
+                if let Some(pos) = position[indices[q].index()] {
+                    acc = acc - data[pos] * data[q];
+                }
+            }
+            data[p] = acc;
+            let l_ij = acc / diag[j];
+            d = d - acc * l_ij;
+        }
+        for p in row_start..data.len() {
+            let j = indices[p].index();
+            position[j] = None;
+            data[p] = data[p] / diag[j];
+        }
+        if !(d > N::zero()) {
+            return Err(SprsError::SingularMatrix);
+        }
+        diag.push(d);
+        indptr.push(I::from_usize(data.len()));
+    }
+    let l_csr = CsMatI::new((n, n), indptr, indices, data);
+    Ok((l_csr.to_csc(), diag))
+}
+
+#[cfg(test)]
+mod test {
+    use super::IncompleteLdl;
+    use ndarray::Array;
+    use sprs::errors::SprsError;
+    use sprs::linalg::iterative::{cg, pcg, StoppingCriteria};
+    use sprs::{CsMat, Permutation, TriMat};
+    use test::laplacian_2d;
+    use LdlNumeric;
+
+    fn tridiagonal(n: usize) -> CsMat<f64> {
+        let mut tri = TriMat::new((n, n));
+        for i in 0..n {
+            tri.add_triplet(i, i, 4. + i as f64);
+            if i > 0 {
+                tri.add_triplet(i, i - 1, -1.);
+                tri.add_triplet(i - 1, i, -1.);
+            }
+        }
+        tri.to_csc()
+    }
+
+    #[test]
+    fn no_fill_is_exact() {
+        // the factorization of a tridiagonal matrix has no fill-in, even
+        // when reversing the ordering
+        let mat = tridiagonal(10);
+        let b: Vec<f64> = (0..10).map(|i| 1. + i as f64).collect();
+        let exact = LdlNumeric::new(mat.view()).solve(&b);
+        let ic = IncompleteLdl::new(mat.view()).unwrap();
+        assert_eq!(ic.nnz(), 9);
+        assert_eq!(ic.shift(), 0.);
+        let perm = Permutation::new((0..10).rev().collect());
+        let ic_rev = IncompleteLdl::new_perm(mat.view(), perm).unwrap();
+        for x in &[ic.solve(&b), ic_rev.solve(&b)] {
+            for (xi, ei) in x.iter().zip(&exact) {
+                assert!((xi - ei).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn pattern_is_preserved() {
+        let mat = laplacian_2d(6);
+        let ic = IncompleteLdl::new(mat.view()).unwrap();
+        // the strictly lower triangular part of the laplacian
+        assert_eq!(ic.nnz(), (mat.nnz() - 36) / 2);
+        for (col, vec) in ic.l().outer_iterator().enumerate() {
+            for (row, _) in vec.iter() {
+                assert!(row > col);
+                assert!(mat.get(row, col).is_some());
+            }
+        }
+        assert!(ic.diag().iter().all(|&d| d > 0.));
+    }
+
+    #[test]
+    fn ic_preconditioned_cg() {
+        let mat = laplacian_2d(12);
+        let n = mat.rows();
+        let b = Array::from_shape_fn(n, |i| 1. + (i % 3) as f64);
+        let criteria = StoppingCriteria::new(1e-10, 500);
+        let mut x = Array::zeros(n);
+        let report = cg(mat.view(), b.view(), x.view_mut(), &criteria);
+        assert!(report.converged());
+
+        let ic = IncompleteLdl::new(mat.view()).unwrap();
+        let mut x_ic = Array::zeros(n);
+        let report_ic =
+            pcg(mat.view(), b.view(), x_ic.view_mut(), &ic, &criteria);
+        assert!(report_ic.converged());
+        assert!(report_ic.iterations() < report.iterations());
+        let residual = &b - &(&mat * &x_ic);
+        assert!(residual.iter().all(|r| r.abs() < 1e-8));
+
+        // the complete factorization is an exact preconditioner
+        let ldl = LdlNumeric::new(mat.view());
+        let mut x_ldl = Array::zeros(n);
+        let report_ldl =
+            pcg(mat.view(), b.view(), x_ldl.view_mut(), &ldl, &criteria);
+        assert!(report_ldl.converged());
+        assert!(report_ldl.iterations() <= 2);
+    }
+
+    #[test]
+    fn breakdown_and_shift() {
+        // indefinite matrix, with a positive diagonal
+        let mat: CsMat<f64> = CsMat::new_csc(
+            (2, 2),
+            vec![0, 2, 4],
+            vec![0, 1, 0, 1],
+            vec![1., 2., 2., 1.],
+        );
+        assert_eq!(
+            IncompleteLdl::new(mat.view()).unwrap_err(),
+            SprsError::SingularMatrix
+        );
+        let perm = Permutation::identity(2);
+        let ic = IncompleteLdl::new_shifted(mat.view(), perm, 0.01).unwrap();
+        // the first shift avoiding the breakdown is above 1
+        assert_eq!(ic.shift(), 1.28);
+        assert!(ic.diag().iter().all(|&d| d > 0.));
+        // there is no fill-in, so the shifted factorization is exact for
+        // the shifted matrix
+        let shifted = CsMat::new_csc(
+            (2, 2),
+            vec![0, 2, 4],
+            vec![0, 1, 0, 1],
+            vec![2.28, 2., 2., 2.28],
+        );
+        let b = vec![1., 2.];
+        let exact = LdlNumeric::new(shifted.view()).solve(&b);
+        for (xi, ei) in ic.solve(&b).iter().zip(&exact) {
+            assert!((xi - ei).abs() < 1e-12);
+        }
+
+        // a non positive diagonal cannot be fixed by a shift
+        let mut tri = TriMat::new((2, 2));
+        tri.add_triplet(0, 0, 1.);
+        tri.add_triplet(1, 1, -1.);
+        let mat: CsMat<f64> = tri.to_csc();
+        let perm = Permutation::identity(2);
+        assert!(IncompleteLdl::new_shifted(mat.view(), perm, 0.1).is_err());
+    }
+}
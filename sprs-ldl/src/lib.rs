@@ -1,3 +1,4 @@
+extern crate ndarray;
 extern crate num_traits;
 ///! Cholesky factorization module.
 ///!
@@ -19,6 +20,14 @@ extern crate num_traits;
 ///! to precompute part of the factorization by using the `LdlSymbolic` struct.
 ///! This struct can the be converted into a `LdlNumeric` once the non-zero
 ///! values are known, using the `LdlSymbolic::factor` method.
+///!
+///! The non-zero count of the factor depends heavily on the ordering of the
+///! matrix. A fill-reducing ordering can be computed using the `amd`
+///! function, or directly by the `new_amd` constructors.
+///!
+///! An incomplete factorization, `IncompleteLdl`, is also available. It is
+///! not exact, but is cheap to compute and can be used as a preconditioner
+///! for the iterative solvers in `sprs::linalg::iterative`.
 // This method is adapted from the LDL library by Tim Davis:
 //
 // LDL Copyright (c) 2005 by Timothy A. Davis.  All Rights Reserved.
@@ -57,13 +66,21 @@ extern crate sprs;
 use std::ops::Deref;
 use std::ops::IndexMut;
 
+use ndarray::{ArrayView1, ArrayViewMut1};
 use num_traits::Num;
 
 use sprs::indexing::SpIndex;
 use sprs::linalg;
+use sprs::linalg::iterative::Preconditioner;
 use sprs::stack::DStack;
 use sprs::{is_symmetric, CsMatViewI, PermOwnedI, Permutation};
 
+mod amd;
+mod incomplete;
+
+pub use amd::amd;
+pub use incomplete::IncompleteLdl;
+
 pub enum SymmetryCheck {
     CheckSymmetry,
     DontCheckSymmetry,
@@ -147,6 +164,21 @@ impl<I: SpIndex> LdlSymbolic<I> {
         }
     }
 
+    /// Compute the symbolic decomposition L D L^T = P A P^T
+    /// where P is a fill-reducing permutation computed by the
+    /// Approximate Minimum Degree algorithm.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not symmetric
+    pub fn new_amd<N>(mat: CsMatViewI<N, I>) -> LdlSymbolic<I>
+    where
+        N: Copy + PartialEq,
+    {
+        let perm = amd(mat.view());
+        LdlSymbolic::new_perm(mat, perm)
+    }
+
     /// The size of the linear system associated with this decomposition
     #[inline]
     pub fn problem_size(&self) -> usize {
@@ -216,6 +248,21 @@ impl<N, I: SpIndex> LdlNumeric<N, I> {
         symbolic.factor(mat)
     }
 
+    /// Compute the numeric decomposition L D L^T = P A P^T
+    /// where P is a fill-reducing permutation computed by the
+    /// Approximate Minimum Degree algorithm.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not symmetric
+    pub fn new_amd(mat: CsMatViewI<N, I>) -> Self
+    where
+        N: Copy + Num + PartialOrd,
+    {
+        let symbolic = LdlSymbolic::new_amd(mat.view());
+        symbolic.factor(mat)
+    }
+
     /// Update the decomposition with the given matrix. The matrix must
     /// have the same non-zero pattern as the original matrix, otherwise
     /// the result is unspecified.
@@ -280,6 +327,30 @@ impl<N, I: SpIndex> LdlNumeric<N, I> {
     }
 }
 
+impl<N, I> Preconditioner<N> for LdlNumeric<N, I>
+where
+    N: Copy + Num,
+    I: SpIndex,
+{
+    fn apply(&self, rhs: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        assert_eq!(out.len(), self.problem_size(), "Dimension mismatch");
+        let x = self.solve(&rhs.to_vec());
+        for (o, xi) in out.iter_mut().zip(x) {
+            *o = xi;
+        }
+    }
+}
+
+/// View a symmetric matrix in CSR format, so that its outer vectors are its
+/// rows. The transpose of a CSC matrix is used, which is the same matrix.
+fn csr_view<N, I: SpIndex>(mat: CsMatViewI<N, I>) -> CsMatViewI<N, I> {
+    if mat.is_csc() {
+        mat.transpose_into()
+    } else {
+        mat
+    }
+}
+
 /// Perform a symbolic LDLt decomposition of a symmetric sparse matrix
 pub fn ldl_symbolic<N, I, PStorage>(
     mat: CsMatViewI<N, I>,
@@ -305,6 +376,7 @@ pub fn ldl_symbolic<N, I, PStorage>(
 
     let n = mat.rows();
 
+    let mat = csr_view(mat);
     let outer_it = mat.outer_iterator_perm(perm.view());
     // compute the elimination tree of L
     for (k, (_, vec)) in outer_it.enumerate() {
@@ -354,6 +426,7 @@ pub fn ldl_numeric<N, I, PStorage>(
     I: SpIndex,
     PStorage: Deref<Target = [I]>,
 {
+    let mat = csr_view(mat);
     let outer_it = mat.outer_iterator_perm(perm.view());
     for (k, (_, vec)) in outer_it.enumerate() {
         // compute the nonzero pattern of the kth row of L
@@ -445,8 +518,15 @@ where
 #[cfg(test)]
 mod test {
     use super::SymmetryCheck;
+    use ndarray::{Array, Array2};
+    use sprs::linalg::amg::{Amg, AmgOptions};
+    use sprs::linalg::eigen::{
+        lanczos_shift_invert, lobpcg, EigenOptions, Which,
+    };
+    use sprs::linalg::etree::{symmetric_etree, CholeskyCounts};
+    use sprs::linalg::iterative::{pcg, StoppingCriteria};
     use sprs::stack::DStack;
-    use sprs::{self, linalg, CsMat, CsMatView, Permutation};
+    use sprs::{self, grid_laplacian, linalg, CsMat, CsMatView, Permutation};
 
     fn test_mat1() -> CsMat<f64> {
         let indptr = vec![0, 2, 5, 6, 7, 13, 14, 17, 20, 24, 28];
@@ -462,6 +542,11 @@ mod test {
         CsMat::new_csc((10, 10), indptr, indices, data)
     }
 
+    /// 5-point laplacian on a square grid, in CSR format
+    pub fn laplacian_2d(size: usize) -> CsMat<f64> {
+        grid_laplacian(&[size, size])
+    }
+
     fn test_vec1() -> Vec<f64> {
         vec![
             0.287, 0.22, 0.45, 0.44, 2.486, 0.72, 1.55, 1.424, 1.621, 3.759,
@@ -655,4 +740,118 @@ mod test {
         let x = ldlt.solve(&b);
         assert_eq!(x, x0);
     }
+
+    #[test]
+    fn shift_invert_eigenvalues() {
+        // the smallest eigenvalues of the laplacian, using the factorization
+        // of the laplacian itself
+        let size = 15;
+        let lap = laplacian_2d(size);
+        let ldlt = super::LdlNumeric::new_amd(lap.view());
+        let options = EigenOptions::new(1e-12, 50);
+        let res =
+            lanczos_shift_invert(lap.view(), &ldlt, 0., 4, &options).unwrap();
+        assert!(res.converged());
+        let h = ::std::f64::consts::PI / (size + 1) as f64;
+        let mut expected = Vec::new();
+        for i in 1..size + 1 {
+            for j in 1..size + 1 {
+                let (i, j) = (i as f64, j as f64);
+                expected.push(4. - 2. * (i * h).cos() - 2. * (j * h).cos());
+            }
+        }
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for (&val, &exp) in res.eigenvalues().iter().zip(&expected) {
+            assert!((val - exp).abs() < 1e-10);
+        }
+        assert!(res.residual_norms().iter().all(|&r| r < 1e-8));
+    }
+
+    #[test]
+    fn lobpcg_preconditioner() {
+        // the factorization of the laplacian is an excellent preconditioner
+        // when looking for its smallest eigenvalues
+        let size = 20;
+        let lap = laplacian_2d(size);
+        let n = lap.rows();
+        let eye = CsMat::eye(n);
+        let ldlt = super::LdlNumeric::new_amd(lap.view());
+        let x0 = Array2::from_shape_fn((n, 3), |(i, j)| {
+            ((i * (j + 1)) as f64 * 0.37).sin()
+        });
+        let options = EigenOptions::new(1e-10, 100);
+        let res = lobpcg(
+            lap.view(),
+            eye.view(),
+            &ldlt,
+            x0,
+            Which::SmallestReal,
+            &options,
+        )
+        .unwrap();
+        assert!(res.converged());
+        assert!(res.iterations() < 50);
+        let h = ::std::f64::consts::PI / (size + 1) as f64;
+        let lowest = 4. - 4. * h.cos();
+        let second = 4. - 2. * h.cos() - 2. * (2. * h).cos();
+        let expected = [lowest, second, second];
+        for (&val, &exp) in res.eigenvalues().iter().zip(&expected) {
+            assert!((val - exp).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn condition_number_estimate() {
+        let lap = laplacian_2d(10);
+        let n = lap.rows();
+        let ldlt = super::LdlNumeric::new_amd(lap.view());
+        let solve = |b: &[f64]| ldlt.solve(&b);
+        let inv_norm = linalg::normest::onenormest(n, &solve, &solve);
+        let exact = (0..n)
+            .map(|j| {
+                let mut e = vec![0.; n];
+                e[j] = 1.;
+                solve(&e).iter().fold(0., |acc, x| acc + x.abs())
+            })
+            .fold(0., f64::max);
+        assert!((inv_norm - exact).abs() < 1e-10 * exact);
+        let cond = lap.one_norm() * inv_norm;
+        assert!(cond > 1. && cond < 1e3);
+    }
+
+    #[test]
+    fn amg_coarse_solve() {
+        let lap = laplacian_2d(40);
+        let n = lap.rows();
+        let options = AmgOptions::new().with_max_coarse(100);
+        let amg = Amg::new(lap.view(), &options, super::LdlNumeric::new);
+        assert!(amg.num_levels() > 2);
+        let coarse_dim = *amg.level_dims().last().unwrap();
+        assert_eq!(amg.coarse_solver().problem_size(), coarse_dim);
+        let rhs = Array::from_elem(n, 1.);
+        let mut x = Array::zeros(n);
+        let criteria = StoppingCriteria::new(1e-10, 100);
+        let report =
+            pcg(lap.view(), rhs.view(), x.view_mut(), &amg, &criteria);
+        assert!(report.converged());
+        assert!(report.iterations() < 15);
+    }
+
+    #[test]
+    fn predicted_nnz() {
+        let lap = laplacian_2d(12);
+        let n = lap.rows();
+        let parents = symmetric_etree(lap.view());
+        let postorder = parents.postorder();
+        let counts = CholeskyCounts::new(lap.view(), &parents, &postorder);
+        let symbolic = super::LdlSymbolic::new(lap.view());
+        // the diagonal of L is not stored by the LDL^T decomposition
+        assert_eq!(counts.nnz(), symbolic.nnz() + n);
+        for node in 0..n {
+            assert_eq!(
+                parents.get_parent(node),
+                symbolic.parents.get_parent(node)
+            );
+        }
+    }
 }
@@ -0,0 +1,586 @@
+extern crate num_traits;
+///! Sparse LU factorization module.
+///!
+///! Contains the left-looking LU factorization with threshold partial pivoting
+///! of Gilbert and Peierls.
+///!
+///! This decomposition operates on general square matrices, and is written
+///! `P A Q = L U`, where `P` is a row permutation chosen during the
+///! factorization to ensure numerical stability, `Q` is a column permutation
+///! given by the user to reduce fill-in, `L` is unit lower triangular and
+///! `U` is upper triangular.
+///!
+///! The easiest way to use this API is to create a `SparseLu` instance from
+///! a matrix, then use the `SparseLu::solve` method.
+///!
+///! As the non-zero structure of the factors depends on the pivoting, only
+///! the column ordering can be computed before the numerical values are
+///! known. It is held by the `LuSymbolic` struct, which can be used to factor
+///! several matrices having the same non-zero pattern, using the
+///! `LuSymbolic::factor` method.
+///!
+///! Each column of `L` and `U` is computed by a sparse triangular solve with
+///! the previous columns of `L`, whose non-zero pattern is determined by a
+///! depth first search in the graph of `L`, as in `lsolve_csc_sparse_rhs`.
+///! The total work is thus proportional to the number of floating point
+///! operations.
+extern crate sprs;
+
+use std::ops::Deref;
+
+use num_traits::Float;
+
+use sprs::errors::SprsError;
+use sprs::indexing::SpIndex;
+use sprs::linalg::trisolve;
+use sprs::stack::DStack;
+use sprs::{CsMatI, CsMatViewI, PermOwnedI, PermViewI, Permutation};
+
+/// Marker for the rows which have not been chosen as pivot yet
+const NOT_PIVOTED: usize = usize::MAX;
+
+/// Structure to hold the symbolic analysis of a LU decomposition, ie the
+/// column ordering.
+#[derive(Debug, Clone)]
+pub struct LuSymbolic<I> {
+    n: usize,
+    col_perm: PermOwnedI<I>,
+}
+
+/// Structure to hold a numeric LU decomposition
+#[derive(Debug)]
+pub struct SparseLu<N, I: SpIndex> {
+    l: CsMatI<N, I>,
+    u: CsMatI<N, I>,
+    row_perm: PermOwnedI<I>,
+    col_perm: PermOwnedI<I>,
+}
+
+impl<I: SpIndex> LuSymbolic<I> {
+    /// Prepare the LU decomposition of the given matrix, without column
+    /// reordering.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    pub fn new<N>(mat: CsMatViewI<N, I>) -> LuSymbolic<I> {
+        let perm = Permutation::identity(mat.rows());
+        LuSymbolic::new_perm(mat, perm)
+    }
+
+    /// Prepare the LU decomposition `P A Q = L U` of the given matrix, where
+    /// `Q` is a column permutation: the `k`-th column factored is the column
+    /// `col_perm.at(k)` of the matrix.
+    ///
+    /// A good column ordering can greatly reduce the fill-in of the factors.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    /// * if the dimension of `col_perm` does not match the matrix
+    pub fn new_perm<N>(
+        mat: CsMatViewI<N, I>,
+        col_perm: PermOwnedI<I>,
+    ) -> LuSymbolic<I> {
+        let n = mat.rows();
+        assert!(mat.cols() == n, "matrix should be square");
+        assert_eq!(col_perm.dim(), n, "Dimension mismatch");
+        LuSymbolic {
+            n: n,
+            col_perm: col_perm,
+        }
+    }
+
+    /// The size of the linear system associated with this decomposition
+    #[inline]
+    pub fn problem_size(&self) -> usize {
+        self.n
+    }
+
+    /// The column permutation
+    pub fn col_perm(&self) -> PermViewI<I> {
+        self.col_perm.view()
+    }
+
+    /// Compute the numerical decomposition of the given matrix, which
+    /// can be stored in either CSC or CSR format.
+    ///
+    /// `pivot_threshold` controls the pivoting: the diagonal element of
+    /// the column being factored is chosen as the pivot if its magnitude is
+    /// at least `pivot_threshold` times the largest magnitude in the
+    /// candidate pivots. A threshold of 1 corresponds to the usual partial
+    /// pivoting, while smaller thresholds tend to preserve the sparsity
+    /// of the factors.
+    ///
+    /// Returns `SprsError::SingularMatrix` if no non-zero pivot can be
+    /// found for a column.
+    ///
+    /// # Panics
+    ///
+    /// * if the dimension of mat does not match this symbolic decomposition
+    /// * if `pivot_threshold` is not in `(0, 1]`
+    pub fn factor<N>(
+        &self,
+        mat: CsMatViewI<N, I>,
+        pivot_threshold: N,
+    ) -> Result<SparseLu<N, I>, SprsError>
+    where
+        N: Float + Default,
+    {
+        let n = self.n;
+        assert!(mat.rows() == n && mat.cols() == n, "Dimension mismatch");
+        assert!(
+            pivot_threshold > N::zero() && pivot_threshold <= N::one(),
+            "pivot threshold should be in (0, 1]"
+        );
+        let csc_storage;
+        let mat = if mat.is_csc() {
+            mat
+        } else {
+            csc_storage = mat.to_csc();
+            csc_storage.view()
+        };
+
+        // Columns of L are stored with the original row indices, without the
+        // unit diagonal, and the row indices of U are the pivot steps. The
+        // row indices of L are only known once all pivots have been chosen.
+        let nnz_guess = 4 * mat.nnz() + n;
+        let mut l_indptr = Vec::with_capacity(n + 1);
+        let mut l_indices = Vec::with_capacity(nnz_guess);
+        let mut l_data = Vec::with_capacity(nnz_guess);
+        let mut u_indptr = Vec::with_capacity(n + 1);
+        let mut u_indices = Vec::with_capacity(nnz_guess);
+        let mut u_data = Vec::with_capacity(nnz_guess);
+        l_indptr.push(0);
+        u_indptr.push(0);
+
+        let mut pinv = vec![NOT_PIVOTED; n];
+        let mut x = vec![N::zero(); n];
+        let mut dstack = DStack::with_capacity(2 * n + 2);
+        let mut visited = vec![false; n];
+        let mut child_pos = vec![0; n];
+
+        for k in 0..n {
+            let col = self.col_perm.at(k);
+            let a_col = mat.outer_view(col).expect("column is in bounds");
+            reach(
+                &l_indptr,
+                &l_indices,
+                &pinv,
+                a_col.indices(),
+                &mut dstack,
+                &mut visited,
+                &mut child_pos,
+            );
+
+            // sparse triangular solve, in topological order
+            for (i, &val) in a_col.iter() {
+                x[i] = val;
+            }
+            for &i in dstack.iter_right() {
+                let l_col = pinv[i];
+                if l_col == NOT_PIVOTED {
+                    continue;
+                }
+                let x_i = x[i];
+                for p in l_indptr[l_col]..l_indptr[l_col + 1] {
+                    let row = l_indices[p];
+                    x[row] = x[row] - l_data[p] * x_i;
+                }
+            }
+
+            // the pivoted rows give the column of U, the other ones
+            // are the pivot candidates
+            let mut pivot_row = NOT_PIVOTED;
+            let mut max_abs = N::zero();
+            for &i in dstack.iter_right() {
+                if pinv[i] == NOT_PIVOTED {
+                    if x[i].abs() > max_abs {
+                        max_abs = x[i].abs();
+                        pivot_row = i;
+                    }
+                } else {
+                    u_indices.push(pinv[i]);
+                    u_data.push(x[i]);
+                }
+            }
+            if pivot_row == NOT_PIVOTED {
+                return Err(SprsError::SingularMatrix);
+            }
+            if pinv[col] == NOT_PIVOTED
+                && x[col].abs() >= pivot_threshold * max_abs
+            {
+                pivot_row = col;
+            }
+            let pivot = x[pivot_row];
+            pinv[pivot_row] = k;
+            u_indices.push(k);
+            u_data.push(pivot);
+            u_indptr.push(u_indices.len());
+
+            for &i in dstack.iter_right() {
+                if pinv[i] == NOT_PIVOTED {
+                    l_indices.push(i);
+                    l_data.push(x[i] / pivot);
+                }
+                x[i] = N::zero();
+                visited[i] = false;
+            }
+            l_indptr.push(l_indices.len());
+            dstack.clear_right();
+        }
+
+        // express L in the pivoted row order, with its unit diagonal
+        for i in l_indices.iter_mut() {
+            *i = pinv[*i];
+        }
+        let mut l_full_indices = Vec::with_capacity(l_indices.len() + n);
+        let mut l_full_data = Vec::with_capacity(l_indices.len() + n);
+        let mut l_full_indptr = Vec::with_capacity(n + 1);
+        l_full_indptr.push(I::zero());
+        for k in 0..n {
+            l_full_indices.push(I::from_usize(k));
+            l_full_data.push(N::one());
+            let range = l_indptr[k]..l_indptr[k + 1];
+            push_sorted(
+                &l_indices[range.clone()],
+                &l_data[range],
+                &mut l_full_indices,
+                &mut l_full_data,
+            );
+            l_full_indptr.push(I::from_usize(l_full_indices.len()));
+        }
+        let l =
+            CsMatI::new_csc((n, n), l_full_indptr, l_full_indices, l_full_data);
+
+        let mut u_sorted_indices = Vec::with_capacity(u_indices.len());
+        let mut u_sorted_data = Vec::with_capacity(u_indices.len());
+        for k in 0..n {
+            let range = u_indptr[k]..u_indptr[k + 1];
+            push_sorted(
+                &u_indices[range.clone()],
+                &u_data[range],
+                &mut u_sorted_indices,
+                &mut u_sorted_data,
+            );
+        }
+        let u_indptr = u_indptr.into_iter().map(I::from_usize).collect();
+        let u =
+            CsMatI::new_csc((n, n), u_indptr, u_sorted_indices, u_sorted_data);
+
+        let mut row_perm = vec![I::zero(); n];
+        for (i, &k) in pinv.iter().enumerate() {
+            row_perm[k] = I::from_usize(i);
+        }
+
+        Ok(SparseLu {
+            l: l,
+            u: u,
+            row_perm: Permutation::new(row_perm),
+            col_perm: self.col_perm.clone(),
+        })
+    }
+}
+
+/// Compute the non-zero pattern of the solution of `L x = b`, where
+/// `b` has the non-zero pattern given by `roots`, using depth first
+/// searches in the graph of `L`.
+///
+/// On exit, the right stack of `dstack` holds the non-zero pattern in
+/// topological order, and the visited nodes are flagged in `visited`.
+fn reach<I>(
+    l_indptr: &[usize],
+    l_indices: &[usize],
+    pinv: &[usize],
+    roots: &[I],
+    dstack: &mut DStack<usize>,
+    visited: &mut [bool],
+    child_pos: &mut [usize],
+) where
+    I: SpIndex,
+{
+    for root in roots.iter().map(|i| i.index()) {
+        if visited[root] {
+            continue;
+        }
+        // the left stack holds the current path in the tree
+        dstack.push_left(root);
+        while let Some(node) = dstack.pop_left() {
+            let l_col = pinv[node];
+            let end = if l_col == NOT_PIVOTED {
+                0
+            } else {
+                l_indptr[l_col + 1]
+            };
+            if !visited[node] {
+                visited[node] = true;
+                child_pos[node] = if l_col == NOT_PIVOTED {
+                    0
+                } else {
+                    l_indptr[l_col]
+                };
+            }
+            let mut done = true;
+            while child_pos[node] < end {
+                let child = l_indices[child_pos[node]];
+                child_pos[node] += 1;
+                if !visited[child] {
+                    dstack.push_left(node);
+                    dstack.push_left(child);
+                    done = false;
+                    break;
+                }
+            }
+            if done {
+                dstack.push_right(node);
+            }
+        }
+    }
+}
+
+/// Push the given entries, sorted by index
+fn push_sorted<N, I>(
+    indices: &[usize],
+    data: &[N],
+    sorted_indices: &mut Vec<I>,
+    sorted_data: &mut Vec<N>,
+) where
+    N: Copy,
+    I: SpIndex,
+{
+    let mut entries: Vec<_> = indices.iter().zip(data.iter()).collect();
+    entries.sort_by_key(|&(&i, _)| i);
+    for (&i, &val) in entries {
+        sorted_indices.push(I::from_usize(i));
+        sorted_data.push(val);
+    }
+}
+
+impl<N, I> SparseLu<N, I>
+where
+    N: Float + Default,
+    I: SpIndex,
+{
+    /// Compute the LU decomposition of the given matrix, using partial
+    /// pivoting and no column reordering.
+    ///
+    /// Returns `SprsError::SingularMatrix` if the matrix is singular.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    pub fn new(mat: CsMatViewI<N, I>) -> Result<Self, SprsError> {
+        let symbolic = LuSymbolic::new(mat.view());
+        symbolic.factor(mat, N::one())
+    }
+
+    /// Compute the LU decomposition `P A Q = L U` of the given matrix,
+    /// using partial pivoting and the given column permutation.
+    ///
+    /// Returns `SprsError::SingularMatrix` if the matrix is singular.
+    ///
+    /// # Panics
+    ///
+    /// * if mat is not square
+    /// * if the dimension of `col_perm` does not match the matrix
+    pub fn new_perm(
+        mat: CsMatViewI<N, I>,
+        col_perm: PermOwnedI<I>,
+    ) -> Result<Self, SprsError> {
+        let symbolic = LuSymbolic::new_perm(mat.view(), col_perm);
+        symbolic.factor(mat, N::one())
+    }
+
+    /// Solve the system A x = rhs
+    ///
+    /// # Panics
+    ///
+    /// * if the dimension of rhs does not match the decomposition
+    pub fn solve<'a, V>(&self, rhs: &V) -> Vec<N>
+    where
+        N: 'a,
+        V: Deref<Target = [N]>,
+    {
+        let mut x = &self.row_perm * &rhs[..];
+        // the factors are triangular with a non-zero diagonal by construction
+        trisolve::lsolve_csc_dense_rhs(self.l.view(), &mut x[..])
+            .expect("L has a unit diagonal");
+        trisolve::usolve_csc_dense_rhs(self.u.view(), &mut x[..])
+            .expect("U has a non-zero diagonal");
+        let q_inv = self.col_perm.inv();
+        &q_inv * &x
+    }
+
+    /// The unit lower triangular factor L, in CSC format
+    pub fn l(&self) -> CsMatViewI<N, I> {
+        self.l.view()
+    }
+
+    /// The upper triangular factor U, in CSC format
+    pub fn u(&self) -> CsMatViewI<N, I> {
+        self.u.view()
+    }
+
+    /// The row permutation P chosen by the pivoting: the `k`-th row of
+    /// `P A Q` is the row `row_perm().at(k)` of `A`.
+    pub fn row_perm(&self) -> PermViewI<I> {
+        self.row_perm.view()
+    }
+
+    /// The column permutation Q: the `k`-th column of `P A Q` is the
+    /// column `col_perm().at(k)` of `A`.
+    pub fn col_perm(&self) -> PermViewI<I> {
+        self.col_perm.view()
+    }
+
+    /// The size of the linear system associated with this decomposition
+    #[inline]
+    pub fn problem_size(&self) -> usize {
+        self.u.rows()
+    }
+
+    /// The number of non-zero entries in L and U, including their diagonals
+    #[inline]
+    pub fn nnz(&self) -> usize {
+        self.l.nnz() + self.u.nnz()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LuSymbolic, SparseLu};
+    use sprs::errors::SprsError;
+    use sprs::{
+        transform_mat_aq, transform_mat_pa, CsMat, Permutation, TriMat,
+    };
+
+    /// Check that P A Q = L U
+    fn check_factors(mat: &CsMat<f64>, lu: &SparseLu<f64, usize>) {
+        let aq = transform_mat_aq(mat.view(), lu.col_perm().reborrow_inv());
+        let paq = transform_mat_pa(aq.view(), lu.row_perm().reborrow());
+        let prod = &lu.l() * &lu.u();
+        let diff = &prod - &paq;
+        assert!(diff.data().iter().all(|d| d.abs() < 1e-12));
+        for (col, vec) in lu.l().outer_iterator().enumerate() {
+            assert_eq!(vec.get(col), Some(&1.));
+            assert!(vec.indices().iter().all(|&row| row >= col));
+        }
+        for (col, vec) in lu.u().outer_iterator().enumerate() {
+            assert!(vec.indices().iter().all(|&row| row <= col));
+        }
+    }
+
+    /// Non symmetric matrix, with some zero diagonal entries
+    fn test_mat(n: usize) -> CsMat<f64> {
+        let mut tri = TriMat::new((n, n));
+        for i in 0..n {
+            if i % 3 != 1 {
+                tri.add_triplet(i, i, 1. + (i % 4) as f64);
+            }
+            if i + 1 < n {
+                tri.add_triplet(i, i + 1, 2.);
+                tri.add_triplet(i + 1, i, -1.5);
+            }
+            if i + 5 < n {
+                tri.add_triplet(i + 5, i, 0.5);
+            }
+        }
+        tri.to_csc()
+    }
+
+    #[test]
+    fn lu_partial_pivoting_bounds_l() {
+        let mat = test_mat(30);
+        let lu = SparseLu::new(mat.view()).unwrap();
+        check_factors(&mat, &lu);
+        for vec in lu.l().outer_iterator() {
+            assert!(vec.data().iter().all(|v| v.abs() <= 1.));
+        }
+    }
+
+    #[test]
+    fn lu_requires_pivoting() {
+        // | 0 1 2 |
+        // | 3 0 4 |
+        // | 5 6 0 |
+        let mat = CsMat::new(
+            (3, 3),
+            vec![0, 2, 4, 6],
+            vec![1, 2, 0, 2, 0, 1],
+            vec![1., 2., 3., 4., 5., 6.],
+        );
+        let lu = SparseLu::new(mat.view()).unwrap();
+        check_factors(&mat, &lu);
+        let x = lu.solve(&vec![8., 15., 17.]);
+        let expected = [1., 2., 3.];
+        for (xi, ei) in x.iter().zip(&expected) {
+            assert!((xi - ei).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn lu_col_perm() {
+        let mat = test_mat(20);
+        let x0: Vec<f64> = (0..20).map(|i| (i as f64 * 0.4).sin()).collect();
+        let mut b = vec![0.; 20];
+        for (&val, (row, col)) in mat.iter() {
+            b[row] += val * x0[col];
+        }
+        let col_perm =
+            Permutation::new((0..20).map(|i| (i * 7) % 20).collect());
+        for lu in &[
+            SparseLu::new(mat.view()).unwrap(),
+            SparseLu::new(mat.to_csr().view()).unwrap(),
+            SparseLu::new_perm(mat.view(), col_perm).unwrap(),
+        ] {
+            check_factors(&mat, lu);
+            let x = lu.solve(&b);
+            for (xi, ei) in x.iter().zip(&x0) {
+                assert!((xi - ei).abs() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn lu_pivot_threshold() {
+        // diagonally weak matrix: partial pivoting swaps rows, while a small
+        // threshold keeps the diagonal pivots
+        let mat = CsMat::new_csc(
+            (2, 2),
+            vec![0, 2, 4],
+            vec![0, 1, 0, 1],
+            vec![1., 2., 1., 3.],
+        );
+        let symbolic = LuSymbolic::new(mat.view());
+        let lu = symbolic.factor(mat.view(), 1.).unwrap();
+        check_factors(&mat, &lu);
+        assert_eq!(lu.row_perm().vec(), vec![1, 0]);
+        let lu = symbolic.factor(mat.view(), 0.1).unwrap();
+        check_factors(&mat, &lu);
+        assert_eq!(lu.row_perm().vec(), vec![0, 1]);
+        let x = lu.solve(&vec![2., 5.]);
+        assert!((x[0] - 1.).abs() < 1e-12 && (x[1] - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lu_singular() {
+        // the third column is the sum of the first two
+        let mat = CsMat::new_csc(
+            (3, 3),
+            vec![0, 2, 4, 7],
+            vec![0, 1, 1, 2, 0, 1, 2],
+            vec![1., 2., 3., 4., 1., 5., 4.],
+        );
+        assert_eq!(
+            SparseLu::new(mat.view()).unwrap_err(),
+            SprsError::SingularMatrix
+        );
+        // structurally singular
+        let mat: CsMat<f64> =
+            CsMat::new_csc((2, 2), vec![0, 2, 2], vec![0, 1], vec![1., 2.]);
+        assert_eq!(
+            SparseLu::new(mat.view()).unwrap_err(),
+            SprsError::SingularMatrix
+        );
+    }
+}
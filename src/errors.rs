@@ -8,6 +8,8 @@ pub enum SprsError {
     NonSortedIndices,
     UnsortedIndptr,
     SingularMatrix,
+    NonSymmetricMatrix,
+    NotPositiveDefinite,
 }
 
 use self::SprsError::*;
@@ -18,6 +20,8 @@ impl SprsError {
             NonSortedIndices => "a vector's indices are not sorted",
             UnsortedIndptr => "indptr is not sorted",
             SingularMatrix => "matrix is singular",
+            NonSymmetricMatrix => "matrix is not symmetric",
+            NotPositiveDefinite => "matrix is not positive definite",
         }
     }
 }
@@ -0,0 +1,883 @@
+///! Graph algorithms on the non-zero pattern of sparse matrices
+///!
+///! A square sparse matrix is seen as the adjacency matrix of a directed
+///! graph, where the neighbours of node `i` are the inner indices of the
+///! `i`-th outer vector. For a CSR matrix, there is thus an edge from `i`
+///! to `j` when `A[i, j]` is non-zero, and the edges are reversed for a CSC
+///! matrix. The graph of a structurally symmetric matrix is undirected
+///! and does not depend on the storage. Only the non-zero pattern of the
+///! matrix is used.
+///!
+///! The strongly connected components and a maximum transversal give the
+///! block triangular form of a non-symmetric matrix, whose diagonal blocks
+///! can be factored independently, as in the KLU solver.
+///!
+///! References:
+///!
+///! - R. Tarjan, Depth-first search and linear graph algorithms, SIAM J.
+///!   Comput. 1(2), 146-160 (1972).
+///! - I. S. Duff, On algorithms for obtaining a maximum transversal, ACM
+///!   Trans. Math. Softw. 7(3), 315-330 (1981).
+use indexing::SpIndex;
+use sparse::permutation::{PermOwnedI, PermViewI};
+use sparse::CsMatViewI;
+use stack::DStack;
+
+/// Breadth first traversal of the nodes reachable from `start`.
+///
+/// The nodes are returned in the order they are visited, the neighbours
+/// of a node being visited in the order of the inner indices.
+///
+/// # Panics
+///
+/// * if mat is not square
+/// * if `start` is out of bounds
+pub fn bfs<N, I>(mat: CsMatViewI<N, I>, start: usize) -> Vec<usize>
+where
+    I: SpIndex,
+{
+    let n = mat.rows();
+    assert!(mat.cols() == n, "matrix should be square");
+    assert!(start < n, "start node out of bounds");
+    let mut visited = vec![false; n];
+    visited[start] = true;
+    let mut order = vec![start];
+    let mut head = 0;
+    while head < order.len() {
+        let node = order[head];
+        head += 1;
+        let vec = mat.outer_view(node).expect("node is in bounds");
+        for (neighbour, _) in vec.iter() {
+            if !visited[neighbour] {
+                visited[neighbour] = true;
+                order.push(neighbour);
+            }
+        }
+    }
+    order
+}
+
+/// The orders in which a depth first search visits the nodes of a graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DfsOrders {
+    preorder: Vec<usize>,
+    postorder: Vec<usize>,
+}
+
+impl DfsOrders {
+    /// The nodes in the order they are discovered
+    pub fn preorder(&self) -> &[usize] {
+        &self.preorder
+    }
+
+    /// The nodes in the order they are finished, ie after all their
+    /// descendants in the search tree
+    pub fn postorder(&self) -> &[usize] {
+        &self.postorder
+    }
+}
+
+/// Depth first traversal of the nodes reachable from `start`.
+///
+/// The neighbours of a node are visited in the order of the inner indices.
+///
+/// # Panics
+///
+/// * if mat is not square
+/// * if `start` is out of bounds
+pub fn dfs<N, I>(mat: CsMatViewI<N, I>, start: usize) -> DfsOrders
+where
+    I: SpIndex,
+{
+    let n = mat.rows();
+    assert!(mat.cols() == n, "matrix should be square");
+    assert!(start < n, "start node out of bounds");
+    let mut visited = vec![false; n];
+    // position of the next neighbour to explore for each node
+    let mut next_neighbour = vec![0; n];
+    // the left stack holds the path from the start to the current node,
+    // and the right stack the finished nodes
+    let mut dstack = DStack::with_capacity(2 * n);
+    let mut preorder = Vec::new();
+    visited[start] = true;
+    preorder.push(start);
+    dstack.push_left(start);
+    while let Some(node) = dstack.pop_left() {
+        let vec = mat.outer_view(node).expect("node is in bounds");
+        let neighbours = vec.indices();
+        let mut next = None;
+        while next_neighbour[node] < neighbours.len() {
+            let neighbour = neighbours[next_neighbour[node]].index();
+            next_neighbour[node] += 1;
+            if !visited[neighbour] {
+                next = Some(neighbour);
+                break;
+            }
+        }
+        match next {
+            Some(neighbour) => {
+                dstack.push_left(node);
+                visited[neighbour] = true;
+                preorder.push(neighbour);
+                dstack.push_left(neighbour);
+            }
+            None => dstack.push_right(node),
+        }
+    }
+    let postorder = dstack.iter_right().rev().cloned().collect();
+    DfsOrders {
+        preorder: preorder,
+        postorder: postorder,
+    }
+}
+
+/// Rooted level structure of a connected component, ie its nodes grouped
+/// by their distance to the root.
+///
+/// The structure can be rebuilt from different roots without allocating.
+#[derive(Debug, Clone)]
+pub struct LevelStructure {
+    nodes: Vec<usize>,
+    level_ptr: Vec<usize>,
+    // nodes having marks[i] == stamp belong to the current structure
+    marks: Vec<usize>,
+    stamp: usize,
+}
+
+impl LevelStructure {
+    /// Create an empty level structure for a graph with `n` nodes
+    pub fn new(n: usize) -> LevelStructure {
+        LevelStructure {
+            nodes: Vec::with_capacity(n),
+            level_ptr: Vec::new(),
+            marks: vec![0; n],
+            stamp: 0,
+        }
+    }
+
+    /// Compute the level structure rooted at the given node
+    ///
+    /// # Panics
+    ///
+    /// * if the number of nodes of the graph of `mat` is not the one
+    ///   given at construction
+    /// * if `root` is out of bounds
+    pub fn build<N, I>(&mut self, mat: &CsMatViewI<N, I>, root: usize)
+    where
+        I: SpIndex,
+    {
+        assert!(mat.outer_dims() == self.marks.len(), "Dimension mismatch");
+        self.stamp += 1;
+        self.nodes.clear();
+        self.level_ptr.clear();
+        self.level_ptr.push(0);
+        self.nodes.push(root);
+        self.marks[root] = self.stamp;
+        let mut level_start = 0;
+        while level_start < self.nodes.len() {
+            let level_end = self.nodes.len();
+            self.level_ptr.push(level_end);
+            for k in level_start..level_end {
+                let node = self.nodes[k];
+                let vec = mat.outer_view(node).expect("node is in bounds");
+                for (neighbour, _) in vec.iter() {
+                    if self.marks[neighbour] != self.stamp {
+                        self.marks[neighbour] = self.stamp;
+                        self.nodes.push(neighbour);
+                    }
+                }
+            }
+            level_start = level_end;
+        }
+    }
+
+    /// The root of the structure
+    ///
+    /// # Panics
+    ///
+    /// * if the structure has not been built
+    pub fn root(&self) -> usize {
+        self.nodes[0]
+    }
+
+    /// The number of levels, ie the eccentricity of the root plus one
+    pub fn nb_levels(&self) -> usize {
+        self.level_ptr.len().saturating_sub(1)
+    }
+
+    /// The nodes at distance `level` from the root
+    ///
+    /// # Panics
+    ///
+    /// * if `level` is not smaller than the number of levels
+    pub fn level(&self, level: usize) -> &[usize] {
+        assert!(level < self.nb_levels(), "level out of bounds");
+        &self.nodes[self.level_ptr[level]..self.level_ptr[level + 1]]
+    }
+
+    /// The nodes of the last level, the farthest from the root
+    ///
+    /// # Panics
+    ///
+    /// * if the structure has not been built
+    pub fn last_level(&self) -> &[usize] {
+        let nb_levels = self.nb_levels();
+        assert!(nb_levels > 0, "the level structure is empty");
+        self.level(nb_levels - 1)
+    }
+
+    /// The nodes of the structure, level by level, in breadth first order
+    pub fn nodes(&self) -> &[usize] {
+        &self.nodes
+    }
+
+    /// The number of nodes of the largest level
+    pub fn width(&self) -> usize {
+        self.level_ptr
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Compute the level structure of the graph of `mat` rooted at `root`
+///
+/// # Panics
+///
+/// * if mat is not square
+/// * if `root` is out of bounds
+pub fn level_structure<N, I>(
+    mat: CsMatViewI<N, I>,
+    root: usize,
+) -> LevelStructure
+where
+    I: SpIndex,
+{
+    assert!(mat.rows() == mat.cols(), "matrix should be square");
+    let mut levels = LevelStructure::new(mat.rows());
+    levels.build(&mat, root);
+    levels
+}
+
+/// The connected components of a graph, either weakly or strongly
+/// connected
+#[derive(Debug, Clone)]
+pub struct Components<I> {
+    labels: Vec<usize>,
+    component_ptr: Vec<usize>,
+    nodes: Vec<usize>,
+    perm: PermOwnedI<I>,
+}
+
+impl<I: SpIndex> Components<I> {
+    /// The number of connected components
+    pub fn nb_components(&self) -> usize {
+        self.component_ptr.len() - 1
+    }
+
+    /// The component of each node
+    pub fn labels(&self) -> &[usize] {
+        &self.labels
+    }
+
+    /// The nodes of the given component, sorted
+    ///
+    /// # Panics
+    ///
+    /// * if `component` is out of bounds
+    pub fn component(&self, component: usize) -> &[usize] {
+        let start = self.component_ptr[component];
+        let end = self.component_ptr[component + 1];
+        &self.nodes[start..end]
+    }
+
+    /// The permutation `P` grouping the nodes by component: `P A P^T` is
+    /// block diagonal, the nodes of component `k` being at the positions
+    /// `component_ptr()[k]..component_ptr()[k + 1]`.
+    pub fn perm(&self) -> PermViewI<I> {
+        self.perm.view()
+    }
+
+    /// The positions of the components in `perm()`
+    pub fn component_ptr(&self) -> &[usize] {
+        &self.component_ptr
+    }
+
+    /// Group the nodes by their component label
+    fn from_labels(labels: Vec<usize>, nb_components: usize) -> Self {
+        let mut component_ptr = vec![0; nb_components + 1];
+        for &label in &labels {
+            component_ptr[label + 1] += 1;
+        }
+        for k in 0..nb_components {
+            component_ptr[k + 1] += component_ptr[k];
+        }
+        let mut next = component_ptr.clone();
+        let mut nodes = vec![0; labels.len()];
+        for (node, &label) in labels.iter().enumerate() {
+            nodes[next[label]] = node;
+            next[label] += 1;
+        }
+        let perm = nodes.iter().map(|&node| I::from_usize(node)).collect();
+        Components {
+            labels: labels,
+            component_ptr: component_ptr,
+            nodes: nodes,
+            perm: PermOwnedI::new(perm),
+        }
+    }
+}
+
+/// The non-zero pattern of the transpose of a matrix, as the positions of
+/// the outer vectors in the inner indices
+fn transposed_pattern<N, I>(mat: &CsMatViewI<N, I>) -> (Vec<usize>, Vec<usize>)
+where
+    I: SpIndex,
+{
+    let inner_dims = mat.inner_dims();
+    let mut transposed_ptr = vec![0; inner_dims + 1];
+    for &ind in mat.indices() {
+        transposed_ptr[ind.index() + 1] += 1;
+    }
+    for i in 0..inner_dims {
+        transposed_ptr[i + 1] += transposed_ptr[i];
+    }
+    let mut next = transposed_ptr.clone();
+    let mut transposed = vec![0; mat.nnz()];
+    for (outer, vec) in mat.outer_iterator().enumerate() {
+        for (inner, _) in vec.iter() {
+            transposed[next[inner]] = outer;
+            next[inner] += 1;
+        }
+    }
+    (transposed_ptr, transposed)
+}
+
+/// Compute the weakly connected components of the graph of `mat`, ie the
+/// connected components of the graph where the direction of the edges is
+/// ignored. For a structurally symmetric matrix, these are the connected
+/// components of its undirected graph.
+///
+/// The components are numbered by increasing smallest node.
+///
+/// # Panics
+///
+/// * if mat is not square
+pub fn connected_components<N, I>(mat: CsMatViewI<N, I>) -> Components<I>
+where
+    I: SpIndex,
+{
+    let n = mat.rows();
+    assert!(mat.cols() == n, "matrix should be square");
+    // pattern of the transposed matrix, to follow the edges backwards
+    let (transposed_ptr, transposed) = transposed_pattern(&mat);
+
+    let mut labels = vec![n; n];
+    let mut nb_components = 0;
+    if n > 0 {
+        // the left stack holds the nodes to explore
+        let mut dstack = DStack::with_capacity(2 * n);
+        for root in 0..n {
+            if labels[root] != n {
+                continue;
+            }
+            labels[root] = nb_components;
+            dstack.push_left(root);
+            while let Some(node) = dstack.pop_left() {
+                let vec = mat.outer_view(node).expect("node is in bounds");
+                let forward = vec.indices().iter().map(|ind| ind.index());
+                let backward = transposed
+                    [transposed_ptr[node]..transposed_ptr[node + 1]]
+                    .iter()
+                    .cloned();
+                for neighbour in forward.chain(backward) {
+                    if labels[neighbour] == n {
+                        labels[neighbour] = nb_components;
+                        dstack.push_left(neighbour);
+                    }
+                }
+            }
+            nb_components += 1;
+        }
+    }
+
+    Components::from_labels(labels, nb_components)
+}
+
+/// Compute the strongly connected components of the graph of `mat` using
+/// Tarjan's algorithm. Two nodes belong to the same strongly connected
+/// component if there is a path from each of them to the other.
+///
+/// The components are numbered in a topological order of the graph of
+/// the components, ie there is no edge from a node to a node of a
+/// previous component. For a CSR matrix, `P A P^T` is thus upper block
+/// triangular, where `P` is the permutation returned by `perm()`.
+///
+/// # Panics
+///
+/// * if mat is not square
+pub fn strongly_connected_components<N, I>(
+    mat: CsMatViewI<N, I>,
+) -> Components<I>
+where
+    I: SpIndex,
+{
+    let n = mat.rows();
+    assert!(mat.cols() == n, "matrix should be square");
+    let indptr = mat.indptr();
+    let indices = mat.indices();
+    tarjan(n, |node| {
+        &indices[indptr[node].index()..indptr[node + 1].index()]
+    })
+}
+
+/// Tarjan's algorithm on the graph whose neighbours of each node are
+/// given by `neighbours`
+fn tarjan<'a, J, F, I>(n: usize, neighbours: F) -> Components<I>
+where
+    J: 'a + SpIndex,
+    F: Fn(usize) -> &'a [J],
+    I: SpIndex,
+{
+    let mut labels = vec![n; n];
+    let mut nb_components = 0;
+    if n == 0 {
+        return Components::from_labels(labels, nb_components);
+    }
+    // discovery index of each node, and smallest index reachable from its
+    // subtree by following at most one back edge
+    let mut index = vec![n; n];
+    let mut low_link = vec![n; n];
+    let mut next_neighbour = vec![0; n];
+    // the left stack holds the path from the root to the current node, and
+    // the right stack the visited nodes not yet assigned to a component
+    let mut dstack = DStack::with_capacity(2 * n);
+    let mut counter = 0;
+    for root in 0..n {
+        if index[root] != n {
+            continue;
+        }
+        index[root] = counter;
+        low_link[root] = counter;
+        counter += 1;
+        dstack.push_right(root);
+        dstack.push_left(root);
+        while let Some(node) = dstack.pop_left() {
+            let node_neighbours = neighbours(node);
+            let mut next = None;
+            while next_neighbour[node] < node_neighbours.len() {
+                let neighbour = node_neighbours[next_neighbour[node]].index();
+                next_neighbour[node] += 1;
+                if index[neighbour] == n {
+                    next = Some(neighbour);
+                    break;
+                } else if labels[neighbour] == n {
+                    // the neighbour is still on the right stack
+                    low_link[node] = low_link[node].min(index[neighbour]);
+                }
+            }
+            if let Some(neighbour) = next {
+                dstack.push_left(node);
+                index[neighbour] = counter;
+                low_link[neighbour] = counter;
+                counter += 1;
+                dstack.push_right(neighbour);
+                dstack.push_left(neighbour);
+                continue;
+            }
+            if low_link[node] == index[node] {
+                // node is the root of a component
+                while let Some(member) = dstack.pop_right() {
+                    labels[member] = nb_components;
+                    if member == node {
+                        break;
+                    }
+                }
+                nb_components += 1;
+            }
+            if let Some(parent) = dstack.pop_left() {
+                low_link[parent] = low_link[parent].min(low_link[node]);
+                dstack.push_left(parent);
+            }
+        }
+    }
+    // the components are found in reverse topological order
+    for label in &mut labels {
+        *label = nb_components - 1 - *label;
+    }
+    Components::from_labels(labels, nb_components)
+}
+
+/// Compute a maximum transversal of a matrix, ie a maximum set of non-zero
+/// entries with no two entries in the same row or column, using depth
+/// first searches for augmenting paths with a lookahead, as in Duff's MC21
+/// algorithm.
+///
+/// The transversal is returned as the row matched to each column, if any.
+/// Its size is the structural rank of the matrix, and the matrix is
+/// structurally singular if it is smaller than the number of columns.
+pub fn maximum_transversal<N, I>(mat: CsMatViewI<N, I>) -> Vec<Option<usize>>
+where
+    I: SpIndex,
+{
+    let outer_matches = match_outer(&mat);
+    if mat.is_csc() {
+        return outer_matches;
+    }
+    let mut col_matches = vec![None; mat.cols()];
+    for (row, col) in outer_matches.into_iter().enumerate() {
+        if let Some(col) = col {
+            col_matches[col] = Some(row);
+        }
+    }
+    col_matches
+}
+
+/// Maximum matching of the outer vectors with the inner indices
+fn match_outer<N, I>(mat: &CsMatViewI<N, I>) -> Vec<Option<usize>>
+where
+    I: SpIndex,
+{
+    let outer_dims = mat.outer_dims();
+    let mut outer_matches = vec![None; outer_dims];
+    let mut inner_matches = vec![None; mat.inner_dims()];
+    if outer_dims == 0 {
+        return outer_matches;
+    }
+    // position of the next inner index to try for a cheap assignment, which
+    // never needs to be reset
+    let mut lookahead = vec![0; outer_dims];
+    let mut next_inner = vec![0; outer_dims];
+    // the root of the last search visiting each outer vector
+    let mut marks = vec![outer_dims; outer_dims];
+    // the left stack holds the current alternating path
+    let mut dstack = DStack::with_capacity(2 * outer_dims);
+    for root in 0..outer_dims {
+        marks[root] = root;
+        next_inner[root] = 0;
+        dstack.push_left(root);
+        let mut free_inner = None;
+        while let Some(outer) = dstack.pop_left() {
+            let vec = mat.outer_view(outer).expect("outer is in bounds");
+            let inners = vec.indices();
+            while lookahead[outer] < inners.len() {
+                let inner = inners[lookahead[outer]].index();
+                lookahead[outer] += 1;
+                if inner_matches[inner].is_none() {
+                    free_inner = Some(inner);
+                    break;
+                }
+            }
+            if free_inner.is_some() {
+                dstack.push_left(outer);
+                break;
+            }
+            // all the inner indices are matched, look for an augmenting
+            // path through the outer vectors they are matched to
+            while next_inner[outer] < inners.len() {
+                let inner = inners[next_inner[outer]].index();
+                next_inner[outer] += 1;
+                let other = inner_matches[inner].expect("inner is matched");
+                if marks[other] != root {
+                    marks[other] = root;
+                    next_inner[other] = 0;
+                    dstack.push_left(outer);
+                    dstack.push_left(other);
+                    break;
+                }
+            }
+        }
+        if let Some(mut inner) = free_inner {
+            // flip the matches along the augmenting path
+            while let Some(outer) = dstack.pop_left() {
+                let previous = outer_matches[outer];
+                outer_matches[outer] = Some(inner);
+                inner_matches[inner] = Some(outer);
+                if let Some(previous) = previous {
+                    inner = previous;
+                }
+            }
+        }
+    }
+    outer_matches
+}
+
+/// The block upper triangular form of a square matrix, obtained by
+/// permuting its rows and columns.
+#[derive(Debug, Clone)]
+pub struct BlockTriangularForm<I> {
+    row_perm: PermOwnedI<I>,
+    col_perm: PermOwnedI<I>,
+    block_ptr: Vec<usize>,
+    structural_rank: usize,
+}
+
+impl<I: SpIndex> BlockTriangularForm<I> {
+    /// The row permutation: the `k`-th row of the permuted matrix is the
+    /// row `row_perm().at(k)` of `A`.
+    pub fn row_perm(&self) -> PermViewI<I> {
+        self.row_perm.view()
+    }
+
+    /// The column permutation: the `k`-th column of the permuted matrix is
+    /// the column `col_perm().at(k)` of `A`.
+    pub fn col_perm(&self) -> PermViewI<I> {
+        self.col_perm.view()
+    }
+
+    /// The number of diagonal blocks
+    pub fn nb_blocks(&self) -> usize {
+        self.block_ptr.len() - 1
+    }
+
+    /// The boundaries of the diagonal blocks: the rows and columns of the
+    /// `k`-th block are in `block_ptr()[k]..block_ptr()[k + 1]`.
+    pub fn block_ptr(&self) -> &[usize] {
+        &self.block_ptr
+    }
+
+    /// The structural rank of the matrix, which is its dimension if the
+    /// diagonal of the permuted matrix is free of zeros.
+    pub fn structural_rank(&self) -> usize {
+        self.structural_rank
+    }
+}
+
+/// Compute the block upper triangular form of a square matrix.
+///
+/// The rows are first permuted to put a maximum transversal on the
+/// diagonal, then the strongly connected components of the graph of the
+/// permuted matrix give its diagonal blocks, below which all the entries
+/// are zeros. A linear system can then be solved by factoring each
+/// diagonal block independently and by a block back substitution.
+///
+/// For a structurally nonsingular matrix, the diagonal blocks are
+/// irreducible, and this is the fine decomposition of Dulmage and
+/// Mendelsohn.
+///
+/// If the matrix is structurally singular, the transversal is completed
+/// by matching the unmatched columns with the unmatched rows in increasing
+/// order, regardless of the non-zero pattern. Some diagonal entries of the
+/// permuted matrix are then zeros, the blocks depend on this arbitrary
+/// completion and are not necessarily irreducible, and the coarse
+/// decomposition into under-determined, square and over-determined parts
+/// is not computed. The structural rank tells whether this is the case.
+///
+/// # Panics
+///
+/// * if mat is not square
+pub fn block_triangular_form<N, I>(
+    mat: CsMatViewI<N, I>,
+) -> BlockTriangularForm<I>
+where
+    I: SpIndex,
+{
+    let n = mat.rows();
+    assert!(mat.cols() == n, "matrix should be square");
+    let col_matches = maximum_transversal(mat.view());
+    let structural_rank = col_matches.iter().filter(|m| m.is_some()).count();
+    // complete the transversal with the unmatched rows
+    let mut matched_rows = vec![false; n];
+    for &row in col_matches.iter().filter_map(|m| m.as_ref()) {
+        matched_rows[row] = true;
+    }
+    let mut free_rows = (0..n).filter(|&row| !matched_rows[row]);
+    let row_of_col: Vec<usize> = col_matches
+        .iter()
+        .map(|m| m.or_else(|| free_rows.next()).expect("square matrix"))
+        .collect();
+
+    // the rows of the matrix with the transversal on the diagonal, whose
+    // node k is the row row_of_col[k] and the column k of the matrix
+    let (row_ptr, row_indices) = if mat.is_csr() {
+        let indptr = mat.indptr().iter().map(|i| i.index()).collect();
+        let indices = mat.indices().iter().map(|i| i.index()).collect();
+        (indptr, indices)
+    } else {
+        transposed_pattern(&mat)
+    };
+    let components: Components<I> = tarjan(n, |node| {
+        let row = row_of_col[node];
+        &row_indices[row_ptr[row]..row_ptr[row + 1]]
+    });
+
+    let perm = components.perm();
+    let row_perm = (0..n)
+        .map(|k| I::from_usize(row_of_col[perm.at(k)]))
+        .collect();
+    let col_perm = (0..n).map(|k| I::from_usize(perm.at(k))).collect();
+    BlockTriangularForm {
+        row_perm: PermOwnedI::new(row_perm),
+        col_perm: PermOwnedI::new(col_perm),
+        block_ptr: components.component_ptr().to_vec(),
+        structural_rank: structural_rank,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BlockTriangularForm;
+    use sparse::permutation::transform_mat_papt;
+    use sparse::{CsMat, TriMat};
+    use test_data::grid_laplacian;
+
+    /// A directed graph with the edges 0 -> 1, 0 -> 2, 1 -> 3, 2 -> 3,
+    /// 3 -> 0, and a separate edge 5 -> 4.
+    fn directed_graph() -> CsMat<f64> {
+        let mut tri = TriMat::new((6, 6));
+        for &(i, j) in &[(0, 1), (0, 2), (1, 3), (2, 3), (3, 0), (5, 4)] {
+            tri.add_triplet(i, j, 1.);
+        }
+        tri.to_csr()
+    }
+
+    #[test]
+    fn traversals() {
+        let mat = directed_graph();
+        assert_eq!(super::bfs(mat.view(), 0), vec![0, 1, 2, 3]);
+        assert_eq!(super::bfs(mat.view(), 3), vec![3, 0, 1, 2]);
+        assert_eq!(super::bfs(mat.view(), 4), vec![4]);
+        let orders = super::dfs(mat.view(), 0);
+        assert_eq!(orders.preorder(), &[0, 1, 3, 2]);
+        assert_eq!(orders.postorder(), &[3, 1, 2, 0]);
+        // the edges are reversed in CSC storage
+        let csc = mat.to_csc();
+        assert_eq!(super::bfs(csc.view(), 0), vec![0, 3, 1, 2]);
+        assert_eq!(super::bfs(csc.view(), 4), vec![4, 5]);
+        let orders = super::dfs(csc.view(), 0);
+        assert_eq!(orders.preorder(), &[0, 3, 1, 2]);
+        assert_eq!(orders.postorder(), &[1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn level_structure_grid() {
+        let mat = grid_laplacian(4, 6);
+        let levels = super::level_structure(mat.view(), 0);
+        assert_eq!(levels.root(), 0);
+        // the levels are the anti-diagonals of the grid
+        assert_eq!(levels.nb_levels(), 9);
+        assert_eq!(levels.level(1), &[1, 6]);
+        assert_eq!(levels.last_level(), &[23]);
+        assert_eq!(levels.width(), 4);
+        assert_eq!(levels.nodes().len(), 24);
+        assert_eq!(levels.nodes(), &super::bfs(mat.view(), 0)[..]);
+    }
+
+    #[test]
+    fn components() {
+        let mat = directed_graph();
+        let components = super::connected_components(mat.view());
+        assert_eq!(components.nb_components(), 2);
+        assert_eq!(components.labels(), &[0, 0, 0, 0, 1, 1]);
+        assert_eq!(components.component(1), &[4, 5]);
+
+        // two interleaved paths and an isolated node
+        let mut tri = TriMat::new((7, 7));
+        for &(i, j) in &[(0, 2), (2, 4), (4, 6), (1, 5)] {
+            tri.add_triplet(i, j, 1.);
+            tri.add_triplet(j, i, 1.);
+        }
+        let mat: CsMat<f64> = tri.to_csc();
+        let components = super::connected_components(mat.view());
+        assert_eq!(components.nb_components(), 3);
+        assert_eq!(components.labels(), &[0, 1, 0, 2, 0, 1, 0]);
+        assert_eq!(components.component_ptr(), &[0, 4, 6, 7]);
+        assert_eq!(components.perm().vec(), vec![0, 2, 4, 6, 1, 5, 3]);
+        let blocks = transform_mat_papt(mat.view(), components.perm());
+        for (row, vec) in blocks.outer_iterator().enumerate() {
+            for (col, _) in vec.iter() {
+                let block = |i| {
+                    components.component_ptr()[1..]
+                        .iter()
+                        .position(|&end| i < end)
+                };
+                assert_eq!(block(row), block(col));
+            }
+        }
+
+        let empty: CsMat<f64> = CsMat::zero((0, 0));
+        let components = super::connected_components(empty.view());
+        assert_eq!(components.nb_components(), 0);
+    }
+
+    #[test]
+    fn strongly_connected() {
+        let mat = directed_graph();
+        let components = super::strongly_connected_components(mat.view());
+        assert_eq!(components.nb_components(), 3);
+        assert_eq!(components.labels(), &[2, 2, 2, 2, 1, 0]);
+        assert_eq!(components.component(2), &[0, 1, 2, 3]);
+        assert_eq!(components.perm().vec(), vec![5, 4, 0, 1, 2, 3]);
+        // reversing the edges reverses the topological order
+        let csc = mat.to_csc();
+        let components = super::strongly_connected_components(csc.view());
+        assert_eq!(components.labels(), &[2, 2, 2, 2, 0, 1]);
+    }
+
+    #[test]
+    fn maximum_transversal() {
+        // the first column has to give up its first row
+        let mat =
+            CsMat::new_csc((2, 2), vec![0, 2, 3], vec![0, 1, 0], vec![1.; 3]);
+        let expected = vec![Some(1), Some(0)];
+        assert_eq!(super::maximum_transversal(mat.view()), expected);
+        assert_eq!(super::maximum_transversal(mat.to_csr().view()), expected);
+
+        let mut tri = TriMat::new((3, 3));
+        for &(i, j) in &[(0, 0), (1, 0), (2, 1), (2, 2)] {
+            tri.add_triplet(i, j, 1.);
+        }
+        let singular: CsMat<f64> = tri.to_csr();
+        let matches = super::maximum_transversal(singular.view());
+        assert_eq!(matches.iter().filter(|m| m.is_some()).count(), 2);
+        let btf = super::block_triangular_form(singular.view());
+        assert_eq!(btf.structural_rank(), 2);
+        check_btf(&singular, &btf);
+    }
+
+    /// Check that a block triangular form is block upper triangular and
+    /// has a zero-free diagonal up to the structural rank
+    fn check_btf(mat: &CsMat<f64>, btf: &BlockTriangularForm<usize>) {
+        let (row_perm, col_perm) = (btf.row_perm(), btf.col_perm());
+        let block = |k: usize| {
+            btf.block_ptr()[1..]
+                .iter()
+                .position(|&end| k < end)
+                .unwrap()
+        };
+        for (_, (row, col)) in mat.iter() {
+            assert!(block(row_perm.at_inv(row)) <= block(col_perm.at_inv(col)));
+        }
+        let nb_zero_diags = (0..mat.rows())
+            .filter(|&k| mat.get(row_perm.at(k), col_perm.at(k)).is_none())
+            .count();
+        assert_eq!(nb_zero_diags, mat.rows() - btf.structural_rank());
+    }
+
+    #[test]
+    fn block_triangular_form() {
+        // irreducible diagonal blocks {0, 1}, {2} and {3, 4, 5}, with
+        // scrambled rows and columns
+        let mut entries = vec![(0, 1), (1, 0), (3, 4), (4, 5), (5, 3)];
+        entries.extend((0..6).map(|i| (i, i)));
+        entries.extend(&[(0, 2), (1, 4), (2, 3), (2, 5)]);
+        let row_scramble = [3, 5, 0, 4, 1, 2];
+        let col_scramble = [1, 4, 5, 0, 2, 3];
+        let mut tri = TriMat::new((6, 6));
+        for &(i, j) in entries.iter() {
+            tri.add_triplet(row_scramble[i], col_scramble[j], 1.);
+        }
+        for mat in &[tri.to_csr(), tri.to_csc()] {
+            let btf = super::block_triangular_form(mat.view());
+            assert_eq!(btf.structural_rank(), 6);
+            assert_eq!(btf.nb_blocks(), 3);
+            assert_eq!(btf.block_ptr(), &[0, 2, 3, 6]);
+            check_btf(mat, &btf);
+        }
+
+        let empty: CsMat<f64> = CsMat::zero((0, 0));
+        let btf = super::block_triangular_form(empty.view());
+        assert_eq!(btf.nb_blocks(), 0);
+    }
+}
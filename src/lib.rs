@@ -12,10 +12,17 @@ It features a sparse matrix type, [**`CsMat`**](struct.CsMatBase.html), and a sp
 - sparse vector/sparse vector addition, dot product.
 - sparse matrix/dense matrix addition, multiplication.
 - sparse triangular solves.
+- iterative solvers for sparse linear systems.
+- eigensolvers for sparse matrices.
+- bandwidth reducing reordering (Reverse Cuthill-McKee).
+- graph traversals, connected components and block triangular form of the
+  sparsity structure.
 - powerful iteration over the sparse structure, enabling easy extension of the library.
 - matrix construction using the [triplet format](struct.TriMatBase.html),
   vertical and horizontal stacking, block construction.
 - sparse cholesky solver in the separate crate `sprs-ldl`.
+- sparse LU solver in the separate crate `sprs-lu`.
+- supernodal sparse Cholesky solver in the separate crate `sprs-cholesky`.
 - fully generic integer type for the storage of indices, enabling compact
   representations.
 - planned interoperability with existing sparse solvers such as SuiteSparse.
@@ -70,6 +77,7 @@ extern crate tempdir;
 
 pub mod array_backend;
 pub mod errors;
+pub mod graph;
 pub mod indexing;
 pub mod io;
 mod num_kinds;
@@ -94,7 +102,8 @@ pub use sparse::{
 pub use sparse::symmetric::is_symmetric;
 
 pub use sparse::permutation::{
-    PermOwned, PermOwnedI, PermView, PermViewI, Permutation,
+    transform_mat_aq, transform_mat_pa, transform_mat_papt, PermOwned,
+    PermOwnedI, PermView, PermViewI, Permutation,
 };
 
 pub use sparse::CompressedStorage::{self, CSC, CSR};
@@ -113,7 +122,7 @@ pub mod vec {
 }
 
 pub use sparse::construct::{
-    bmat, csc_from_dense, csr_from_dense, hstack, vstack,
+    bmat, csc_from_dense, csr_from_dense, grid_laplacian, hstack, vstack,
 };
 
 pub use sparse::to_dense::assign_to_dense;
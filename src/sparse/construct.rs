@@ -203,6 +203,59 @@ where
     csr_from_dense(m.reversed_axes(), epsilon).transpose_into()
 }
 
+/// Create the discrete laplacian on a regular grid, with Dirichlet boundary
+/// conditions, as a CSR matrix.
+///
+/// `shape` gives the number of nodes along each dimension of the grid, and
+/// the nodes are numbered in C-order, the last dimension varying the
+/// fastest. The sign of the laplacian is flipped to make it symmetric
+/// positive definite: a node has `2 * shape.len()` on the diagonal, and
+/// `-1` for each of its neighbours.
+pub fn grid_laplacian<N>(shape: &[usize]) -> CsMat<N>
+where
+    N: Num + Copy,
+{
+    let ndims = shape.len();
+    let n = shape.iter().fold(1, |acc, &size| acc * size);
+    let diag = (0..2 * ndims).fold(N::zero(), |acc, _| acc + N::one());
+    // the distance between the indices of neighbours along each dimension
+    let mut strides = vec![1; ndims];
+    for dim in (1..ndims).rev() {
+        strides[dim - 1] = strides[dim] * shape[dim];
+    }
+
+    let mut indptr = Vec::with_capacity(n + 1);
+    let mut indices = Vec::with_capacity((2 * ndims + 1) * n);
+    let mut data = Vec::with_capacity((2 * ndims + 1) * n);
+    indptr.push(0);
+    for ind in 0..n {
+        // the neighbours are visited by increasing index
+        for dim in 0..ndims {
+            if (ind / strides[dim]) % shape[dim] > 0 {
+                indices.push(ind - strides[dim]);
+                data.push(N::zero() - N::one());
+            }
+        }
+        indices.push(ind);
+        data.push(diag);
+        for dim in (0..ndims).rev() {
+            if (ind / strides[dim]) % shape[dim] + 1 < shape[dim] {
+                indices.push(ind + strides[dim]);
+                data.push(N::zero() - N::one());
+            }
+        }
+        indptr.push(indices.len());
+    }
+    CsMat {
+        storage: CompressedStorage::CSR,
+        nrows: n,
+        ncols: n,
+        indptr: indptr,
+        indices: indices,
+        data: data,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use ndarray::{arr2, Array};
@@ -410,4 +463,31 @@ mod test {
 
         assert_eq!(m_sparse, expected_output);
     }
+
+    #[test]
+    fn grid_laplacian() {
+        let lap: CsMat<f64> = super::grid_laplacian(&[2, 3]);
+        let expected = arr2(&[
+            [4., -1., 0., -1., 0., 0.],
+            [-1., 4., -1., 0., -1., 0.],
+            [0., -1., 4., 0., 0., -1.],
+            [-1., 0., 0., 4., -1., 0.],
+            [0., -1., 0., -1., 4., -1.],
+            [0., 0., -1., 0., -1., 4.],
+        ]);
+        assert_eq!(lap, super::csr_from_dense(expected.view(), 0.));
+
+        let lap: CsMat<f64> = super::grid_laplacian(&[3, 4, 5]);
+        assert_eq!(lap.rows(), 60);
+        assert!(lap.is_csr());
+        assert!(::is_symmetric(&lap));
+        let row_sums: Vec<_> = lap
+            .outer_iterator()
+            .map(|row| row.iter().fold(0., |acc, (_, &x)| acc + x))
+            .collect();
+        // a corner node has one neighbour along each dimension, an
+        // interior node has two
+        assert_eq!(row_sums[0], 3.);
+        assert_eq!(row_sums[20 + 2 * 5 + 3], 0.);
+    }
 }
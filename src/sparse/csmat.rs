@@ -1,4 +1,4 @@
-use num_traits::{Num, Zero};
+use num_traits::{Float, Num, Zero};
 ///! A sparse matrix in the Compressed Sparse Row/Column format
 ///
 /// In the CSR format, a matrix is a structure containing three vectors:
@@ -1129,6 +1129,86 @@ where
             inner_iter: self.indices.iter().zip(self.data.iter()).enumerate(),
         }
     }
+
+    /// The bandwidth of this matrix, ie the largest distance between a
+    /// non-zero entry and the diagonal: `max |i - j|` for `A[i, j] != 0`.
+    pub fn bandwidth(&self) -> usize {
+        self.iter()
+            .map(|(_, (row, col))| {
+                let (row, col) = (row.index(), col.index());
+                if row > col {
+                    row - col
+                } else {
+                    col - row
+                }
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The profile, or envelope size, of this matrix:
+    /// `sum_i (i - f_i)`, where `f_i` is the column of the first non-zero
+    /// entry of row `i`, or `i` if there is no non-zero entry on the left of
+    /// the diagonal. Only the lower triangular part is considered, which is
+    /// enough to describe the envelope of a structurally symmetric matrix.
+    pub fn profile(&self) -> usize {
+        let mut first_col: Vec<usize> = (0..self.rows()).collect();
+        for (_, (row, col)) in self.iter() {
+            let (row, col) = (row.index(), col.index());
+            if col < first_col[row] {
+                first_col[row] = col;
+            }
+        }
+        first_col
+            .iter()
+            .enumerate()
+            .map(|(row, &col)| row - col)
+            .sum()
+    }
+}
+
+/// # Norms of sparse matrices
+///
+/// These norms do not depend on the storage order of the matrix.
+impl<N, I, IptrStorage, IndStorage, DataStorage>
+    CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>
+where
+    N: Float,
+    I: SpIndex,
+    IptrStorage: Deref<Target = [I]>,
+    IndStorage: Deref<Target = [I]>,
+    DataStorage: Deref<Target = [N]>,
+{
+    /// The Frobenius norm of this matrix: `sqrt(sum_ij |A[i, j]|^2)`
+    pub fn frobenius_norm(&self) -> N {
+        self.data.iter().fold(N::zero(), |acc, &x| acc.hypot(x))
+    }
+
+    /// The 1-norm of this matrix, ie its largest absolute column sum:
+    /// `max_j sum_i |A[i, j]|`
+    pub fn one_norm(&self) -> N {
+        let mut col_sums = vec![N::zero(); self.cols()];
+        for (&val, (_, col)) in self.iter() {
+            col_sums[col.index()] = col_sums[col.index()] + val.abs();
+        }
+        col_sums.iter().fold(N::zero(), |acc, &x| acc.max(x))
+    }
+
+    /// The infinity norm of this matrix, ie its largest absolute row sum:
+    /// `max_i sum_j |A[i, j]|`
+    pub fn inf_norm(&self) -> N {
+        let mut row_sums = vec![N::zero(); self.rows()];
+        for (&val, (row, _)) in self.iter() {
+            row_sums[row.index()] = row_sums[row.index()] + val.abs();
+        }
+        row_sums.iter().fold(N::zero(), |acc, &x| acc.max(x))
+    }
+
+    /// The largest absolute value of the entries of this matrix:
+    /// `max_ij |A[i, j]|`. This is not a consistent matrix norm.
+    pub fn max_abs(&self) -> N {
+        self.data.iter().fold(N::zero(), |acc, &x| acc.max(x.abs()))
+    }
 }
 
 /// # Methods to convert between storage orders
@@ -1925,6 +2005,7 @@ impl<'a, N: 'a, I: 'a + SpIndex> Iterator for ChunkOuterBlocks<'a, N, I> {
 mod test {
     use super::CompressedStorage::{CSC, CSR};
     use errors::SprsError;
+    use sparse::permutation::PermOwned;
     use sparse::{CsMat, CsMatI, CsMatView};
     use test_data::{mat1, mat1_csc, mat1_times_2};
 
@@ -2362,6 +2443,56 @@ mod test {
         assert_eq!(mat_.data(), &[1.0f32, 1., 1., 1.]);
     }
 
+    #[test]
+    fn bandwidth_profile() {
+        // | 1 . 2 . |
+        // | . 3 . . |
+        // | 4 . 5 6 |
+        // | . . 7 8 |
+        let mat = CsMat::new(
+            (4, 4),
+            vec![0, 2, 3, 6, 8],
+            vec![0, 2, 1, 0, 2, 3, 2, 3],
+            vec![1., 2., 3., 4., 5., 6., 7., 8.],
+        );
+        assert_eq!(mat.bandwidth(), 2);
+        assert_eq!(mat.to_csc().bandwidth(), 2);
+        assert_eq!(mat.profile(), 3);
+        assert_eq!(mat.to_csc().profile(), 3);
+        let eye: CsMat<f64> = CsMat::eye(3);
+        assert_eq!(eye.bandwidth(), 0);
+        assert_eq!(eye.profile(), 0);
+        let empty: CsMat<f64> = CsMat::zero((0, 0));
+        assert_eq!(empty.bandwidth(), 0);
+        assert_eq!(empty.profile(), 0);
+    }
+
+    #[test]
+    fn norms() {
+        // | 1 . -2 . |
+        // | . 3  . . |
+        // | 4 . -5 6 |
+        let mat = CsMat::new(
+            (3, 4),
+            vec![0, 2, 3, 6],
+            vec![0, 2, 1, 0, 2, 3],
+            vec![1., -2., 3., 4., -5., 6.],
+        );
+        for mat in &[mat.to_owned(), mat.to_csc()] {
+            assert!((mat.frobenius_norm() - 91f64.sqrt()).abs() < 1e-12);
+            assert_eq!(mat.one_norm(), 7.);
+            assert_eq!(mat.inf_norm(), 15.);
+            assert_eq!(mat.max_abs(), 6.);
+            assert_eq!(mat.transpose_view().one_norm(), 15.);
+            assert_eq!(mat.transpose_view().inf_norm(), 7.);
+        }
+        let empty: CsMat<f64> = CsMat::zero((0, 3));
+        assert_eq!(empty.frobenius_norm(), 0.);
+        assert_eq!(empty.one_norm(), 0.);
+        assert_eq!(empty.inf_norm(), 0.);
+        assert_eq!(empty.max_abs(), 0.);
+    }
+
     #[test]
     fn iter() {
         let mat = CsMat::new_csc(
@@ -2377,4 +2508,48 @@ mod test {
         assert_eq!(iter.next(), Some((&1., (2, 2))));
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn outer_iterator_perm() {
+        // [1 2 0]
+        // [0 3 0]
+        // [4 0 5]
+        let csr = CsMat::new(
+            (3, 3),
+            vec![0, 2, 3, 5],
+            vec![0, 1, 1, 0, 2],
+            vec![1., 2., 3., 4., 5.],
+        );
+        let csc = csr.to_csc();
+        // this permutation is not its own inverse
+        let perm = PermOwned::new(vec![1, 2, 0]);
+
+        // the rows of a CSR matrix follow the permutation
+        let outer: Vec<_> = csr
+            .outer_iterator_perm(perm.view())
+            .map(|(i, row)| (i, row.indices().to_vec(), row.data().to_vec()))
+            .collect();
+        assert_eq!(
+            outer,
+            vec![
+                (1, vec![1], vec![3.]),
+                (2, vec![0, 2], vec![4., 5.]),
+                (0, vec![0, 1], vec![1., 2.]),
+            ]
+        );
+
+        // the columns of a CSC matrix follow the inverse permutation
+        let outer: Vec<_> = csc
+            .outer_iterator_perm(perm.view())
+            .map(|(j, col)| (j, col.indices().to_vec(), col.data().to_vec()))
+            .collect();
+        assert_eq!(
+            outer,
+            vec![
+                (2, vec![2], vec![5.]),
+                (0, vec![0, 2], vec![1., 4.]),
+                (1, vec![0, 1], vec![2., 3.]),
+            ]
+        );
+    }
 }
@@ -0,0 +1,689 @@
+///! Smoothed aggregation algebraic multigrid
+///!
+///! Multigrid methods solve the linear systems arising from the
+///! discretization of elliptic problems, such as the Poisson equation, with
+///! a number of iterations independent of the problem size. The error
+///! components which are not damped by a few relaxation sweeps are smooth,
+///! and are corrected by solving a smaller system on a coarser level.
+///!
+///! Algebraic multigrid builds the hierarchy of coarse levels from the
+///! matrix alone. In smoothed aggregation, the unknowns are grouped into
+///! aggregates of strongly connected unknowns, which become the unknowns of
+///! the coarse level. The tentative prolongator, which interpolates
+///! constant values inside each aggregate, is then smoothed by a Jacobi
+///! step, and the coarse operator is the Galerkin product `P^T A P`.
+///!
+///! The coarsest level is solved by a direct solver provided by the caller,
+///! for instance the `LdlNumeric` factorization of the `sprs-ldl` crate.
+///!
+///! Reference: P. Vanek, J. Mandel and M. Brezina, Algebraic multigrid by
+///! smoothed aggregation for second and fourth order elliptic problems,
+///! Computing 56(3), 179-196 (1996).
+use ndarray::{Array, ArrayView1, ArrayViewMut1};
+use num_traits::Float;
+
+use indexing::SpIndex;
+use sparse::linalg::iterative::Preconditioner;
+use sparse::linalg::relaxation::{Relaxation, SweepDirection};
+use sparse::prod::csr_mul_csr;
+use sparse::symmetric::is_symmetric;
+use sparse::{CsMatI, CsMatViewI};
+
+/// The multigrid cycle, which describes how often the coarse levels are
+/// visited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cycle {
+    /// Visit each coarse level once
+    V,
+    /// Visit each coarse level twice, which is more robust but more
+    /// expensive than the V-cycle
+    W,
+}
+
+/// Parameters of the construction and application of a smoothed
+/// aggregation hierarchy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmgOptions<N> {
+    strength_threshold: N,
+    prolongator_weight: N,
+    smoother: Relaxation<N>,
+    pre_sweeps: usize,
+    post_sweeps: usize,
+    cycle: Cycle,
+    max_levels: usize,
+    max_coarse: usize,
+}
+
+impl<N: Float> Default for AmgOptions<N> {
+    fn default() -> AmgOptions<N> {
+        AmgOptions::new()
+    }
+}
+
+impl<N: Float> AmgOptions<N> {
+    /// Default options: a V-cycle with one forward Gauss-Seidel sweep
+    /// before the coarse correction and one backward sweep after it,
+    /// a strength threshold of `0.08`, and at most 10 levels, the coarsest
+    /// one having no more than 300 unknowns when possible.
+    pub fn new() -> AmgOptions<N> {
+        let n = |x: f64| N::from(x).expect("representable constant");
+        AmgOptions {
+            strength_threshold: n(0.08),
+            prolongator_weight: n(4. / 3.),
+            smoother: Relaxation::gauss_seidel(SweepDirection::Forward),
+            pre_sweeps: 1,
+            post_sweeps: 1,
+            cycle: Cycle::V,
+            max_levels: 10,
+            max_coarse: 300,
+        }
+    }
+
+    /// Set the strength of connection threshold. An off-diagonal entry
+    /// `a_ij` is a strong connection if
+    /// `|a_ij| >= threshold * sqrt(|a_ii a_jj|)`.
+    ///
+    /// # Panics
+    ///
+    /// * if `threshold` is negative
+    pub fn with_strength_threshold(mut self, threshold: N) -> AmgOptions<N> {
+        assert!(threshold >= N::zero(), "threshold should be non-negative");
+        self.strength_threshold = threshold;
+        self
+    }
+
+    /// Set the weight of the Jacobi step smoothing the tentative
+    /// prolongator, relative to the inverse of an upper bound of the
+    /// spectral radius of `D^-1 A`. The default of `4 / 3` is optimal for
+    /// model problems.
+    pub fn with_prolongator_weight(mut self, weight: N) -> AmgOptions<N> {
+        self.prolongator_weight = weight;
+        self
+    }
+
+    /// Set the relaxation method used as a smoother. The sweeps after the
+    /// coarse correction use the reverse direction of the sweeps before it,
+    /// so that a cycle is a symmetric preconditioner for a symmetric
+    /// matrix when there are as many pre and post sweeps.
+    pub fn with_smoother(mut self, smoother: Relaxation<N>) -> AmgOptions<N> {
+        self.smoother = smoother;
+        self
+    }
+
+    /// Set the number of smoothing sweeps before and after the coarse
+    /// correction
+    pub fn with_sweeps(mut self, pre: usize, post: usize) -> AmgOptions<N> {
+        self.pre_sweeps = pre;
+        self.post_sweeps = post;
+        self
+    }
+
+    /// Set the multigrid cycle
+    pub fn with_cycle(mut self, cycle: Cycle) -> AmgOptions<N> {
+        self.cycle = cycle;
+        self
+    }
+
+    /// Set the maximum number of levels, including the finest one
+    ///
+    /// # Panics
+    ///
+    /// * if `max_levels` is zero
+    pub fn with_max_levels(mut self, max_levels: usize) -> AmgOptions<N> {
+        assert!(max_levels > 0, "at least one level is needed");
+        self.max_levels = max_levels;
+        self
+    }
+
+    /// Set the dimension below which a level is solved directly instead of
+    /// being coarsened
+    pub fn with_max_coarse(mut self, max_coarse: usize) -> AmgOptions<N> {
+        self.max_coarse = max_coarse;
+        self
+    }
+
+    /// The strength of connection threshold
+    pub fn strength_threshold(&self) -> N {
+        self.strength_threshold
+    }
+
+    /// The weight of the prolongator smoothing step
+    pub fn prolongator_weight(&self) -> N {
+        self.prolongator_weight
+    }
+
+    /// The relaxation method used as a smoother
+    pub fn smoother(&self) -> Relaxation<N> {
+        self.smoother
+    }
+
+    /// The number of smoothing sweeps before and after the coarse
+    /// correction
+    pub fn sweeps(&self) -> (usize, usize) {
+        (self.pre_sweeps, self.post_sweeps)
+    }
+
+    /// The multigrid cycle
+    pub fn cycle(&self) -> Cycle {
+        self.cycle
+    }
+
+    /// The maximum number of levels
+    pub fn max_levels(&self) -> usize {
+        self.max_levels
+    }
+
+    /// The dimension below which a level is solved directly
+    pub fn max_coarse(&self) -> usize {
+        self.max_coarse
+    }
+}
+
+/// A level of the hierarchy, with the operators to transfer vectors from
+/// and to the next coarser level
+#[derive(Debug)]
+struct Level<N, I: SpIndex> {
+    mat: CsMatI<N, I>,
+    prolongator: CsMatI<N, I>,
+    restrictor: CsMatI<N, I>,
+}
+
+/// A smoothed aggregation multigrid hierarchy, whose cycles can be used as
+/// a preconditioner for the solvers in `linalg::iterative`, or repeated as
+/// a solver.
+///
+/// The coarsest level is solved by a direct solver of type `S`.
+#[derive(Debug)]
+pub struct Amg<N, I: SpIndex, S> {
+    levels: Vec<Level<N, I>>,
+    coarse_mat: CsMatI<N, I>,
+    coarse_solver: S,
+    pre_smoother: Relaxation<N>,
+    post_smoother: Relaxation<N>,
+    pre_sweeps: usize,
+    post_sweeps: usize,
+    cycle: Cycle,
+}
+
+impl<N, I, S> Amg<N, I, S>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+    S: Preconditioner<N>,
+{
+    /// Build the multigrid hierarchy of a square matrix in CSR format.
+    ///
+    /// The matrix should be symmetric, or nearly so, with positive diagonal
+    /// entries, and have the constant vector as near null space, as for
+    /// discretizations of scalar elliptic problems. When the matrix is
+    /// symmetric, the rounding errors of the Galerkin products are removed
+    /// so that the coarse operators are exactly symmetric.
+    ///
+    /// `coarse_solver` is called once with the matrix of the coarsest
+    /// level, and should return a direct solver for it. With the `sprs-ldl`
+    /// crate, `LdlNumeric::new` is a good choice.
+    ///
+    /// # Panics
+    ///
+    /// * if `mat` is not square, or not stored in CSR format
+    /// * if a diagonal entry of `mat` is zero or missing
+    pub fn new<F>(
+        mat: CsMatViewI<N, I>,
+        options: &AmgOptions<N>,
+        coarse_solver: F,
+    ) -> Amg<N, I, S>
+    where
+        F: FnOnce(CsMatViewI<N, I>) -> S,
+    {
+        assert!(mat.rows() == mat.cols(), "matrix should be square");
+        assert!(mat.is_csr(), "multigrid requires a CSR matrix");
+        let symmetric = is_symmetric(&mat);
+        let mut levels = Vec::new();
+        let mut current = mat.to_owned();
+        while levels.len() + 1 < options.max_levels
+            && current.rows() > options.max_coarse
+        {
+            let prolongator = smoothed_prolongator(
+                &current.view(),
+                options.strength_threshold,
+                options.prolongator_weight,
+            );
+            if prolongator.cols() == 0 || prolongator.cols() == current.rows() {
+                // aggregation made no progress
+                break;
+            }
+            let restrictor = prolongator.transpose_view().to_csr();
+            let mut workspace = vec![N::zero(); prolongator.cols()];
+            let ap = csr_mul_csr(&current, &prolongator, &mut workspace);
+            let mut coarse = csr_mul_csr(&restrictor, &ap, &mut workspace);
+            if symmetric {
+                coarse = symmetric_part(&coarse);
+            }
+            levels.push(Level {
+                mat: current,
+                prolongator: prolongator,
+                restrictor: restrictor,
+            });
+            current = coarse;
+        }
+        let coarse_solver = coarse_solver(current.view());
+        Amg {
+            levels: levels,
+            coarse_mat: current,
+            coarse_solver: coarse_solver,
+            pre_smoother: options.smoother,
+            post_smoother: reversed(options.smoother),
+            pre_sweeps: options.pre_sweeps,
+            post_sweeps: options.post_sweeps,
+            cycle: options.cycle,
+        }
+    }
+
+    /// The number of levels of the hierarchy, including the finest and the
+    /// coarsest ones
+    pub fn num_levels(&self) -> usize {
+        self.levels.len() + 1
+    }
+
+    /// The dimension of each level, from the finest to the coarsest
+    pub fn level_dims(&self) -> Vec<usize> {
+        self.levels
+            .iter()
+            .map(|level| level.mat.rows())
+            .chain(Some(self.coarse_mat.rows()))
+            .collect()
+    }
+
+    /// The operator complexity of the hierarchy, the ratio of the number of
+    /// non-zeros of the matrices of all levels to the number of non-zeros
+    /// of the finest one. It measures the memory and time overhead of
+    /// a cycle compared to a smoothing sweep on the finest level.
+    pub fn operator_complexity(&self) -> f64 {
+        let fine_nnz = match self.levels.first() {
+            Some(level) => level.mat.nnz(),
+            None => self.coarse_mat.nnz(),
+        };
+        let total_nnz = self
+            .levels
+            .iter()
+            .map(|level| level.mat.nnz())
+            .fold(self.coarse_mat.nnz(), |acc, nnz| acc + nnz);
+        total_nnz as f64 / fine_nnz.max(1) as f64
+    }
+
+    /// The direct solver of the coarsest level
+    pub fn coarse_solver(&self) -> &S {
+        &self.coarse_solver
+    }
+
+    /// Perform one multigrid cycle on the system `A x = b`, where `x`
+    /// holds the current approximation.
+    ///
+    /// # Panics
+    ///
+    /// * if the dimensions of `rhs` or `x` do not match the matrix
+    pub fn cycle(&self, rhs: ArrayView1<N>, x: ArrayViewMut1<N>) {
+        let n = self.level_dims()[0];
+        assert!(rhs.len() == n && x.len() == n, "Dimension mismatch");
+        self.cycle_level(0, rhs, x);
+    }
+
+    fn cycle_level(
+        &self,
+        depth: usize,
+        rhs: ArrayView1<N>,
+        mut x: ArrayViewMut1<N>,
+    ) {
+        if depth == self.levels.len() {
+            let res = &rhs - &(&self.coarse_mat * &x);
+            let mut correction = Array::zeros(res.len());
+            self.coarse_solver.apply(res.view(), correction.view_mut());
+            x.zip_mut_with(&correction, |xi, &c| *xi = *xi + c);
+            return;
+        }
+        let level = &self.levels[depth];
+        let mat = level.mat.view();
+        for _ in 0..self.pre_sweeps {
+            self.pre_smoother
+                .sweep(mat.view(), rhs.view(), x.view_mut());
+        }
+        let res = &rhs - &(&mat * &x);
+        let coarse_rhs = &level.restrictor * &res;
+        let mut coarse_x = Array::zeros(coarse_rhs.len());
+        // a second visit of the coarsest level would not change its
+        // solution
+        let visits = match self.cycle {
+            Cycle::W if depth + 1 < self.levels.len() => 2,
+            _ => 1,
+        };
+        for _ in 0..visits {
+            self.cycle_level(depth + 1, coarse_rhs.view(), coarse_x.view_mut());
+        }
+        let correction = &level.prolongator * &coarse_x;
+        x.zip_mut_with(&correction, |xi, &c| *xi = *xi + c);
+        for _ in 0..self.post_sweeps {
+            self.post_smoother
+                .sweep(mat.view(), rhs.view(), x.view_mut());
+        }
+    }
+}
+
+impl<N, I, S> Preconditioner<N> for Amg<N, I, S>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+    S: Preconditioner<N>,
+{
+    fn apply(&self, rhs: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        out.fill(N::zero());
+        self.cycle(rhs, out);
+    }
+}
+
+/// The relaxation sweeping in the reverse direction
+fn reversed<N: Float>(relaxation: Relaxation<N>) -> Relaxation<N> {
+    match relaxation {
+        Relaxation::Sor { omega, direction } => {
+            let direction = match direction {
+                SweepDirection::Forward => SweepDirection::Backward,
+                SweepDirection::Backward => SweepDirection::Forward,
+                SweepDirection::Symmetric => SweepDirection::Symmetric,
+            };
+            Relaxation::sor(omega, direction)
+        }
+        Relaxation::Jacobi { .. } => relaxation,
+    }
+}
+
+/// The symmetric part `(A + A^T) / 2` of a square CSR matrix, which is
+/// exactly symmetric
+fn symmetric_part<N, I>(mat: &CsMatI<N, I>) -> CsMatI<N, I>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+{
+    let transposed = mat.transpose_view().to_csr();
+    let half = N::from(0.5).expect("representable constant");
+    let mut sum = mat + &transposed;
+    sum.map_inplace(|&x| x * half);
+    sum
+}
+
+/// The diagonal entries of a square matrix
+///
+/// # Panics
+///
+/// * if a diagonal entry is zero or missing
+fn diagonal<N, I>(mat: &CsMatViewI<N, I>) -> Vec<N>
+where
+    N: Float,
+    I: SpIndex,
+{
+    let mut diag = vec![N::zero(); mat.rows()];
+    for (&val, (row, col)) in mat.iter() {
+        if row.index() == col.index() {
+            diag[row.index()] = val;
+        }
+    }
+    assert!(diag.iter().all(|&d| d != N::zero()), "zero diagonal entry");
+    diag
+}
+
+/// The graph of strong connections of a CSR matrix, in compressed form.
+/// The diagonal is not part of the graph.
+fn strong_connections<N, I>(
+    mat: &CsMatViewI<N, I>,
+    diag: &[N],
+    threshold: N,
+) -> (Vec<usize>, Vec<usize>)
+where
+    N: Float,
+    I: SpIndex,
+{
+    let mut indptr = Vec::with_capacity(mat.rows() + 1);
+    let mut indices = Vec::with_capacity(mat.nnz());
+    indptr.push(0);
+    for (row_ind, row) in mat.outer_iterator().enumerate() {
+        for (col_ind, &val) in row.iter() {
+            let bound =
+                threshold * (diag[row_ind] * diag[col_ind]).abs().sqrt();
+            if col_ind != row_ind && val != N::zero() && val.abs() >= bound {
+                indices.push(col_ind);
+            }
+        }
+        indptr.push(indices.len());
+    }
+    (indptr, indices)
+}
+
+/// Group the nodes of a graph into aggregates, returning the aggregate of
+/// each node and the number of aggregates.
+///
+/// The first pass forms aggregates from the nodes whose neighbors are all
+/// free, together with these neighbors. The second pass adds the remaining
+/// nodes to an aggregate of the first pass they are connected to, and the
+/// third one aggregates the nodes left with their free neighbors.
+fn aggregate(indptr: &[usize], indices: &[usize]) -> (Vec<usize>, usize) {
+    let n = indptr.len() - 1;
+    let neighbors = |node: usize| &indices[indptr[node]..indptr[node + 1]];
+    let mut aggregates: Vec<Option<usize>> = vec![None; n];
+    let mut nb_aggregates = 0;
+    for node in 0..n {
+        if aggregates[node].is_some()
+            || neighbors(node).iter().any(|&j| aggregates[j].is_some())
+        {
+            continue;
+        }
+        aggregates[node] = Some(nb_aggregates);
+        for &j in neighbors(node) {
+            aggregates[j] = Some(nb_aggregates);
+        }
+        nb_aggregates += 1;
+    }
+    let first_pass = aggregates.clone();
+    for node in 0..n {
+        if aggregates[node].is_none() {
+            aggregates[node] =
+                neighbors(node).iter().filter_map(|&j| first_pass[j]).next();
+        }
+    }
+    for node in 0..n {
+        if aggregates[node].is_some() {
+            continue;
+        }
+        aggregates[node] = Some(nb_aggregates);
+        for &j in neighbors(node) {
+            if aggregates[j].is_none() {
+                aggregates[j] = Some(nb_aggregates);
+            }
+        }
+        nb_aggregates += 1;
+    }
+    let aggregates = aggregates
+        .into_iter()
+        .map(|agg| agg.expect("all nodes are aggregated"))
+        .collect();
+    (aggregates, nb_aggregates)
+}
+
+/// The smoothed prolongator `P = (I - w D^-1 A) T` of a CSR matrix, where
+/// `T` interpolates the constant vector on the aggregates of the strong
+/// connections, and `w` is the given weight divided by a bound of the
+/// spectral radius of `D^-1 A`.
+fn smoothed_prolongator<N, I>(
+    mat: &CsMatViewI<N, I>,
+    threshold: N,
+    weight: N,
+) -> CsMatI<N, I>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+{
+    let n = mat.rows();
+    let diag = diagonal(mat);
+    let (indptr, indices) = strong_connections(mat, &diag, threshold);
+    let (aggregates, nb_aggregates) = aggregate(&indptr, &indices);
+
+    // the tentative prolongator has orthonormal columns
+    let mut sizes = vec![0; nb_aggregates];
+    for &agg in &aggregates {
+        sizes[agg] += 1;
+    }
+    let tentative = CsMatI::new(
+        (n, nb_aggregates),
+        (0..n + 1).map(I::from_usize).collect(),
+        aggregates.iter().map(|&agg| I::from_usize(agg)).collect(),
+        aggregates
+            .iter()
+            .map(|&agg| {
+                N::one() / N::from(sizes[agg]).expect("representable").sqrt()
+            })
+            .collect(),
+    );
+
+    // Gershgorin bound of the spectral radius of D^-1 A
+    let radius = mat
+        .outer_iterator()
+        .enumerate()
+        .map(|(row_ind, row)| {
+            row.iter().fold(N::zero(), |acc, (_, &val)| acc + val.abs())
+                / diag[row_ind].abs()
+        })
+        .fold(N::zero(), N::max);
+    let mut workspace = vec![N::zero(); nb_aggregates];
+    let mut smoothing = csr_mul_csr(mat, &tentative, &mut workspace);
+    for (row_ind, mut row) in smoothing.outer_iterator_mut().enumerate() {
+        let scale = weight / (radius * diag[row_ind]);
+        for (_, val) in row.iter_mut() {
+            *val = *val * scale;
+        }
+    }
+    &tentative - &smoothing
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Amg, AmgOptions, Cycle};
+    use ndarray::{Array, ArrayView1, ArrayViewMut1};
+    use sparse::linalg::iterative::{
+        cg, pcg, Preconditioner, StoppingCriteria,
+    };
+    use sparse::linalg::qr::SparseQr;
+    use sparse::{CsMatView, TriMat};
+    use test_data::{grid_laplacian, max_abs_diff};
+
+    /// Coarse solver for the tests, backed by a QR factorization
+    struct QrSolver(SparseQr<f64, usize>);
+
+    impl QrSolver {
+        fn new(mat: CsMatView<f64>) -> QrSolver {
+            QrSolver(SparseQr::new(mat))
+        }
+    }
+
+    impl Preconditioner<f64> for QrSolver {
+        fn apply(&self, rhs: ArrayView1<f64>, mut out: ArrayViewMut1<f64>) {
+            let sol = self.0.solve_least_squares(&rhs.to_vec());
+            for (o, s) in out.iter_mut().zip(sol) {
+                *o = s;
+            }
+        }
+    }
+
+    #[test]
+    fn aggregate_path() {
+        // strong connections of a tridiagonal matrix of dimension 9
+        let n = 9;
+        let mut tri = TriMat::new((n, n));
+        for i in 0..n {
+            tri.add_triplet(i, i, 2.);
+            if i > 0 {
+                tri.add_triplet(i, i - 1, -1.);
+                tri.add_triplet(i - 1, i, -1.);
+            }
+        }
+        let mat = tri.to_csr();
+        let diag = super::diagonal(&mat.view());
+        let (indptr, indices) =
+            super::strong_connections(&mat.view(), &diag, 0.25);
+        assert_eq!(indptr[n], 2 * (n - 1));
+        let (aggregates, nb_aggregates) = super::aggregate(&indptr, &indices);
+        assert_eq!(nb_aggregates, 3);
+        assert_eq!(aggregates, vec![0, 0, 1, 1, 1, 2, 2, 2, 2]);
+
+        // no strong connections above a large threshold
+        let (indptr, indices) =
+            super::strong_connections(&mat.view(), &diag, 0.6);
+        assert_eq!(indptr[n], 0);
+        let (aggregates, nb_aggregates) = super::aggregate(&indptr, &indices);
+        assert_eq!(nb_aggregates, n);
+        assert_eq!(aggregates, (0..n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn amg_preconditioned_cg() {
+        let mat = grid_laplacian(48, 48);
+        let n = mat.rows();
+        let options = AmgOptions::new().with_max_coarse(40);
+        let amg = Amg::new(mat.view(), &options, QrSolver::new);
+        let dims = amg.level_dims();
+        assert!(amg.num_levels() >= 3);
+        assert_eq!(dims[0], n);
+        assert!(dims.windows(2).all(|w| w[1] < w[0] / 3));
+        assert!(*dims.last().unwrap() <= 40);
+        assert!(amg.operator_complexity() < 2.);
+        for level in &amg.levels {
+            assert!(::sparse::symmetric::is_symmetric(&level.mat));
+        }
+        assert!(::sparse::symmetric::is_symmetric(&amg.coarse_mat));
+
+        let x0 = Array::from_shape_fn(n, |i| ((i * i) as f64 * 0.3).sin());
+        let rhs = &mat * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 500);
+        let mut x = Array::zeros(n);
+        let report = pcg(mat.view(), rhs.view(), x.view_mut(), &amg, &criteria);
+        assert!(report.converged());
+        assert!(report.iterations() < 20);
+        let mut x_cg = Array::zeros(n);
+        let report_cg = cg(mat.view(), rhs.view(), x_cg.view_mut(), &criteria);
+        assert!(report_cg.iterations() > 3 * report.iterations());
+        let err = max_abs_diff(&x, &x0);
+        assert!(err < 1e-7);
+    }
+
+    #[test]
+    fn amg_cycles() {
+        let mat = grid_laplacian(30, 30);
+        let n = mat.rows();
+        let rhs = Array::from_elem(n, 1.);
+        let norm = |v: &Array<f64, ::ndarray::Ix1>| {
+            v.iter().fold(0., |acc, x| acc + x * x).sqrt()
+        };
+        let rhs_norm = norm(&rhs);
+        let mut residuals = Vec::new();
+        for &cycle in &[Cycle::V, Cycle::W] {
+            let options = AmgOptions::new()
+                .with_max_coarse(10)
+                .with_cycle(cycle)
+                .with_sweeps(2, 2);
+            let amg = Amg::new(mat.view(), &options, QrSolver::new);
+            let mut x = Array::zeros(n);
+            for _ in 0..8 {
+                amg.cycle(rhs.view(), x.view_mut());
+            }
+            let res = &rhs - &(&mat * &x);
+            residuals.push(norm(&res) / rhs_norm);
+        }
+        assert!(residuals[0] < 1e-4);
+        assert!(residuals[1] < residuals[0]);
+
+        // a hierarchy with a single level is a direct solve
+        let options = AmgOptions::new().with_max_levels(1);
+        let amg = Amg::new(mat.view(), &options, QrSolver::new);
+        assert_eq!(amg.level_dims(), vec![n]);
+        let mut x = Array::zeros(n);
+        amg.apply(rhs.view(), x.view_mut());
+        let res = &rhs - &(&mat * &x);
+        assert!(norm(&res) < 1e-10 * rhs_norm);
+    }
+}
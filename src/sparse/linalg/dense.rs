@@ -0,0 +1,811 @@
+///! Small dense linear algebra kernels
+///!
+///! Krylov and block methods project large sparse problems onto small dense
+///! ones, which are solved by the routines of this module. They are not meant
+///! to be efficient on large matrices.
+use ndarray::{Array, Array2, Axis, Ix1, Slice};
+use num_complex::Complex64;
+use num_traits::Float;
+
+/// Compute the eigen decomposition `A = V diag(w) V^T` of a dense symmetric
+/// matrix using the cyclic Jacobi method.
+///
+/// The eigenvalues are returned unsorted, with the eigenvectors as the
+/// columns of `V`. Only the upper triangular part of `A` is used.
+///
+/// # Panics
+///
+/// * if the matrix is not square
+pub fn symmetric_eigen<N: Float>(
+    mut a: Array2<N>,
+) -> (Array<N, Ix1>, Array2<N>) {
+    let n = a.rows();
+    assert_eq!(a.cols(), n, "matrix should be square");
+    for i in 0..n {
+        for j in 0..i {
+            a[[i, j]] = a[[j, i]];
+        }
+    }
+    let mut v = Array2::eye(n);
+    let two = N::one() + N::one();
+    let frob = a.iter().fold(N::zero(), |acc, &x| acc.hypot(x));
+    for _sweep in 0..100 {
+        let mut off = N::zero();
+        for p in 0..n {
+            for q in (p + 1)..n {
+                off = off.hypot(a[[p, q]]);
+            }
+        }
+        if off <= N::epsilon() * frob {
+            break;
+        }
+        for p in 0..n {
+            for q in (p + 1)..n {
+                let apq = a[[p, q]];
+                if apq == N::zero() {
+                    continue;
+                }
+                // rotation zeroing a[p, q]
+                let theta = (a[[q, q]] - a[[p, p]]) / (two * apq);
+                let t = if theta >= N::zero() {
+                    N::one() / (theta + (theta * theta + N::one()).sqrt())
+                } else {
+                    -N::one() / (-theta + (theta * theta + N::one()).sqrt())
+                };
+                let c = N::one() / (t * t + N::one()).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let akp = a[[k, p]];
+                    let akq = a[[k, q]];
+                    a[[k, p]] = c * akp - s * akq;
+                    a[[k, q]] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let apk = a[[p, k]];
+                    let aqk = a[[q, k]];
+                    a[[p, k]] = c * apk - s * aqk;
+                    a[[q, k]] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let vkp = v[[k, p]];
+                    let vkq = v[[k, q]];
+                    v[[k, p]] = c * vkp - s * vkq;
+                    v[[k, q]] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    (a.diag().to_owned(), v)
+}
+
+/// Compute the thin singular value decomposition `A = U diag(s) V^T` of
+/// a dense matrix with at least as many rows as columns, using the one-sided
+/// Jacobi method.
+///
+/// The singular values are sorted in decreasing order. `U` has the shape of
+/// `A` and orthonormal columns, including for zero singular values, and `V`
+/// is orthogonal.
+///
+/// # Panics
+///
+/// * if the matrix has more columns than rows
+pub fn thin_svd<N: 'static + Float>(
+    mut a: Array2<N>,
+) -> (Array2<N>, Array<N, Ix1>, Array2<N>) {
+    let (m, n) = a.dim();
+    assert!(
+        m >= n,
+        "matrix should have at least as many rows as columns"
+    );
+    let mut v = Array2::eye(n);
+    let two = N::one() + N::one();
+    for _sweep in 0..100 {
+        let mut rotated = false;
+        for p in 0..n {
+            for q in (p + 1)..n {
+                let (mut alpha, mut beta, mut gamma) =
+                    (N::zero(), N::zero(), N::zero());
+                for k in 0..m {
+                    alpha = alpha + a[[k, p]] * a[[k, p]];
+                    beta = beta + a[[k, q]] * a[[k, q]];
+                    gamma = gamma + a[[k, p]] * a[[k, q]];
+                }
+                if gamma.abs() <= N::epsilon() * (alpha * beta).sqrt() {
+                    continue;
+                }
+                rotated = true;
+                // rotation making columns p and q orthogonal
+                let zeta = (beta - alpha) / (two * gamma);
+                let t = zeta.signum()
+                    / (zeta.abs() + (zeta * zeta + N::one()).sqrt());
+                let c = N::one() / (t * t + N::one()).sqrt();
+                let s = t * c;
+                for k in 0..m {
+                    let akp = a[[k, p]];
+                    let akq = a[[k, q]];
+                    a[[k, p]] = c * akp - s * akq;
+                    a[[k, q]] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let vkp = v[[k, p]];
+                    let vkq = v[[k, q]];
+                    v[[k, p]] = c * vkp - s * vkq;
+                    v[[k, q]] = s * vkp + c * vkq;
+                }
+            }
+        }
+        if !rotated {
+            break;
+        }
+    }
+
+    let norms: Vec<N> = a
+        .gencolumns()
+        .into_iter()
+        .map(|col| col.iter().fold(N::zero(), |acc, &x| acc.hypot(x)))
+        .collect();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| {
+        norms[j]
+            .partial_cmp(&norms[i])
+            .expect("singular values should not be NaN")
+    });
+    let max_norm = if n > 0 { norms[order[0]] } else { N::zero() };
+    let small = N::from(m).expect("representable dimension") * N::epsilon();
+    let mut u = Array2::zeros((m, n));
+    let mut sigma = Array::zeros(n);
+    let mut v_sorted = Array2::zeros((n, n));
+    for (k, &j) in order.iter().enumerate() {
+        v_sorted.column_mut(k).assign(&v.column(j));
+        if norms[j] > small * max_norm {
+            sigma[k] = norms[j];
+            u.column_mut(k).assign(&a.column(j).mapv(|x| x / norms[j]));
+        } else {
+            // complete U with a unit vector orthogonal to its columns
+            for i in 0..m {
+                let mut w = Array::zeros(m);
+                w[i] = N::one();
+                orthogonalize(&u, k, &mut w);
+                let w_norm = norm2(&w);
+                if w_norm > N::one() / two {
+                    u.column_mut(k).assign(&w.mapv(|x| x / w_norm));
+                    break;
+                }
+            }
+        }
+    }
+    (u, sigma, v_sorted)
+}
+
+/// Apply one implicitly shifted QR step to a real upper Hessenberg matrix,
+/// with a real shift `s`, or with a complex shift `s` and its conjugate.
+///
+/// The matrix `h` is overwritten with `Z^T H Z`, where `Z` is the orthogonal
+/// factor of the QR decomposition of `H - s I`, or of
+/// `(H - s I) (H - conj(s) I)` for a complex shift, and `q` is overwritten
+/// with `Q Z`. This decomposition is never formed: a bulge introduced by the
+/// first column of the shift polynomial is chased down the subdiagonal with
+/// Householder reflections, so that `h` stays upper Hessenberg by
+/// construction. The step is applied to each unreduced diagonal block of
+/// `h`, its negligible subdiagonal entries being set to zero.
+///
+/// Reference: G. H. Golub and C. F. Van Loan, Matrix Computations, 4th
+/// edition, algorithm 7.5.1, applied one shift at a time as in ARPACK's
+/// `dnapps`.
+///
+/// # Panics
+///
+/// * if the matrix is not square
+/// * if `q` does not have as many columns as `h`
+pub fn hessenberg_shifted_qr(
+    h: &mut Array2<f64>,
+    q: &mut Array2<f64>,
+    shift: Complex64,
+) {
+    let n = h.rows();
+    assert_eq!(h.cols(), n, "matrix should be square");
+    assert_eq!(q.cols(), n, "dimension mismatch");
+    let eps = ::std::f64::EPSILON;
+    let norm = h.iter().fold(0., |acc: f64, x| acc + x.abs());
+    let max_size = if shift.im == 0. { 2 } else { 3 };
+    let mut lo = 0;
+    while lo < n {
+        // the rows and columns `lo..hi` form an unreduced block
+        let mut hi = lo + 1;
+        while hi < n {
+            let mut scale = h[[hi - 1, hi - 1]].abs() + h[[hi, hi]].abs();
+            if scale == 0. {
+                scale = norm;
+            }
+            if h[[hi, hi - 1]].abs() <= eps * scale {
+                h[[hi, hi - 1]] = 0.;
+                break;
+            }
+            hi += 1;
+        }
+        if hi - lo < 2 {
+            lo = hi;
+            continue;
+        }
+
+        // first column of the shift polynomial, then chase the bulge
+        let (mut x, mut y, mut z) = if shift.im == 0. {
+            (h[[lo, lo]] - shift.re, h[[lo + 1, lo]], 0.)
+        } else {
+            let sum = 2. * shift.re;
+            let prod = shift.norm_sqr();
+            (
+                h[[lo, lo]] * h[[lo, lo]] + h[[lo, lo + 1]] * h[[lo + 1, lo]]
+                    - sum * h[[lo, lo]]
+                    + prod,
+                h[[lo + 1, lo]] * (h[[lo, lo]] + h[[lo + 1, lo + 1]] - sum),
+                if lo + 2 < hi {
+                    h[[lo + 1, lo]] * h[[lo + 2, lo + 1]]
+                } else {
+                    0.
+                },
+            )
+        };
+        for k in lo..(hi - 1) {
+            let size = ::std::cmp::min(max_size, hi - k);
+            let mut v = [x, y, z];
+            let v_norm =
+                v[..size].iter().fold(0., |acc: f64, vi| acc.hypot(*vi));
+            if v_norm != 0. {
+                let alpha = if v[0] > 0. { -v_norm } else { v_norm };
+                v[0] -= alpha;
+                let v_norm2 =
+                    v[..size].iter().fold(0., |acc, vi| acc + vi * vi);
+                let beta = 2. / v_norm2;
+                // the reflection maps the bulge onto the subdiagonal
+                if k > lo {
+                    h[[k, k - 1]] = alpha;
+                    for i in 1..size {
+                        h[[k + i, k - 1]] = 0.;
+                    }
+                }
+                for j in k..n {
+                    let dot =
+                        (0..size).fold(0., |acc, i| acc + v[i] * h[[k + i, j]]);
+                    for i in 0..size {
+                        h[[k + i, j]] -= beta * v[i] * dot;
+                    }
+                }
+                let last_row = ::std::cmp::min(k + size + 1, hi);
+                for i in 0..last_row {
+                    let dot =
+                        (0..size).fold(0., |acc, j| acc + v[j] * h[[i, k + j]]);
+                    for j in 0..size {
+                        h[[i, k + j]] -= beta * v[j] * dot;
+                    }
+                }
+                for i in 0..q.rows() {
+                    let dot =
+                        (0..size).fold(0., |acc, j| acc + v[j] * q[[i, k + j]]);
+                    for j in 0..size {
+                        q[[i, k + j]] -= beta * v[j] * dot;
+                    }
+                }
+            }
+            if k + 2 < hi {
+                x = h[[k + 1, k]];
+                y = h[[k + 2, k]];
+                z = if k + 3 < hi { h[[k + 3, k]] } else { 0. };
+            }
+        }
+        lo = hi;
+    }
+}
+
+/// Solve the dense least squares problem `min ||A x - b||`, using Householder
+/// reflections.
+///
+/// The matrix should have full column rank. The coefficients of the columns
+/// found numerically dependent on the previous ones are set to zero.
+///
+/// # Panics
+///
+/// * if the number of rows of `a` does not match the dimension of `b`
+pub fn least_squares<N: Float>(
+    mut a: Array2<N>,
+    mut b: Array<N, Ix1>,
+) -> Array<N, Ix1> {
+    let (m, n) = a.dim();
+    assert_eq!(b.len(), m, "Dimension mismatch");
+    let steps = m.min(n);
+    let a_max = a.iter().fold(N::zero(), |acc, &x| acc.max(x.abs()));
+    let dim = N::from(m.max(n)).expect("representable dimension");
+    let tol = a_max * dim * N::epsilon();
+    for k in 0..steps {
+        let mut v: Vec<N> = (k..m).map(|i| a[[i, k]]).collect();
+        let x_norm = v.iter().fold(N::zero(), |acc, &x| acc.hypot(x));
+        if x_norm == N::zero() {
+            continue;
+        }
+        let alpha = if v[0] > N::zero() { -x_norm } else { x_norm };
+        v[0] = v[0] - alpha;
+        let v_norm2 = v.iter().fold(N::zero(), |acc, &x| acc + x * x);
+        if v_norm2 == N::zero() {
+            continue;
+        }
+        let scale = (N::one() + N::one()) / v_norm2;
+        for j in k..n {
+            let dot = v
+                .iter()
+                .enumerate()
+                .fold(N::zero(), |acc, (i, &vi)| acc + vi * a[[k + i, j]]);
+            for (i, &vi) in v.iter().enumerate() {
+                a[[k + i, j]] = a[[k + i, j]] - scale * dot * vi;
+            }
+        }
+        let dot = v
+            .iter()
+            .enumerate()
+            .fold(N::zero(), |acc, (i, &vi)| acc + vi * b[k + i]);
+        for (i, &vi) in v.iter().enumerate() {
+            b[k + i] = b[k + i] - scale * dot * vi;
+        }
+    }
+    let mut x = Array::zeros(n);
+    for k in (0..steps).rev() {
+        if a[[k, k]].abs() <= tol {
+            continue;
+        }
+        let sum = ((k + 1)..n).fold(b[k], |acc, j| acc - a[[k, j]] * x[j]);
+        x[k] = sum / a[[k, k]];
+    }
+    x
+}
+
+/// Compute the eigenvalues of a real upper Hessenberg matrix, using the
+/// Francis double shift QR algorithm.
+///
+/// Complex conjugate eigenvalues are returned next to each other, the one
+/// with a positive imaginary part first.
+///
+/// The second returned value tells whether the QR iteration converged. If
+/// it did not, the eigenvalues of the blocks that were not deflated are
+/// approximated by their diagonal entries.
+///
+/// Reference: G. H. Golub and C. F. Van Loan, Matrix Computations, 4th
+/// edition, algorithms 7.5.1 and 7.5.2. The deflation criterion and the
+/// exceptional shifts follow LAPACK's `dlahqr`.
+///
+/// # Panics
+///
+/// * if the matrix is not square
+pub fn hessenberg_eigenvalues(h: &Array2<f64>) -> (Vec<Complex64>, bool) {
+    let n = h.rows();
+    assert_eq!(h.cols(), n, "matrix should be square");
+    let eps = ::std::f64::EPSILON;
+    let mut a = h.to_owned();
+    let norm = a.iter().fold(0., |acc: f64, x| acc + x.abs());
+    let mut values = vec![Complex64::new(0., 0.); n];
+    let max_iterations = 30 * ::std::cmp::max(10, n);
+    let mut iterations = 0;
+    // number of QR steps since the last deflation
+    let mut steps = 0;
+    // the rows and columns `lo..hi` form the active unreduced block
+    let mut hi = n;
+    while hi > 0 {
+        let mut lo = hi - 1;
+        while lo > 0 {
+            let mut scale = a[[lo - 1, lo - 1]].abs() + a[[lo, lo]].abs();
+            if scale == 0. {
+                scale = norm;
+            }
+            if a[[lo, lo - 1]].abs() <= eps * scale {
+                a[[lo, lo - 1]] = 0.;
+                break;
+            }
+            lo -= 1;
+        }
+        if lo + 1 == hi {
+            values[hi - 1] = Complex64::new(a[[hi - 1, hi - 1]], 0.);
+            hi -= 1;
+            steps = 0;
+            continue;
+        }
+        if lo + 2 == hi {
+            let (first, second) = block_eigenvalues(
+                a[[lo, lo]],
+                a[[lo, lo + 1]],
+                a[[lo + 1, lo]],
+                a[[lo + 1, lo + 1]],
+            );
+            values[lo] = first;
+            values[lo + 1] = second;
+            hi -= 2;
+            steps = 0;
+            continue;
+        }
+        if iterations == max_iterations {
+            for i in 0..hi {
+                values[i] = Complex64::new(a[[i, i]], 0.);
+            }
+            return (values, false);
+        }
+        iterations += 1;
+        steps += 1;
+
+        // the shifts are the eigenvalues of the trailing 2x2 block, given
+        // by their sum and product, or ad hoc values if the iteration
+        // stagnates
+        let (p, q) = (hi - 2, hi - 1);
+        let (sum, prod) = if steps % 10 == 0 {
+            let s = a[[q, p]].abs() + a[[p, p - 1]].abs();
+            let diag = 0.75 * s + a[[q, q]];
+            (2. * diag, diag * diag + 0.4375 * s * s)
+        } else {
+            (
+                a[[p, p]] + a[[q, q]],
+                a[[p, p]] * a[[q, q]] - a[[p, q]] * a[[q, p]],
+            )
+        };
+
+        // first column of (H - s1 I) (H - s2 I), then chase the bulge
+        let mut x = a[[lo, lo]] * a[[lo, lo]]
+            + a[[lo, lo + 1]] * a[[lo + 1, lo]]
+            - sum * a[[lo, lo]]
+            + prod;
+        let mut y = a[[lo + 1, lo]] * (a[[lo, lo]] + a[[lo + 1, lo + 1]] - sum);
+        let mut z = a[[lo + 1, lo]] * a[[lo + 2, lo + 1]];
+        for k in lo..(hi - 1) {
+            let size = if k + 2 < hi { 3 } else { 2 };
+            let mut v = [x, y, z];
+            let v_norm =
+                v[..size].iter().fold(0., |acc: f64, vi| acc.hypot(*vi));
+            if v_norm != 0. {
+                let alpha = if v[0] > 0. { -v_norm } else { v_norm };
+                v[0] -= alpha;
+                let v_norm2 =
+                    v[..size].iter().fold(0., |acc, vi| acc + vi * vi);
+                let beta = 2. / v_norm2;
+                // apply the reflection on the left and on the right
+                let first_col = if k > lo { k - 1 } else { lo };
+                for j in first_col..hi {
+                    let dot =
+                        (0..size).fold(0., |acc, i| acc + v[i] * a[[k + i, j]]);
+                    for i in 0..size {
+                        a[[k + i, j]] -= beta * v[i] * dot;
+                    }
+                }
+                let last_row = ::std::cmp::min(k + 4, hi);
+                for i in lo..last_row {
+                    let dot =
+                        (0..size).fold(0., |acc, j| acc + v[j] * a[[i, k + j]]);
+                    for j in 0..size {
+                        a[[i, k + j]] -= beta * v[j] * dot;
+                    }
+                }
+                if k > lo {
+                    a[[k + 1, k - 1]] = 0.;
+                    if size == 3 {
+                        a[[k + 2, k - 1]] = 0.;
+                    }
+                }
+            }
+            if k + 2 < hi {
+                x = a[[k + 1, k]];
+                y = a[[k + 2, k]];
+                if k + 3 < hi {
+                    z = a[[k + 3, k]];
+                }
+            }
+        }
+    }
+    (values, true)
+}
+
+/// The eigenvalues of the 2x2 matrix `[[a, b], [c, d]]`
+fn block_eigenvalues(a: f64, b: f64, c: f64, d: f64) -> (Complex64, Complex64) {
+    let mean = 0.5 * (a + d);
+    let half_diff = 0.5 * (a - d);
+    let disc = half_diff * half_diff + b * c;
+    if disc >= 0. {
+        // avoid the cancellation in the smallest eigenvalue in magnitude
+        let root = disc.sqrt();
+        let largest = if mean >= 0. { mean + root } else { mean - root };
+        let det = a * d - b * c;
+        let other = if largest != 0. { det / largest } else { 0. };
+        (Complex64::new(largest, 0.), Complex64::new(other, 0.))
+    } else {
+        let im = (-disc).sqrt();
+        (Complex64::new(mean, im), Complex64::new(mean, -im))
+    }
+}
+
+/// Compute a normalized eigenvector of a real square matrix associated to
+/// the given eigenvalue, by inverse iteration.
+pub fn complex_eigenvector(
+    a: &Array2<f64>,
+    eigenvalue: Complex64,
+) -> Array<Complex64, Ix1> {
+    let n = a.rows();
+    let scale = a
+        .iter()
+        .fold(0., |acc: f64, x| acc.max(x.abs()))
+        .max(1e-300);
+    let tiny = ::std::f64::EPSILON * scale;
+    // slightly perturb the eigenvalue to avoid an exactly singular system
+    let shift = eigenvalue + Complex64::new(tiny, 0.);
+    let mut lu = Array2::from_shape_fn((n, n), |(i, j)| {
+        let aij = Complex64::new(a[[i, j]], 0.);
+        if i == j {
+            aij - shift
+        } else {
+            aij
+        }
+    });
+    // LU factorization with partial pivoting
+    let mut pivots = vec![0; n];
+    for k in 0..n {
+        let piv = (k..n)
+            .max_by(|&i, &j| {
+                lu[[i, k]]
+                    .norm()
+                    .partial_cmp(&lu[[j, k]].norm())
+                    .expect("no NaN")
+            })
+            .expect("non empty range");
+        pivots[k] = piv;
+        for j in 0..n {
+            let tmp = lu[[k, j]];
+            lu[[k, j]] = lu[[piv, j]];
+            lu[[piv, j]] = tmp;
+        }
+        if lu[[k, k]].norm() < tiny {
+            lu[[k, k]] = Complex64::new(tiny, 0.);
+        }
+        let pivot = lu[[k, k]];
+        for i in (k + 1)..n {
+            let factor = lu[[i, k]] / pivot;
+            lu[[i, k]] = factor;
+            for j in (k + 1)..n {
+                let update = factor * lu[[k, j]];
+                lu[[i, j]] = lu[[i, j]] - update;
+            }
+        }
+    }
+    let mut x = Array::from_elem(n, Complex64::new(1., 0.));
+    for _ in 0..3 {
+        for k in 0..n {
+            x.swap(k, pivots[k]);
+        }
+        for i in 0..n {
+            for j in 0..i {
+                let update = lu[[i, j]] * x[j];
+                x[i] = x[i] - update;
+            }
+        }
+        for i in (0..n).rev() {
+            for j in (i + 1)..n {
+                let update = lu[[i, j]] * x[j];
+                x[i] = x[i] - update;
+            }
+            x[i] = x[i] / lu[[i, i]];
+        }
+        let norm = x.iter().fold(0., |acc: f64, xi| acc.hypot(xi.norm()));
+        x.mapv_inplace(|xi| xi / norm);
+    }
+    x
+}
+
+/// Orthogonalize `w` against the first `dim` columns of `basis`, using two
+/// passes of classical Gram-Schmidt. Returns the projection coefficients.
+pub fn orthogonalize<N>(
+    basis: &Array2<N>,
+    dim: usize,
+    w: &mut Array<N, Ix1>,
+) -> Array<N, Ix1>
+where
+    N: 'static + Float,
+{
+    let vecs = basis.slice_axis(Axis(1), Slice::from(0..dim));
+    let mut coeffs = Array::zeros(dim);
+    for _ in 0..2 {
+        let h = vecs.t().dot(w);
+        *w = &*w - &vecs.dot(&h);
+        coeffs = coeffs + &h;
+    }
+    coeffs
+}
+
+/// The euclidean norm of a vector
+pub fn norm2<N: Float>(x: &Array<N, Ix1>) -> N {
+    x.iter().fold(N::zero(), |acc, &xi| acc.hypot(xi))
+}
+
+/// A small deterministic pseudo random number generator (xorshift64*),
+/// used to build starting vectors.
+#[derive(Debug, Clone)]
+pub struct XorShift(u64);
+
+impl XorShift {
+    pub fn new(seed: u64) -> XorShift {
+        // the state should never be zero
+        XorShift(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A number uniformly distributed in `[-1, 1)`
+    pub fn next_float<N: Float>(&mut self) -> N {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        N::from(2. * unit - 1.).expect("representable random number")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::{arr2, Array, Array2};
+    use num_complex::Complex64;
+
+    #[test]
+    fn least_squares() {
+        // fit of a line through (0, 1), (1, 2), (2, 4)
+        let a: Array2<f64> = arr2(&[[1., 0.], [1., 1.], [1., 2.]]);
+        let b = Array::from_vec(vec![1., 2., 4.]);
+        let x = super::least_squares(a, b);
+        assert!((x[0] - 5. / 6.).abs() < 1e-14);
+        assert!((x[1] - 1.5).abs() < 1e-14);
+
+        // square systems are solved exactly
+        let a: Array2<f64> = arr2(&[[0., 2., 1.], [1., 1., 0.], [3., 0., 1.]]);
+        let b = Array::from_vec(vec![5., 3., 4.]);
+        let x = super::least_squares(a, b);
+        for (xi, expected) in x.iter().zip(&[1., 2., 1.]) {
+            assert!((xi - expected).abs() < 1e-14);
+        }
+    }
+
+    #[test]
+    fn symmetric_eigen() {
+        let a: Array2<f64> = arr2(&[
+            [4., 1., -2., 2.],
+            [1., 2., 0., 1.],
+            [-2., 0., 3., -2.],
+            [2., 1., -2., -1.],
+        ]);
+        let (w, v) = super::symmetric_eigen(a.clone());
+        // A V = V diag(w), V^T V = I
+        let av = a.dot(&v);
+        for j in 0..4 {
+            for i in 0..4 {
+                assert!((av[[i, j]] - w[j] * v[[i, j]]).abs() < 1e-12);
+            }
+        }
+        let vtv = v.t().dot(&v);
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected = if i == j { 1. } else { 0. };
+                assert!((vtv[[i, j]] - expected).abs() < 1e-12);
+            }
+        }
+        let trace = w.iter().fold(0., |acc, x| acc + x);
+        assert!((trace - 8.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn hessenberg_eigenvalues() {
+        // eigenvalues 1 +- 2i, 3 and -1
+        let a: Array2<f64> = arr2(&[
+            [1., -2., 0.5, 1.],
+            [2., 1., 0.3, -1.],
+            [0., 0., 3., 2.],
+            [0., 0., 0., -1.],
+        ]);
+        let (mut values, converged) = super::hessenberg_eigenvalues(&a);
+        assert!(converged);
+        values.sort_by(|x, y| (x.re, x.im).partial_cmp(&(y.re, y.im)).unwrap());
+        let expected = [(-1., 0.), (1., -2.), (1., 2.), (3., 0.)];
+        for (val, &(re, im)) in values.iter().zip(&expected) {
+            assert!((val.re - re).abs() < 1e-12);
+            assert!((val.im - im).abs() < 1e-12);
+        }
+        for val in values {
+            let vec = super::complex_eigenvector(&a, val);
+            for i in 0..4 {
+                let mut av = Complex64::new(0., 0.);
+                for j in 0..4 {
+                    av = av + vec[j] * a[[i, j]];
+                }
+                assert!((av - vec[i] * val).norm() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn hessenberg_eigenvalues_cyclic() {
+        // the cyclic shift stagnates with the standard shifts, its
+        // eigenvalues are the roots of unity
+        let n = 5;
+        let a = Array2::from_shape_fn((n, n), |(i, j)| {
+            if i == j + 1 || (i == 0 && j == n - 1) {
+                1.
+            } else {
+                0.
+            }
+        });
+        let (values, converged) = super::hessenberg_eigenvalues(&a);
+        assert!(converged);
+        assert_eq!(values.len(), n);
+        for k in 0..n {
+            let angle = 2. * ::std::f64::consts::PI * k as f64 / n as f64;
+            let root = Complex64::new(angle.cos(), angle.sin());
+            assert!(values.iter().any(|val| (val - root).norm() < 1e-10));
+        }
+    }
+
+    #[test]
+    fn thin_svd() {
+        // rank 2 matrix, with a zero singular value
+        let a: Array2<f64> =
+            arr2(&[[1., 2., 3.], [2., -1., 1.], [0., 1., 1.], [1., 0., 1.]]);
+        let (u, s, v) = super::thin_svd(a.clone());
+        assert!(s[0] >= s[1] && s[1] >= s[2]);
+        assert!(s[2].abs() < 1e-12);
+        let mut us = u.clone();
+        for j in 0..3 {
+            us.column_mut(j).mapv_inplace(|x| x * s[j]);
+        }
+        let usvt = us.dot(&v.t());
+        let utu = u.t().dot(&u);
+        let vtv = v.t().dot(&v);
+        for i in 0..4 {
+            for j in 0..3 {
+                assert!((usvt[[i, j]] - a[[i, j]]).abs() < 1e-12);
+            }
+        }
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { 1. } else { 0. };
+                assert!((utu[[i, j]] - expected).abs() < 1e-12);
+                assert!((vtv[[i, j]] - expected).abs() < 1e-12);
+            }
+        }
+        let frob2 = a.iter().fold(0., |acc, x| acc + x * x);
+        assert!((s[0] * s[0] + s[1] * s[1] - frob2).abs() < 1e-10);
+    }
+
+    #[test]
+    fn hessenberg_shifted_qr() {
+        let a: Array2<f64> = arr2(&[
+            [4., 1., -2., 2., 1.],
+            [3., 2., 0., 1., -1.],
+            [0., -2., 3., -2., 0.5],
+            [0., 0., 1., -1., 2.],
+            [0., 0., 0., -3., 1.],
+        ]);
+        let (values, converged) = super::hessenberg_eigenvalues(&a);
+        assert!(converged);
+        // shifting by an eigenvalue deflates it at the bottom of the matrix
+        let check = |shift: Complex64, deflated: usize| {
+            let mut h = a.clone();
+            let mut q = Array2::eye(5);
+            super::hessenberg_shifted_qr(&mut h, &mut q, shift);
+            let qtq = q.t().dot(&q);
+            let qtaq = q.t().dot(&a).dot(&q);
+            for i in 0..5 {
+                for j in 0..5 {
+                    let expected = if i == j { 1. } else { 0. };
+                    assert!((qtq[[i, j]] - expected).abs() < 1e-12);
+                    assert!((qtaq[[i, j]] - h[[i, j]]).abs() < 1e-12);
+                    if i > j + 1 {
+                        assert_eq!(h[[i, j]], 0.);
+                    }
+                }
+            }
+            assert!(h[[deflated, deflated - 1]].abs() < 1e-10);
+        };
+        let real = values.iter().find(|val| val.im == 0.).unwrap();
+        check(*real, 4);
+        let complex = values.iter().find(|val| val.im > 0.).unwrap();
+        check(*complex, 3);
+    }
+}
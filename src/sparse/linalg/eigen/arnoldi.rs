@@ -0,0 +1,321 @@
+///! Implicitly restarted Arnoldi method
+///!
+///! Reference: R. B. Lehoucq, D. C. Sorensen and C. Yang, ARPACK Users'
+///! Guide: Solution of Large-Scale Eigenvalue Problems with Implicitly
+///! Restarted Arnoldi Methods, SIAM (1998).
+use ndarray::{Array, Array2, Axis, Ix1, Slice};
+use num_complex::Complex64;
+
+use super::{EigenOptions, EigenResult, Which};
+use indexing::SpIndex;
+use sparse::linalg::dense::{
+    complex_eigenvector, hessenberg_eigenvalues, hessenberg_shifted_qr, norm2,
+    orthogonalize, XorShift,
+};
+use sparse::CsMatViewI;
+
+/// Compute `nev` eigenpairs of a general square matrix using the implicitly
+/// restarted Arnoldi method.
+///
+/// The eigenvalues selected by `which` are computed. As the matrix is not
+/// symmetric, the eigenvalues and eigenvectors can be complex. The
+/// eigenvalues of a real matrix come in complex conjugate pairs, which are
+/// returned next to each other, the one with a positive imaginary part
+/// first.
+///
+/// The matrix can be stored in either CSR or CSC format. If the eigenpairs
+/// do not converge, the best approximations found are returned, and the
+/// result reports the absence of convergence.
+///
+/// # Panics
+///
+/// * if `mat` is not square
+/// * if `nev` is zero or larger than the dimension of `mat`
+/// * if the subspace dimension requested in `options` is invalid
+pub fn arnoldi<I>(
+    mat: CsMatViewI<f64, I>,
+    nev: usize,
+    which: Which,
+    options: &EigenOptions<f64>,
+) -> EigenResult<f64, Complex64>
+where
+    I: SpIndex,
+{
+    let n = mat.rows();
+    assert!(mat.cols() == n, "matrix should be square");
+    let m = options.subspace_dim_for(nev, n);
+    let small = n as f64 * ::std::f64::EPSILON;
+    let eps23 = ::std::f64::EPSILON.powf(2. / 3.);
+    let mut rng = XorShift::new(n as u64);
+    // the Arnoldi basis, with the next Arnoldi vector in its last column,
+    // and the extended Hessenberg matrix of the Arnoldi relation
+    // `A V_m = V_{m+1} H`
+    let mut basis = Array2::zeros((n, m + 1));
+    let mut hess = Array2::zeros((m + 1, m));
+    let mut w = Array::from_shape_fn(n, |_| rng.next_float());
+    let w_norm = norm2(&w);
+    basis.column_mut(0).assign(&w.mapv(|wi| wi / w_norm));
+
+    let mut kept = 0;
+    let mut restarts = 0;
+    loop {
+        // extend the Arnoldi factorization up to m vectors
+        let mut dim = m;
+        for j in kept..m {
+            let v = basis.column(j).to_owned();
+            w = &mat * &v;
+            let op_norm = norm2(&w);
+            let coeffs = orthogonalize(&basis, j + 1, &mut w);
+            for (i, &h) in coeffs.iter().enumerate() {
+                hess[[i, j]] = h;
+            }
+            let beta = norm2(&w);
+            hess[[j + 1, j]] = beta;
+            if beta <= small * op_norm {
+                // invariant subspace, continue with a random vector
+                hess[[j + 1, j]] = 0.;
+                w = Array::from_shape_fn(n, |_| rng.next_float());
+                let rand_norm = norm2(&w);
+                orthogonalize(&basis, j + 1, &mut w);
+                if norm2(&w) <= small * rand_norm {
+                    // the basis spans the whole space
+                    dim = j + 1;
+                    break;
+                }
+            }
+            let w_norm = norm2(&w);
+            basis.column_mut(j + 1).assign(&w.mapv(|wi| wi / w_norm));
+        }
+        let beta = if dim < m { 0. } else { hess[[m, m - 1]] };
+
+        // Ritz values, sorted from the most wanted
+        let h_dim = hess
+            .slice_axis(Axis(0), Slice::from(0..dim))
+            .slice_axis(Axis(1), Slice::from(0..dim))
+            .to_owned();
+        let (ritz, ritz_converged) = hessenberg_eigenvalues(&h_dim);
+        let order = which.sort_complex(&ritz);
+        let scale = ritz.iter().fold(0., |acc: f64, t| acc.max(t.norm()));
+        let ritz_vectors: Vec<_> = order[..nev]
+            .iter()
+            .map(|&i| complex_eigenvector(&h_dim, ritz[i]))
+            .collect();
+        let converged = ritz_converged
+            && order[..nev].iter().zip(&ritz_vectors).all(|(&i, y)| {
+                let res = beta * y[dim - 1].norm();
+                res <= options.tol() * ritz[i].norm().max(eps23 * scale)
+            });
+        // complex conjugate pairs should not be split by the restart
+        let mut wanted = nev;
+        if wanted < dim && ritz[order[wanted - 1]].im > 0. {
+            wanted += 1;
+        }
+        let finished = converged
+            || !ritz_converged
+            || dim < m
+            || wanted >= dim
+            || restarts >= options.max_restarts();
+        if finished {
+            let basis_dim = basis.slice_axis(Axis(1), Slice::from(0..dim));
+            let values = order[..nev].iter().map(|&i| ritz[i]).collect();
+            let mut vectors = Array2::zeros((n, nev));
+            for (k, y) in ritz_vectors.iter().enumerate() {
+                let re = basis_dim.dot(&y.mapv(|yi| yi.re));
+                let im = basis_dim.dot(&y.mapv(|yi| yi.im));
+                let norm = re
+                    .iter()
+                    .chain(im.iter())
+                    .fold(0., |acc, x| f64::hypot(acc, *x));
+                for i in 0..n {
+                    vectors[[i, k]] = Complex64::new(re[i], im[i]) / norm;
+                }
+            }
+            return eigen_result(
+                &mat,
+                Array::from_vec(values),
+                vectors,
+                restarts,
+                converged,
+            );
+        }
+
+        // implicit restart, using the unwanted Ritz values as shifts, the
+        // complex ones together with their conjugate
+        let mut h_shifted = h_dim;
+        let mut q = Array2::eye(dim);
+        let mut idx = wanted;
+        while idx < dim {
+            let shift = ritz[order[idx]];
+            hessenberg_shifted_qr(&mut h_shifted, &mut q, shift);
+            idx += if shift.im == 0. { 1 } else { 2 };
+        }
+
+        // truncate the factorization to the wanted part:
+        // A V_k = V_k H_k + f e_k^T, with V_k = V_m Q[:, :k]
+        let basis_dim =
+            basis.slice_axis(Axis(1), Slice::from(0..dim)).to_owned();
+        let mut f =
+            basis_dim.dot(&q.column(wanted)) * h_shifted[[wanted, wanted - 1]];
+        f.scaled_add(beta * q[[dim - 1, wanted - 1]], &basis.column(dim));
+        let new_basis =
+            basis_dim.dot(&q.slice_axis(Axis(1), Slice::from(0..wanted)));
+        basis
+            .slice_axis_mut(Axis(1), Slice::from(0..wanted))
+            .assign(&new_basis);
+        hess.fill(0.);
+        hess.slice_axis_mut(Axis(0), Slice::from(0..wanted))
+            .slice_axis_mut(Axis(1), Slice::from(0..wanted))
+            .assign(
+                &h_shifted
+                    .slice_axis(Axis(0), Slice::from(0..wanted))
+                    .slice_axis(Axis(1), Slice::from(0..wanted)),
+            );
+        let f_norm = norm2(&f);
+        hess[[wanted, wanted - 1]] = f_norm;
+        if f_norm <= small * scale {
+            hess[[wanted, wanted - 1]] = 0.;
+            f = Array::from_shape_fn(n, |_| rng.next_float());
+            orthogonalize(&basis, wanted, &mut f);
+        }
+        let f_norm = norm2(&f);
+        basis.column_mut(wanted).assign(&f.mapv(|fi| fi / f_norm));
+        kept = wanted;
+        restarts += 1;
+    }
+}
+
+/// Compute the true residual norms of the eigenpairs
+fn eigen_result<I>(
+    mat: &CsMatViewI<f64, I>,
+    eigenvalues: Array<Complex64, Ix1>,
+    eigenvectors: Array2<Complex64>,
+    iterations: usize,
+    converged: bool,
+) -> EigenResult<f64, Complex64>
+where
+    I: SpIndex,
+{
+    let re = eigenvectors.mapv(|x| x.re);
+    let im = eigenvectors.mapv(|x| x.im);
+    let prod_re = mat * &re;
+    let prod_im = mat * &im;
+    let residual_norms = eigenvalues
+        .iter()
+        .enumerate()
+        .map(|(k, &val)| {
+            (0..mat.rows()).fold(0., |acc: f64, i| {
+                let prod = Complex64::new(prod_re[[i, k]], prod_im[[i, k]]);
+                acc.hypot((prod - val * eigenvectors[[i, k]]).norm())
+            })
+        })
+        .collect();
+    EigenResult {
+        eigenvalues: eigenvalues,
+        eigenvectors: eigenvectors,
+        residual_norms: residual_norms,
+        iterations: iterations,
+        converged: converged,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{EigenOptions, Which};
+    use num_complex::Complex64;
+    use sparse::permutation::{transform_mat_papt, PermOwned};
+    use sparse::{CsMat, TriMat};
+
+    /// Random walk on a directed cycle, which jumps back to the first node
+    /// with probability `0.2`. Its stationary distribution is proportional
+    /// to `0.8^i`.
+    fn cycle_with_reset(n: usize) -> CsMat<f64> {
+        let mut tri = TriMat::new((n, n));
+        for i in 0..n {
+            tri.add_triplet((i + 1) % n, i, 0.8);
+            tri.add_triplet(0, i, 0.2);
+        }
+        tri.to_csr()
+    }
+
+    #[test]
+    fn arnoldi_markov_chain() {
+        let n = 60;
+        let mat = cycle_with_reset(n);
+        let options = EigenOptions::new(1e-10, 300);
+        let res =
+            super::arnoldi(mat.view(), 1, Which::LargestMagnitude, &options);
+        assert!(res.converged());
+        assert!((res.eigenvalues()[0] - Complex64::new(1., 0.)).norm() < 1e-8);
+        assert!(res.residual_norms()[0] < 1e-8);
+        let stationary = res.eigenvectors().column(0).to_owned();
+        let first = stationary[0];
+        for (i, x) in stationary.iter().enumerate() {
+            let expected = first * 0.8f64.powi(i as i32);
+            assert!((x - expected).norm() < 1e-8);
+        }
+    }
+
+    /// A block upper triangular matrix, hidden by a symmetric permutation,
+    /// with eigenvalues `a_b +- i c_b` for each 2x2 diagonal block and
+    /// real eigenvalues `-1 - r` for the trailing diagonal entries.
+    fn block_triangular() -> (CsMat<f64>, Vec<Complex64>) {
+        let nb_blocks = 25;
+        let nb_real = 6;
+        let n = 2 * nb_blocks + nb_real;
+        let mut tri = TriMat::new((n, n));
+        let mut eigenvalues = Vec::new();
+        for b in 0..nb_blocks {
+            let a = b as f64 / 10.;
+            let c = 0.5 + (b % 3) as f64 * 0.2;
+            tri.add_triplet(2 * b, 2 * b, a);
+            tri.add_triplet(2 * b, 2 * b + 1, c);
+            tri.add_triplet(2 * b + 1, 2 * b, -c);
+            tri.add_triplet(2 * b + 1, 2 * b + 1, a);
+            eigenvalues.push(Complex64::new(a, c));
+            eigenvalues.push(Complex64::new(a, -c));
+            if 2 * b + 3 < n {
+                tri.add_triplet(2 * b, 2 * b + 3, 0.3);
+            }
+        }
+        for r in 0..nb_real {
+            let i = 2 * nb_blocks + r;
+            tri.add_triplet(i, i, -1. - r as f64);
+            tri.add_triplet(0, i, 1.);
+            eigenvalues.push(Complex64::new(-1. - r as f64, 0.));
+        }
+        let perm = PermOwned::new((0..n).map(|i| (i * 13) % n).collect());
+        let mat = transform_mat_papt(tri.to_csr().view(), perm.view());
+        (mat, eigenvalues)
+    }
+
+    #[test]
+    fn arnoldi_which() {
+        let (mat, mut expected) = block_triangular();
+        let options = EigenOptions::new(1e-10, 300);
+
+        // the third eigenvalue belongs to a conjugate pair
+        let res = super::arnoldi(mat.view(), 3, Which::LargestReal, &options);
+        assert!(res.converged());
+        assert_eq!(res.eigenvalues().len(), 3);
+        let top = [(2.4, 0.5), (2.4, -0.5), (2.3, 0.9)];
+        for (val, &(re, im)) in res.eigenvalues().iter().zip(&top) {
+            assert!((val - Complex64::new(re, im)).norm() < 1e-8);
+        }
+        assert!(res.residual_norms().iter().all(|&r| r < 1e-8));
+
+        let res = super::arnoldi(mat.view(), 4, Which::SmallestReal, &options);
+        assert!(res.converged());
+        for (val, &exp) in res.eigenvalues().iter().zip(&[-6., -5., -4., -3.]) {
+            assert!((val - Complex64::new(exp, 0.)).norm() < 1e-8);
+        }
+
+        let res =
+            super::arnoldi(mat.view(), 4, Which::LargestMagnitude, &options);
+        assert!(res.converged());
+        expected.sort_by(|a, b| b.norm().partial_cmp(&a.norm()).unwrap());
+        for (val, exp) in res.eigenvalues().iter().zip(&expected) {
+            assert!((val.norm() - exp.norm()).abs() < 1e-8);
+        }
+        assert!(res.residual_norms().iter().all(|&r| r < 1e-8));
+    }
+}
@@ -0,0 +1,357 @@
+///! Thick-restart Lanczos method
+///!
+///! Reference: K. Wu and H. Simon, Thick-restart Lanczos method for large
+///! symmetric eigenvalue problems, SIAM J. Matrix Anal. Appl. 22(2),
+///! 602-616 (2000).
+use ndarray::{Array, Array2, ArrayView1, ArrayViewMut1, Axis, Ix1, Slice};
+use num_traits::Float;
+
+use super::{check_matrix, EigenOptions, EigenResult, Which};
+use errors::SprsError;
+use indexing::SpIndex;
+use sparse::linalg::dense::{norm2, orthogonalize, symmetric_eigen, XorShift};
+use sparse::linalg::iterative::Preconditioner;
+use sparse::CsMatViewI;
+
+/// Compute `nev` eigenpairs of a symmetric matrix using the thick-restart
+/// Lanczos method.
+///
+/// The eigenvalues selected by `which` are computed. Eigenvalues of small
+/// magnitude are found much faster using `lanczos_shift_invert`.
+///
+/// The matrix can be stored in either CSR or CSC format. Its symmetry is
+/// only checked if requested by `options`. If the eigenpairs do not
+/// converge, the best approximations found are returned, and the result
+/// reports the absence of convergence.
+///
+/// # Errors
+///
+/// * `NonSymmetricMatrix` if the symmetry check was requested and failed
+///
+/// # Panics
+///
+/// * if `mat` is not square
+/// * if `nev` is zero or larger than the dimension of `mat`
+/// * if the subspace dimension requested in `options` is invalid
+/// * if `which` selects eigenvalues by their imaginary part, since the
+///   eigenvalues of a symmetric matrix are real
+pub fn lanczos<N, I>(
+    mat: CsMatViewI<N, I>,
+    nev: usize,
+    which: Which,
+    options: &EigenOptions<N>,
+) -> Result<EigenResult<N>, SprsError>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+{
+    assert!(which.is_real(), "eigenvalues of symmetric matrices are real");
+    check_matrix(&mat, options)?;
+    let op = |v: ArrayView1<N>, mut out: ArrayViewMut1<N>| {
+        out.assign(&(&mat * &v));
+    };
+    let (values, vectors, iterations, converged) =
+        thick_restart_lanczos(mat.rows(), op, nev, which, options);
+    Ok(eigen_result(&mat, values, vectors, iterations, converged))
+}
+
+/// Compute the `nev` eigenpairs of a symmetric matrix whose eigenvalues are
+/// the closest to `sigma`, using the thick-restart Lanczos method in
+/// shift-invert mode.
+///
+/// `shifted_inverse` should apply the inverse of `A - sigma I`. The Lanczos
+/// method is applied to this operator, whose largest eigenvalues `1 / (l -
+/// sigma)` correspond to the eigenvalues `l` of `A` closest to `sigma`, and
+/// thus converge quickly. The factorization of `A - sigma I` is usually
+/// obtained with a sparse direct solver such as `sprs_ldl::LdlNumeric`.
+///
+/// The eigenvalues are returned by increasing distance to `sigma`, and the
+/// tolerance of `options` applies to the eigenvalues of the inverse.
+///
+/// # Errors
+///
+/// * `NonSymmetricMatrix` if the symmetry check was requested and failed
+///
+/// # Panics
+///
+/// * if `mat` is not square
+/// * if `nev` is zero or larger than the dimension of `mat`
+/// * if the subspace dimension requested in `options` is invalid
+pub fn lanczos_shift_invert<N, I, P>(
+    mat: CsMatViewI<N, I>,
+    shifted_inverse: &P,
+    sigma: N,
+    nev: usize,
+    options: &EigenOptions<N>,
+) -> Result<EigenResult<N>, SprsError>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+    P: Preconditioner<N>,
+{
+    check_matrix(&mat, options)?;
+    let op = |v: ArrayView1<N>, out: ArrayViewMut1<N>| {
+        shifted_inverse.apply(v, out);
+    };
+    let (values, vectors, iterations, converged) = thick_restart_lanczos(
+        mat.rows(),
+        op,
+        nev,
+        Which::LargestMagnitude,
+        options,
+    );
+    let values = values.mapv(|theta| sigma + N::one() / theta);
+    Ok(eigen_result(&mat, values, vectors, iterations, converged))
+}
+
+/// Compute the true residual norms of the eigenpairs
+fn eigen_result<N, I>(
+    mat: &CsMatViewI<N, I>,
+    eigenvalues: Array<N, Ix1>,
+    eigenvectors: Array2<N>,
+    iterations: usize,
+    converged: bool,
+) -> EigenResult<N>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+{
+    let mut residuals = mat * &eigenvectors;
+    for (mut res, (vec, &val)) in residuals
+        .gencolumns_mut()
+        .into_iter()
+        .zip(eigenvectors.gencolumns().into_iter().zip(&eigenvalues))
+    {
+        res.scaled_add(-val, &vec);
+    }
+    let residual_norms = residuals
+        .gencolumns()
+        .into_iter()
+        .map(|res| res.iter().fold(N::zero(), |acc, &r| acc.hypot(r)))
+        .collect();
+    EigenResult {
+        eigenvalues: eigenvalues,
+        eigenvectors: eigenvectors,
+        residual_norms: residual_norms,
+        iterations: iterations,
+        converged: converged,
+    }
+}
+
+/// Core of the thick-restart Lanczos method for a symmetric operator `op`
+/// of dimension `n`, computing `out = Op v`.
+///
+/// Returns the wanted Ritz values and vectors, the number of restarts, and
+/// whether all wanted Ritz pairs have converged.
+fn thick_restart_lanczos<N, F>(
+    n: usize,
+    op: F,
+    nev: usize,
+    which: Which,
+    options: &EigenOptions<N>,
+) -> (Array<N, Ix1>, Array2<N>, usize, bool)
+where
+    N: 'static + Float,
+    F: Fn(ArrayView1<N>, ArrayViewMut1<N>),
+{
+    let m = options.subspace_dim_for(nev, n);
+    let small = N::from(n).expect("representable dimension") * N::epsilon();
+    let eps23 = N::epsilon().powf(N::from(2. / 3.).expect("representable"));
+    let mut rng = XorShift::new(n as u64);
+    // the Lanczos basis, with the next Lanczos vector in its last column,
+    // and the projection of the operator on the basis
+    let mut basis = Array2::zeros((n, m + 1));
+    let mut proj = Array2::zeros((m, m));
+    let mut w = Array::from_shape_fn(n, |_| rng.next_float());
+    let w_norm = norm2(&w);
+    basis.column_mut(0).assign(&w.mapv(|wi| wi / w_norm));
+
+    let mut kept = 0;
+    let mut restarts = 0;
+    loop {
+        // extend the Lanczos basis up to m vectors
+        let mut dim = m;
+        let mut beta = N::zero();
+        for j in kept..m {
+            // the product with a sparse matrix needs a contiguous vector
+            let v = basis.column(j).to_owned();
+            op(v.view(), w.view_mut());
+            let op_norm = norm2(&w);
+            let coeffs = orthogonalize(&basis, j + 1, &mut w);
+            for (i, &h) in coeffs.iter().enumerate() {
+                proj[[i, j]] = h;
+                proj[[j, i]] = h;
+            }
+            beta = norm2(&w);
+            if beta <= small * op_norm {
+                // invariant subspace, continue with a random vector
+                beta = N::zero();
+                w = Array::from_shape_fn(n, |_| rng.next_float());
+                let rand_norm = norm2(&w);
+                orthogonalize(&basis, j + 1, &mut w);
+                if norm2(&w) <= small * rand_norm {
+                    // the basis spans the whole space
+                    dim = j + 1;
+                    break;
+                }
+            }
+            let w_norm = norm2(&w);
+            basis.column_mut(j + 1).assign(&w.mapv(|wi| wi / w_norm));
+        }
+
+        // Rayleigh-Ritz projection
+        let sub_proj = proj
+            .slice_axis(Axis(0), Slice::from(0..dim))
+            .slice_axis(Axis(1), Slice::from(0..dim))
+            .to_owned();
+        let (theta, y) = symmetric_eigen(sub_proj);
+        let order = which.sort_real(theta.as_slice().expect("contiguous"));
+        let scale = theta.iter().fold(N::zero(), |acc, t| acc.max(t.abs()));
+        let converged = order[..nev].iter().all(|&i| {
+            let res = (beta * y[[dim - 1, i]]).abs();
+            res <= options.tol() * theta[i].abs().max(eps23 * scale)
+        });
+        let ritz_vectors = |count: usize| {
+            let mut sel = Array2::zeros((dim, count));
+            for (k, &i) in order[..count].iter().enumerate() {
+                sel.column_mut(k).assign(&y.column(i));
+            }
+            basis.slice_axis(Axis(1), Slice::from(0..dim)).dot(&sel)
+        };
+        if converged || dim < m || restarts >= options.max_restarts() {
+            let values = order[..nev].iter().map(|&i| theta[i]).collect();
+            let vectors = ritz_vectors(nev);
+            return (Array::from_vec(values), vectors, restarts, converged);
+        }
+
+        // thick restart, keeping the most wanted Ritz vectors and the next
+        // Lanczos vector
+        kept = (nev + (m - nev) / 2).min(m - 1);
+        let kept_vectors = ritz_vectors(kept);
+        let next = basis.column(m).to_owned();
+        basis
+            .slice_axis_mut(Axis(1), Slice::from(0..kept))
+            .assign(&kept_vectors);
+        basis.column_mut(kept).assign(&next);
+        proj.fill(N::zero());
+        for (k, &i) in order[..kept].iter().enumerate() {
+            proj[[k, k]] = theta[i];
+        }
+        restarts += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{EigenOptions, Which};
+    use errors::SprsError;
+    use ndarray::{Array, ArrayView1, ArrayViewMut1};
+    use sparse::linalg::iterative::Preconditioner;
+    use sparse::linalg::qr::SparseQr;
+    use sparse::{CsMat, TriMat};
+    use std::f64::consts::PI;
+
+    /// The 1D laplacian `tridiag(-1, 2, -1)`, whose eigenvalues are
+    /// `2 - 2 cos(k pi / (n + 1))` for `k` in `1..n + 1`
+    fn laplacian_1d(n: usize) -> CsMat<f64> {
+        let mut tri = TriMat::new((n, n));
+        for i in 0..n {
+            tri.add_triplet(i, i, 2.);
+            if i + 1 < n {
+                tri.add_triplet(i, i + 1, -1.);
+                tri.add_triplet(i + 1, i, -1.);
+            }
+        }
+        tri.to_csr()
+    }
+
+    fn laplacian_1d_eigenvalue(n: usize, k: usize) -> f64 {
+        2. - 2. * (k as f64 * PI / (n + 1) as f64).cos()
+    }
+
+    #[test]
+    fn lanczos_largest() {
+        let n = 100;
+        let mat = laplacian_1d(n);
+        let options = EigenOptions::new(1e-10, 500).with_symmetry_check(true);
+        let res = super::lanczos(mat.view(), 4, Which::LargestReal, &options)
+            .unwrap();
+        assert!(res.converged());
+        // the default subspace is too small to converge without restarts
+        assert!(res.iterations() > 0);
+        assert_eq!(res.eigenvectors().shape(), &[n, 4]);
+        for (k, &val) in res.eigenvalues().iter().enumerate() {
+            let expected = laplacian_1d_eigenvalue(n, n - k);
+            assert!((val - expected).abs() < 1e-8);
+        }
+        assert!(res.residual_norms().iter().all(|&r| r < 1e-8));
+        // the eigenvectors are orthonormal
+        let vecs = res.eigenvectors();
+        let gram = vecs.t().dot(&vecs);
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected = if i == j { 1. } else { 0. };
+                assert!((gram[[i, j]] - expected).abs() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn lanczos_small_dimension() {
+        // the Krylov subspace spans the whole space
+        let mat = laplacian_1d(6);
+        let options = EigenOptions::default();
+        let res = super::lanczos(mat.view(), 6, Which::SmallestReal, &options)
+            .unwrap();
+        assert!(res.converged());
+        for (k, &val) in res.eigenvalues().iter().enumerate() {
+            assert!((val - laplacian_1d_eigenvalue(6, k + 1)).abs() < 1e-10);
+        }
+
+        let non_sym =
+            CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1], vec![1., 2., 3.]);
+        let options = EigenOptions::default().with_symmetry_check(true);
+        let res =
+            super::lanczos(non_sym.view(), 1, Which::LargestReal, &options);
+        assert_eq!(res, Err(SprsError::NonSymmetricMatrix));
+    }
+
+    /// Apply the inverse of a matrix using its QR factorization
+    struct QrInverse(SparseQr<f64, usize>);
+
+    impl Preconditioner<f64> for QrInverse {
+        fn apply(&self, rhs: ArrayView1<f64>, mut out: ArrayViewMut1<f64>) {
+            let sol = self.0.solve_least_squares(&rhs.to_vec());
+            out.assign(&Array::from_vec(sol));
+        }
+    }
+
+    #[test]
+    fn lanczos_shift_invert() {
+        let n = 200;
+        let mat = laplacian_1d(n);
+        // eigenvalues closest to 1.01, which is not an eigenvalue
+        let sigma = 1.01;
+        let shifted = &mat - &(&CsMat::eye(n) * sigma);
+        let inverse = QrInverse(SparseQr::new(shifted.to_csc().view()));
+        let options = EigenOptions::new(1e-12, 100);
+        let res = super::lanczos_shift_invert(
+            mat.view(),
+            &inverse,
+            sigma,
+            3,
+            &options,
+        )
+        .unwrap();
+        assert!(res.converged());
+        let mut expected: Vec<f64> =
+            (1..n + 1).map(|k| laplacian_1d_eigenvalue(n, k)).collect();
+        expected.sort_by(|a, b| {
+            (a - sigma).abs().partial_cmp(&(b - sigma).abs()).unwrap()
+        });
+        for (&val, &exp) in res.eigenvalues().iter().zip(&expected) {
+            assert!((val - exp).abs() < 1e-10);
+        }
+        assert!(res.residual_norms().iter().all(|&r| r < 1e-8));
+    }
+}
@@ -0,0 +1,359 @@
+///! Locally Optimal Block Preconditioned Conjugate Gradient method
+///!
+///! Reference: A. V. Knyazev, Toward the optimal preconditioned eigensolver:
+///! Locally optimal block preconditioned conjugate gradient method, SIAM J.
+///! Sci. Comput. 23(2), 517-541 (2001).
+use ndarray::{Array, Array2, Axis, Ix1, Slice};
+use num_traits::Float;
+
+use super::{check_matrix, EigenOptions, EigenResult, Which};
+use errors::SprsError;
+use indexing::SpIndex;
+use sparse::linalg::dense::{norm2, symmetric_eigen};
+use sparse::linalg::iterative::Preconditioner;
+use sparse::CsMatViewI;
+
+/// Compute eigenpairs of the symmetric generalized eigenproblem
+/// `K x = l M x` using the LOBPCG method, where `K` is the `stiffness` matrix
+/// and `M` is the symmetric positive definite `mass` matrix.
+///
+/// As many eigenpairs as there are columns in the initial block `x` are
+/// computed, and `which` should select either the largest or the smallest
+/// eigenvalues. The preconditioner is applied to the residuals, and should
+/// approximate the inverse of `K - s M` for some `s` close to the wanted
+/// eigenvalues. It can be a factorization of `K` itself, such as
+/// `sprs_ldl::LdlNumeric`, when seeking the smallest eigenvalues of a
+/// positive definite problem. `IdentityPreconditioner` disables the
+/// preconditioning. A standard eigenproblem is solved by using the identity
+/// as `mass` matrix.
+///
+/// The returned eigenvectors are `M`-orthonormal, and an eigenpair is
+/// considered converged once `||K x - l M x|| <= tol * |l| * ||M x||`. The
+/// maximum number of iterations is given by `options.max_restarts()`,
+/// while the subspace dimension of `options` is not used. If the eigenpairs
+/// do not converge, the best approximations found are returned, and the
+/// result reports the absence of convergence.
+///
+/// The matrices can be stored in either CSR or CSC format. Their symmetry is
+/// only checked if requested by `options`.
+///
+/// # Errors
+///
+/// * `NonSymmetricMatrix` if the symmetry check was requested and failed
+///
+/// # Panics
+///
+/// * if `stiffness` or `mass` is not square
+/// * if the dimensions of `stiffness`, `mass` and `x` do not match
+/// * if `x` has no columns, or does not have full rank
+/// * if `which` is neither `LargestReal` nor `SmallestReal`
+pub fn lobpcg<N, I, P>(
+    stiffness: CsMatViewI<N, I>,
+    mass: CsMatViewI<N, I>,
+    precond: &P,
+    x: Array2<N>,
+    which: Which,
+    options: &EigenOptions<N>,
+) -> Result<EigenResult<N>, SprsError>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+    P: Preconditioner<N>,
+{
+    check_matrix(&stiffness, options)?;
+    check_matrix(&mass, options)?;
+    let n = stiffness.rows();
+    assert!(mass.rows() == n && x.rows() == n, "Dimension mismatch");
+    let nev = x.cols();
+    assert!(nev > 0, "the initial block should not be empty");
+    match which {
+        Which::LargestReal | Which::SmallestReal => (),
+        _ => panic!("LOBPCG computes the largest or smallest eigenvalues"),
+    }
+    let eps23 = N::epsilon().powf(N::from(2. / 3.).expect("representable"));
+
+    let (mut values, coeffs) = rayleigh_ritz(&stiffness, &mass, &x, nev, which);
+    assert!(
+        values.len() == nev,
+        "the initial block should have full rank"
+    );
+    let mut x = x.dot(&coeffs);
+    let mut p: Option<Array2<N>> = None;
+    let mut iterations = 0;
+    loop {
+        // residuals R = K X - M X diag(l)
+        let mut res = &stiffness * &x;
+        let mass_x = &mass * &x;
+        for (mut r, (mx, &val)) in res
+            .gencolumns_mut()
+            .into_iter()
+            .zip(mass_x.gencolumns().into_iter().zip(&values))
+        {
+            r.scaled_add(-val, &mx);
+        }
+        let residual_norms: Vec<N> = res
+            .gencolumns()
+            .into_iter()
+            .map(|r| r.iter().fold(N::zero(), |acc, &ri| acc.hypot(ri)))
+            .collect();
+        let scale = values.iter().fold(N::zero(), |acc, v| acc.max(v.abs()));
+        // only the unconverged columns are used to extend the subspace
+        let active: Vec<usize> = (0..nev)
+            .filter(|&j| {
+                let mx_norm = norm2(&mass_x.column(j).to_owned());
+                let thresh = values[j].abs().max(eps23 * scale) * mx_norm;
+                residual_norms[j] > options.tol() * thresh
+            })
+            .collect();
+        let converged = active.is_empty();
+        if converged || iterations >= options.max_restarts() {
+            return Ok(EigenResult {
+                eigenvalues: values,
+                eigenvectors: x,
+                residual_norms: residual_norms,
+                iterations: iterations,
+                converged: converged,
+            });
+        }
+
+        // search subspace [X, W, P], with W the preconditioned residuals
+        // and P the previous search directions of the active columns
+        let nb_active = active.len();
+        let nb_dirs = if p.is_some() { nb_active } else { 0 };
+        let mut subspace = Array2::zeros((n, nev + nb_active + nb_dirs));
+        subspace
+            .slice_axis_mut(Axis(1), Slice::from(0..nev))
+            .assign(&x);
+        for (k, &j) in active.iter().enumerate() {
+            let r = res.column(j).to_owned();
+            precond.apply(r.view(), subspace.column_mut(nev + k));
+            if let Some(ref p) = p {
+                subspace
+                    .column_mut(nev + nb_active + k)
+                    .assign(&p.column(j));
+            }
+        }
+        let (new_values, coeffs) =
+            rayleigh_ritz(&stiffness, &mass, &subspace, nev, which);
+        let dirs = subspace.slice_axis(Axis(1), Slice::from(nev..));
+        let dirs_coeffs = coeffs.slice_axis(Axis(0), Slice::from(nev..));
+        x = subspace.dot(&coeffs);
+        p = Some(dirs.dot(&dirs_coeffs));
+        values = new_values;
+        iterations += 1;
+    }
+}
+
+/// Rayleigh-Ritz projection of the pencil `(K, M)` on the subspace spanned
+/// by the columns of `basis`, which can be linearly dependent.
+///
+/// Returns the `nev` most wanted Ritz values, along with the coefficients
+/// of the `M`-orthonormal Ritz vectors in `basis`. Less than `nev` Ritz
+/// values are returned if the subspace has a smaller dimension.
+fn rayleigh_ritz<N, I>(
+    stiffness: &CsMatViewI<N, I>,
+    mass: &CsMatViewI<N, I>,
+    basis: &Array2<N>,
+    nev: usize,
+    which: Which,
+) -> (Array<N, Ix1>, Array2<N>)
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+{
+    let nb_cols = basis.cols();
+    let mut gram_k = basis.t().dot(&(stiffness * basis));
+    let mut gram_m = basis.t().dot(&(mass * basis));
+    // scale the basis vectors to unit M-norm, zero vectors being discarded
+    let scaling: Vec<N> = (0..nb_cols)
+        .map(|i| {
+            let d = gram_m[[i, i]];
+            if d > N::zero() {
+                N::one() / d.sqrt()
+            } else {
+                N::zero()
+            }
+        })
+        .collect();
+    for i in 0..nb_cols {
+        for j in 0..nb_cols {
+            gram_k[[i, j]] = gram_k[[i, j]] * scaling[i] * scaling[j];
+            gram_m[[i, j]] = gram_m[[i, j]] * scaling[i] * scaling[j];
+        }
+    }
+
+    // restrict the problem to the numerically independent directions,
+    // on which the projected mass matrix is well conditioned
+    let (gram_m_vals, gram_m_vecs) = symmetric_eigen(gram_m);
+    let max_val = gram_m_vals.iter().fold(N::zero(), |acc, &d| acc.max(d));
+    let independent: Vec<usize> = (0..nb_cols)
+        .filter(|&i| gram_m_vals[i] > max_val * N::epsilon().sqrt())
+        .collect();
+    let mut ortho = Array2::zeros((nb_cols, independent.len()));
+    for (mut col, &i) in ortho.gencolumns_mut().into_iter().zip(&independent) {
+        let inv_sqrt = N::one() / gram_m_vals[i].sqrt();
+        col.assign(&gram_m_vecs.column(i).mapv(|v| v * inv_sqrt));
+    }
+    let reduced = ortho.t().dot(&gram_k).dot(&ortho);
+    let (theta, vecs) = symmetric_eigen(reduced);
+    let order = which.sort_real(&theta.to_vec());
+    let nb_wanted = nev.min(order.len());
+
+    let mut coeffs = Array2::zeros((nb_cols, nb_wanted));
+    let mut values = Array::zeros(nb_wanted);
+    for (k, &i) in order[..nb_wanted].iter().enumerate() {
+        values[k] = theta[i];
+        coeffs.column_mut(k).assign(&ortho.dot(&vecs.column(i)));
+    }
+    for (mut row, &s) in coeffs.genrows_mut().into_iter().zip(&scaling) {
+        row.mapv_inplace(|c| c * s);
+    }
+    (values, coeffs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{EigenOptions, Which};
+    use ndarray::{Array, Array2};
+    use sparse::linalg::iterative::IdentityPreconditioner;
+    use sparse::{CsMat, TriMat};
+    use std::f64::consts::PI;
+    use test_data::grid_laplacian;
+
+    #[test]
+    fn lobpcg_standard() {
+        let (rows, cols) = (12, 10);
+        let lap = grid_laplacian(rows, cols);
+        let n = lap.rows();
+        let eye = CsMat::eye(n);
+        let x0 = Array2::from_shape_fn((n, 4), |(i, j)| {
+            ((i * (j + 1)) as f64 * 0.37).sin()
+        });
+        let options = EigenOptions::new(1e-8, 500);
+        let res = super::lobpcg(
+            lap.view(),
+            eye.view(),
+            &IdentityPreconditioner,
+            x0,
+            Which::SmallestReal,
+            &options,
+        )
+        .unwrap();
+        assert!(res.converged());
+        let mut expected = Vec::new();
+        for i in 1..rows + 1 {
+            for j in 1..cols + 1 {
+                let a = i as f64 * PI / (rows + 1) as f64;
+                let b = j as f64 * PI / (cols + 1) as f64;
+                expected.push(4. - 2. * a.cos() - 2. * b.cos());
+            }
+        }
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for (&val, &exp) in res.eigenvalues().iter().zip(&expected) {
+            assert!((val - exp).abs() < 1e-10);
+        }
+        assert!(res.residual_norms().iter().all(|&r| r < 1e-7));
+        let vecs = res.eigenvectors();
+        let gram = vecs.t().dot(&vecs);
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected = if i == j { 1. } else { 0. };
+                assert!((gram[[i, j]] - expected).abs() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn lobpcg_generalized() {
+        // a 1D laplacian with a diagonal mass matrix, equivalent to the
+        // standard problem for D^-1/2 K D^-1/2
+        let n = 80;
+        let mass_diag: Vec<f64> = (0..n).map(|i| 1. + (i % 3) as f64).collect();
+        let mut stiffness = TriMat::new((n, n));
+        let mut mass = TriMat::new((n, n));
+        let mut scaled = TriMat::new((n, n));
+        for i in 0..n {
+            stiffness.add_triplet(i, i, 2.);
+            mass.add_triplet(i, i, mass_diag[i]);
+            scaled.add_triplet(i, i, 2. / mass_diag[i]);
+            if i + 1 < n {
+                let s = (mass_diag[i] * mass_diag[i + 1]).sqrt();
+                stiffness.add_triplet(i, i + 1, -1.);
+                stiffness.add_triplet(i + 1, i, -1.);
+                scaled.add_triplet(i, i + 1, -1. / s);
+                scaled.add_triplet(i + 1, i, -1. / s);
+            }
+        }
+        let stiffness = stiffness.to_csr();
+        let mass = mass.to_csr();
+        let options = EigenOptions::new(1e-9, 1000).with_symmetry_check(true);
+        let expected = super::super::lanczos(
+            scaled.to_csr().view(),
+            3,
+            Which::LargestReal,
+            &options,
+        )
+        .unwrap();
+
+        let x0 = Array2::from_shape_fn((n, 3), |(i, j)| {
+            ((i * (j + 1)) as f64 * 0.71).cos()
+        });
+        let res = super::lobpcg(
+            stiffness.view(),
+            mass.view(),
+            &IdentityPreconditioner,
+            x0,
+            Which::LargestReal,
+            &options,
+        )
+        .unwrap();
+        assert!(res.converged());
+        for (val, exp) in res.eigenvalues().iter().zip(expected.eigenvalues()) {
+            assert!((val - exp).abs() < 1e-8);
+        }
+        // the eigenvectors are M-orthonormal
+        let vecs = res.eigenvectors().to_owned();
+        let gram = vecs.t().dot(&(&mass * &vecs));
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { 1. } else { 0. };
+                assert!((gram[[i, j]] - expected).abs() < 1e-10);
+            }
+        }
+        assert!(res.residual_norms().iter().all(|&r| r < 1e-7));
+    }
+
+    #[test]
+    #[should_panic]
+    fn lobpcg_rank_deficient_block() {
+        let lap = grid_laplacian(4, 4);
+        let eye = CsMat::eye(16);
+        let x0 = Array2::from_shape_fn((16, 2), |(i, _)| i as f64);
+        let _ = super::lobpcg(
+            lap.view(),
+            eye.view(),
+            &IdentityPreconditioner,
+            x0,
+            Which::SmallestReal,
+            &EigenOptions::default(),
+        );
+    }
+
+    #[test]
+    fn lobpcg_max_iter() {
+        let lap = grid_laplacian(20, 20);
+        let eye = CsMat::eye(400);
+        let x0 = Array::from_shape_fn((400, 2), |(i, j)| (i + j) as f64);
+        let res = super::lobpcg(
+            lap.view(),
+            eye.view(),
+            &IdentityPreconditioner,
+            x0,
+            Which::SmallestReal,
+            &EigenOptions::new(1e-12, 3),
+        )
+        .unwrap();
+        assert!(!res.converged());
+        assert_eq!(res.iterations(), 3);
+    }
+}
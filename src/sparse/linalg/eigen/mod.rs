@@ -0,0 +1,265 @@
+///! Eigensolvers for sparse matrices
+///!
+///! These solvers compute a few eigenpairs of a large sparse matrix, using
+///! only products of the matrix with vectors. The available solvers are:
+///!
+///! - the thick-restart Lanczos method (`lanczos`, `lanczos_shift_invert`)
+///!   for symmetric matrices.
+///! - the implicitly restarted Arnoldi method (`arnoldi`) for non-symmetric
+///!   matrices, whose eigenvalues can be complex.
+///! - the LOBPCG method (`lobpcg`) for symmetric generalized problems
+///!   `K x = l M x`, which iterates on a block of vectors and can be
+///!   preconditioned.
+///!
+///! The eigenvalues that are sought are described by `Which`, and the
+///! convergence of the solvers is controlled by `EigenOptions`.
+use ndarray::{Array, Array2, ArrayView1, ArrayView2, Ix1};
+use num_complex::Complex64;
+use num_traits::Float;
+
+use errors::SprsError;
+use indexing::SpIndex;
+use sparse::symmetric::is_symmetric;
+use sparse::CsMatViewI;
+
+mod arnoldi;
+mod lanczos;
+mod lobpcg;
+
+pub use self::arnoldi::arnoldi;
+pub use self::lanczos::{lanczos, lanczos_shift_invert};
+pub use self::lobpcg::lobpcg;
+
+/// Describe which eigenvalues should be computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Which {
+    /// The eigenvalues of largest magnitude
+    LargestMagnitude,
+    /// The eigenvalues of smallest magnitude
+    SmallestMagnitude,
+    /// The eigenvalues of largest real part
+    LargestReal,
+    /// The eigenvalues of smallest real part
+    SmallestReal,
+    /// The eigenvalues of largest imaginary part, in magnitude
+    LargestImaginary,
+    /// The eigenvalues of smallest imaginary part, in magnitude
+    SmallestImaginary,
+}
+
+impl Which {
+    /// Whether this selection is meaningful for real eigenvalues
+    fn is_real(&self) -> bool {
+        match *self {
+            Which::LargestImaginary | Which::SmallestImaginary => false,
+            _ => true,
+        }
+    }
+
+    /// Sort real eigenvalues from the most wanted to the least wanted.
+    /// Returns the sorting permutation.
+    ///
+    /// # Panics
+    ///
+    /// * if the selection is based on the imaginary part
+    fn sort_real<N: Float>(&self, values: &[N]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        let key = |i: usize| match *self {
+            Which::LargestMagnitude => -values[i].abs(),
+            Which::SmallestMagnitude => values[i].abs(),
+            Which::LargestReal => -values[i],
+            Which::SmallestReal => values[i],
+            Which::LargestImaginary | Which::SmallestImaginary => {
+                panic!("real eigenvalues cannot be selected by imaginary part")
+            }
+        };
+        order.sort_by(|&i, &j| {
+            key(i)
+                .partial_cmp(&key(j))
+                .expect("eigenvalues should not be NaN")
+        });
+        order
+    }
+
+    /// Sort complex eigenvalues from the most wanted to the least wanted,
+    /// keeping complex conjugate pairs next to each other, the one with
+    /// a positive imaginary part first. Returns the sorting permutation.
+    fn sort_complex(&self, values: &[Complex64]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        let key = |i: usize| {
+            let val = values[i];
+            let primary = match *self {
+                Which::LargestMagnitude => -val.norm(),
+                Which::SmallestMagnitude => val.norm(),
+                Which::LargestReal => -val.re,
+                Which::SmallestReal => val.re,
+                Which::LargestImaginary => -val.im.abs(),
+                Which::SmallestImaginary => val.im.abs(),
+            };
+            (primary, val.re, -val.im)
+        };
+        order.sort_by(|&i, &j| {
+            key(i)
+                .partial_cmp(&key(j))
+                .expect("eigenvalues should not be NaN")
+        });
+        order
+    }
+}
+
+/// Options controlling an eigensolver
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EigenOptions<N> {
+    tol: N,
+    max_restarts: usize,
+    subspace_dim: Option<usize>,
+    symmetry_check: bool,
+}
+
+impl<N: Float> EigenOptions<N> {
+    /// Create eigensolver options with the given relative tolerance and
+    /// maximum number of restarts.
+    ///
+    /// An eigenpair `(l, x)` is considered converged once
+    /// `||A x - l x|| <= tol * |l|`, with `||x|| = 1`. Eigenvalues close to
+    /// zero are instead compared to `eps^(2/3)` times the largest computed
+    /// eigenvalue.
+    ///
+    /// # Panics
+    ///
+    /// * if `tol` is negative
+    pub fn new(tol: N, max_restarts: usize) -> EigenOptions<N> {
+        assert!(tol >= N::zero(), "tolerance should be non-negative");
+        EigenOptions {
+            tol: tol,
+            max_restarts: max_restarts,
+            subspace_dim: None,
+            symmetry_check: false,
+        }
+    }
+
+    /// Set the dimension of the search subspace. By default, it is
+    /// `max(2 nev + 1, 20)`, bounded by the dimension of the matrix.
+    pub fn with_subspace_dim(mut self, dim: usize) -> EigenOptions<N> {
+        self.subspace_dim = Some(dim);
+        self
+    }
+
+    /// Check that the matrix is symmetric before solving, for the solvers
+    /// which require it.
+    pub fn with_symmetry_check(mut self, check: bool) -> EigenOptions<N> {
+        self.symmetry_check = check;
+        self
+    }
+
+    /// The relative tolerance on the residual norms
+    pub fn tol(&self) -> N {
+        self.tol
+    }
+
+    /// The maximum number of restarts
+    pub fn max_restarts(&self) -> usize {
+        self.max_restarts
+    }
+
+    /// The requested dimension of the search subspace
+    pub fn subspace_dim(&self) -> Option<usize> {
+        self.subspace_dim
+    }
+
+    /// Whether the symmetry of the matrix is checked
+    pub fn symmetry_check(&self) -> bool {
+        self.symmetry_check
+    }
+
+    /// The dimension of the search subspace used to compute `nev` eigenpairs
+    /// of a problem of dimension `n`
+    ///
+    /// # Panics
+    ///
+    /// * if `nev` is zero or larger than `n`
+    /// * if the requested subspace dimension is not in `nev + 1..n` while
+    ///   `nev < n`
+    pub fn subspace_dim_for(&self, nev: usize, n: usize) -> usize {
+        assert!(nev > 0, "at least one eigenvalue should be requested");
+        assert!(nev <= n, "more eigenvalues requested than the dimension");
+        match self.subspace_dim {
+            Some(dim) => {
+                assert!(
+                    dim <= n && (dim > nev || dim == n),
+                    "invalid subspace dimension"
+                );
+                dim
+            }
+            None => ::std::cmp::max(2 * nev + 1, 20).min(n),
+        }
+    }
+}
+
+impl<N: Float> Default for EigenOptions<N> {
+    /// A relative tolerance of `sqrt(eps)` and at most 300 restarts
+    fn default() -> EigenOptions<N> {
+        EigenOptions::new(N::epsilon().sqrt(), 300)
+    }
+}
+
+/// Check that a matrix is square, and symmetric if requested by `options`
+fn check_matrix<N, I>(
+    mat: &CsMatViewI<N, I>,
+    options: &EigenOptions<N>,
+) -> Result<(), SprsError>
+where
+    N: Float,
+    I: SpIndex,
+{
+    assert!(mat.rows() == mat.cols(), "matrix should be square");
+    if options.symmetry_check() && !is_symmetric(mat) {
+        return Err(SprsError::NonSymmetricMatrix);
+    }
+    Ok(())
+}
+
+/// Eigenpairs computed by an eigensolver, where the eigenvalues and
+/// eigenvectors have the scalar type `V`, which is complex for
+/// non-symmetric problems.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EigenResult<N, V = N> {
+    eigenvalues: Array<V, Ix1>,
+    eigenvectors: Array2<V>,
+    residual_norms: Vec<N>,
+    iterations: usize,
+    converged: bool,
+}
+
+impl<N: Copy, V: Copy> EigenResult<N, V> {
+    /// The eigenvalues, from the most wanted to the least wanted
+    pub fn eigenvalues(&self) -> ArrayView1<V> {
+        self.eigenvalues.view()
+    }
+
+    /// The normalized eigenvectors, stored as the columns of an array,
+    /// in the same order as the eigenvalues
+    pub fn eigenvectors(&self) -> ArrayView2<V> {
+        self.eigenvectors.view()
+    }
+
+    /// The norms of the residuals `A x - l x` of the eigenpairs
+    pub fn residual_norms(&self) -> &[N] {
+        &self.residual_norms[..]
+    }
+
+    /// The number of restarts or iterations performed
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Whether all requested eigenpairs reached the tolerance
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+
+    /// Get the eigenvalues and eigenvectors
+    pub fn into_eigenpairs(self) -> (Array<V, Ix1>, Array2<V>) {
+        (self.eigenvalues, self.eigenvectors)
+    }
+}
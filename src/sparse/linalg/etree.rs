@@ -1,7 +1,20 @@
 ///! Data structures to work with elimination trees (etree).
 ///! etrees arise when considering cholesky factorization, QR factorization, ...
+///!
+///! Along with the etree of a symmetric matrix, this module computes the
+///! usual analysis of the Cholesky factorization `L L^T = A`: a postorder
+///! of the tree, the levels of its nodes, and the number of non-zeros in
+///! each row and column of `L`, which give the memory needed by the
+///! factorization before it is computed.
+///!
+///! Reference: J. R. Gilbert, E. G. Ng and B. W. Peyton, An efficient
+///! algorithm to compute row and column counts for sparse Cholesky
+///! factorization, SIAM J. Matrix Anal. Appl. 15(4), 1075-1091 (1994).
 use std::ops::{Deref, DerefMut};
 
+use indexing::SpIndex;
+use sparse::CsMatViewI;
+
 pub type Parent = Option<usize>;
 
 /// Store an etree as the parent information of each node.
@@ -48,6 +61,102 @@ impl<S: Deref<Target = [Parent]>> Parents<S> {
             parents: &self.parents[..],
         }
     }
+
+    /// Compute a postorder of the tree, where the nodes of each subtree are
+    /// numbered consecutively, and before their root. The returned vector
+    /// gives the node at each position of the postorder. The children of
+    /// a node are visited by increasing index.
+    pub fn postorder(&self) -> Vec<usize> {
+        let n = self.nb_nodes();
+        // linked lists of children, built in reverse so that they are
+        // sorted by increasing index
+        let mut head: Vec<Option<usize>> = vec![None; n];
+        let mut next: Vec<Option<usize>> = vec![None; n];
+        for node in (0..n).rev() {
+            if let Some(parent) = self.parents[node] {
+                next[node] = head[parent];
+                head[parent] = Some(node);
+            }
+        }
+        let mut postorder = Vec::with_capacity(n);
+        let mut stack = Vec::with_capacity(n);
+        for root in (0..n).filter(|&node| self.is_root(node)) {
+            stack.push(root);
+            while let Some(&node) = stack.last() {
+                match head[node] {
+                    Some(child) => {
+                        head[node] = next[child];
+                        stack.push(child);
+                    }
+                    None => {
+                        stack.pop();
+                        postorder.push(node);
+                    }
+                }
+            }
+        }
+        postorder
+    }
+
+    /// The level of each node, ie its distance to the root of its tree
+    pub fn levels(&self) -> Vec<usize> {
+        let n = self.nb_nodes();
+        let mut levels: Vec<Option<usize>> = vec![None; n];
+        let mut path = Vec::new();
+        for node in 0..n {
+            let mut cur = node;
+            // climb until a node of known level
+            let mut level = loop {
+                if let Some(level) = levels[cur] {
+                    break level;
+                }
+                match self.parents[cur] {
+                    Some(parent) => {
+                        path.push(cur);
+                        cur = parent;
+                    }
+                    None => {
+                        levels[cur] = Some(0);
+                        break 0;
+                    }
+                }
+            };
+            while let Some(cur) = path.pop() {
+                level += 1;
+                levels[cur] = Some(level);
+            }
+        }
+        levels
+            .into_iter()
+            .map(|level| level.expect("all levels are computed"))
+            .collect()
+    }
+
+    /// For each node, the position in `postorder` of its first descendant,
+    /// which is the smallest position among the nodes of its subtree.
+    ///
+    /// # Panics
+    ///
+    /// * if `postorder` is not a permutation of the nodes of the tree
+    pub fn first_descendants(&self, postorder: &[usize]) -> Vec<usize> {
+        let n = self.nb_nodes();
+        assert_eq!(postorder.len(), n, "Dimension mismatch");
+        let mut first: Vec<Option<usize>> = vec![None; n];
+        for (pos, &node) in postorder.iter().enumerate() {
+            let mut cur = Some(node);
+            while let Some(node) = cur {
+                if first[node].is_some() {
+                    break;
+                }
+                first[node] = Some(pos);
+                cur = self.parents[node];
+            }
+        }
+        first
+            .into_iter()
+            .map(|pos| pos.expect("postorder should contain all nodes"))
+            .collect()
+    }
 }
 
 impl<S: DerefMut<Target = [Parent]>> Parents<S> {
@@ -98,3 +207,349 @@ impl Parents<Vec<Parent>> {
         }
     }
 }
+
+/// Compute the elimination tree of a symmetric matrix, ie the parent of
+/// each column of its Cholesky factor `L`, which is the row index of the
+/// first off-diagonal non-zero of this column.
+///
+/// Only the non-zero pattern of the matrix is used, which can be stored in
+/// either CSR or CSC format.
+///
+/// # Panics
+///
+/// * if `mat` is not square
+pub fn symmetric_etree<N, I>(mat: CsMatViewI<N, I>) -> ParentsOwned
+where
+    I: SpIndex,
+{
+    assert!(mat.rows() == mat.cols(), "matrix should be square");
+    let n = mat.rows();
+    let mut parents = ParentsOwned::new(n);
+    // the root of the subtree containing each node, with path compression
+    let mut ancestors: Vec<Option<usize>> = vec![None; n];
+    for (k, vec) in mat.outer_iterator().enumerate() {
+        for &ind in vec.indices() {
+            let mut node = Some(ind.index());
+            while let Some(i) = node {
+                if i >= k {
+                    break;
+                }
+                node = ancestors[i];
+                ancestors[i] = Some(k);
+                if node.is_none() {
+                    parents.set_parent(i, k);
+                }
+            }
+        }
+    }
+    parents
+}
+
+/// Compute the column elimination tree of a matrix `A`, ie the elimination
+/// tree of `A^T A`, without forming `A^T A`. It describes the dependencies
+/// between the columns in the QR factorization of `A`: the Householder
+/// reflection of a column only modifies its ancestors in the tree.
+///
+/// Only the non-zero pattern of the matrix is used, which should be stored
+/// in CSC format.
+///
+/// # Panics
+///
+/// * if `mat` is not in CSC format
+pub fn column_etree<N, I>(mat: CsMatViewI<N, I>) -> ParentsOwned
+where
+    I: SpIndex,
+{
+    assert!(mat.is_csc(), "Storage mismatch");
+    let n = mat.cols();
+    let mut parents = ParentsOwned::new(n);
+    let mut ancestors: Vec<Option<usize>> = vec![None; n];
+    // the last column having a non-zero in each row
+    let mut prev_cols: Vec<Option<usize>> = vec![None; mat.rows()];
+    for (k, vec) in mat.outer_iterator().enumerate() {
+        for &ind in vec.indices() {
+            let row = ind.index();
+            let mut node = prev_cols[row];
+            while let Some(i) = node {
+                if i >= k {
+                    break;
+                }
+                node = ancestors[i];
+                ancestors[i] = Some(k);
+                if node.is_none() {
+                    parents.set_parent(i, k);
+                }
+            }
+            prev_cols[row] = Some(k);
+        }
+    }
+    parents
+}
+
+/// The number of non-zeros in each row and column of the Cholesky factor
+/// `L` of a symmetric matrix, including the diagonal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CholeskyCounts {
+    row_counts: Vec<usize>,
+    col_counts: Vec<usize>,
+}
+
+impl CholeskyCounts {
+    /// Compute the row and column counts of the Cholesky factor of
+    /// a symmetric matrix, given its elimination tree and a postorder of
+    /// this tree, using the algorithm of Gilbert, Ng and Peyton, in almost
+    /// linear time in the number of non-zeros of the matrix.
+    ///
+    /// Only the non-zero pattern of the matrix is used, which can be stored
+    /// in either CSR or CSC format.
+    ///
+    /// # Panics
+    ///
+    /// * if `mat` is not square
+    /// * if the dimensions of `parents` or `postorder` do not match `mat`
+    pub fn new<N, I, S>(
+        mat: CsMatViewI<N, I>,
+        parents: &Parents<S>,
+        postorder: &[usize],
+    ) -> CholeskyCounts
+    where
+        I: SpIndex,
+        S: Deref<Target = [Parent]>,
+    {
+        assert!(mat.rows() == mat.cols(), "matrix should be square");
+        let n = mat.rows();
+        assert!(parents.nb_nodes() == n, "Dimension mismatch");
+        let first = parents.first_descendants(postorder);
+        let levels = parents.levels();
+
+        // the column counts are the sums over the subtrees of delta
+        let mut delta: Vec<isize> = (0..n)
+            .map(|node| {
+                let is_leaf = postorder[first[node]] == node;
+                if is_leaf {
+                    1
+                } else {
+                    0
+                }
+            })
+            .collect();
+        let mut row_counts = vec![1; n];
+        // the largest first descendant among the previous leaves of the row
+        // subtree of each node, and the previous leaf itself
+        let mut max_first: Vec<Option<usize>> = vec![None; n];
+        let mut prev_leaf: Vec<Option<usize>> = vec![None; n];
+        let mut ancestors: Vec<usize> = (0..n).collect();
+        for &j in postorder {
+            if let Some(parent) = parents.get_parent(j) {
+                delta[parent] -= 1;
+            }
+            let vec = mat.outer_view(j).expect("outer index in bounds");
+            for &ind in vec.indices() {
+                let i = ind.index();
+                // j is a leaf of the subtree of row i if none of its
+                // descendants was seen in that row
+                if i <= j || max_first[i].map_or(false, |m| first[j] <= m) {
+                    continue;
+                }
+                max_first[i] = Some(first[j]);
+                let lca = match prev_leaf[i] {
+                    None => i,
+                    Some(prev) => {
+                        let mut q = prev;
+                        while q != ancestors[q] {
+                            q = ancestors[q];
+                        }
+                        let mut s = prev;
+                        while s != q {
+                            let next = ancestors[s];
+                            ancestors[s] = q;
+                            s = next;
+                        }
+                        delta[q] -= 1;
+                        q
+                    }
+                };
+                prev_leaf[i] = Some(j);
+                delta[j] += 1;
+                row_counts[i] += levels[j] - levels[lca];
+            }
+            if let Some(parent) = parents.get_parent(j) {
+                ancestors[j] = parent;
+            }
+        }
+        for &j in postorder {
+            if let Some(parent) = parents.get_parent(j) {
+                delta[parent] += delta[j];
+            }
+        }
+        let col_counts = delta.into_iter().map(|d| d as usize).collect();
+        CholeskyCounts {
+            row_counts: row_counts,
+            col_counts: col_counts,
+        }
+    }
+
+    /// The number of non-zeros in each row of `L`
+    pub fn row_counts(&self) -> &[usize] {
+        &self.row_counts
+    }
+
+    /// The number of non-zeros in each column of `L`
+    pub fn col_counts(&self) -> &[usize] {
+        &self.col_counts
+    }
+
+    /// The number of non-zeros in `L`, including its diagonal
+    pub fn nnz(&self) -> usize {
+        self.col_counts.iter().sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{column_etree, symmetric_etree, CholeskyCounts, ParentsOwned};
+    use sparse::{CsMat, TriMat};
+    use test_data::grid_laplacian;
+
+    /// The non-zero pattern of the Cholesky factor, by dense symbolic
+    /// elimination, as `pattern[j][i]` for the entry `(i, j)`
+    fn dense_cholesky_pattern(mat: &CsMat<f64>) -> Vec<Vec<bool>> {
+        let n = mat.rows();
+        let mut pattern = vec![vec![false; n]; n];
+        for (_, (i, j)) in mat.iter() {
+            let (row, col) = if i > j { (i, j) } else { (j, i) };
+            pattern[col][row] = true;
+        }
+        for k in 0..n {
+            pattern[k][k] = true;
+            let below: Vec<usize> =
+                (k + 1..n).filter(|&i| pattern[k][i]).collect();
+            for &i in &below {
+                for &j in &below {
+                    if j >= i {
+                        pattern[i][j] = true;
+                    }
+                }
+            }
+        }
+        pattern
+    }
+
+    /// A symmetric matrix whose etree is a forest
+    fn forest_mat() -> CsMat<f64> {
+        let n = 9;
+        let mut tri = TriMat::new((n, n));
+        for i in 0..n {
+            tri.add_triplet(i, i, 4.);
+        }
+        for &(i, j) in &[(0, 5), (1, 2), (2, 5), (3, 4), (6, 8), (7, 8)] {
+            tri.add_triplet(i, j, -1.);
+            tri.add_triplet(j, i, -1.);
+        }
+        tri.to_csc()
+    }
+
+    #[test]
+    fn etree_postorder_levels() {
+        let mat = forest_mat();
+        let parents = symmetric_etree(mat.view());
+        let expected = [
+            Some(5),
+            Some(2),
+            Some(5),
+            Some(4),
+            None,
+            None,
+            Some(8),
+            Some(8),
+            None,
+        ];
+        for (node, &parent) in expected.iter().enumerate() {
+            assert_eq!(parents.get_parent(node), parent);
+        }
+        let postorder = parents.postorder();
+        assert_eq!(postorder, vec![3, 4, 0, 1, 2, 5, 6, 7, 8]);
+        assert_eq!(parents.levels(), vec![1, 2, 1, 1, 0, 0, 1, 1, 0]);
+        assert_eq!(
+            parents.first_descendants(&postorder),
+            vec![2, 3, 3, 0, 0, 2, 6, 7, 6]
+        );
+
+        let empty = ParentsOwned::new(0);
+        assert!(empty.postorder().is_empty());
+        assert!(empty.levels().is_empty());
+    }
+
+    #[test]
+    fn cholesky_counts() {
+        for mat in &[forest_mat(), grid_laplacian(5, 4), grid_laplacian(3, 7)] {
+            let n = mat.rows();
+            let pattern = dense_cholesky_pattern(mat);
+            for storage_mat in &[mat.to_csr(), mat.to_csc()] {
+                let parents = symmetric_etree(storage_mat.view());
+                let postorder = parents.postorder();
+                let counts = CholeskyCounts::new(
+                    storage_mat.view(),
+                    &parents,
+                    &postorder,
+                );
+                for j in 0..n {
+                    let col_count = pattern[j].iter().filter(|&&nz| nz).count();
+                    let row_count = (0..n).filter(|&k| pattern[k][j]).count();
+                    assert_eq!(counts.col_counts()[j], col_count);
+                    assert_eq!(counts.row_counts()[j], row_count);
+                    // the parent is the first off-diagonal non-zero
+                    let parent = (j + 1..n).find(|&i| pattern[j][i]);
+                    assert_eq!(parents.get_parent(j), parent);
+                }
+                let nnz = pattern
+                    .iter()
+                    .flat_map(|col| col.iter())
+                    .filter(|&&nz| nz)
+                    .count();
+                assert_eq!(counts.nnz(), nnz);
+            }
+        }
+    }
+
+    #[test]
+    fn column_etree_ata() {
+        let (m, n) = (9, 7);
+        let mut tri = TriMat::new((m, n));
+        for &(i, j) in &[
+            (0, 0),
+            (1, 0),
+            (1, 3),
+            (2, 1),
+            (3, 2),
+            (3, 4),
+            (4, 1),
+            (4, 5),
+            (5, 3),
+            (6, 6),
+            (7, 4),
+            (8, 2),
+            (8, 6),
+        ] {
+            tri.add_triplet(i, j, 1.);
+        }
+        let mat: CsMat<f64> = tri.to_csc();
+        // the pattern of A^T A
+        let mut ata = TriMat::new((n, n));
+        for row in mat.to_csr().outer_iterator() {
+            for (j, _) in row.iter() {
+                for (k, _) in row.iter() {
+                    ata.add_triplet(j, k, 1.);
+                }
+            }
+        }
+        let ata: CsMat<f64> = ata.to_csc();
+        let parents = column_etree(mat.view());
+        let expected = symmetric_etree(ata.view());
+        for j in 0..n {
+            assert_eq!(parents.get_parent(j), expected.get_parent(j));
+        }
+        assert_eq!(parents.get_parent(0), Some(3));
+        assert!(parents.is_root(6));
+    }
+}
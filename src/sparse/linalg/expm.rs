@@ -0,0 +1,332 @@
+///! Action of the matrix exponential on vectors
+///!
+///! Computing `exp(t A) B` for a sparse matrix `A` does not require forming
+///! the exponential, which is dense, as a truncated Taylor series of `exp(t
+///! A / s) B`, applied `s` times, is accurate for well chosen scaling
+///! parameters `s`. Only products of `A` with blocks of vectors are needed.
+///!
+///! Reference: A. H. Al-Mohy and N. J. Higham, Computing the action of the
+///! matrix exponential, with an application to exponential integrators,
+///! SIAM J. Sci. Comput. 33(2), 488-511 (2011).
+use ndarray::{Array, Array2, ArrayView1, ArrayView2, Axis, Ix1};
+
+use indexing::SpIndex;
+use sparse::CsMatViewI;
+
+/// The largest values of `||t A||_1` for which a Taylor series of degree
+/// `m` gives a backward error below the double precision unit roundoff,
+/// as `(m, theta_m)` pairs.
+const THETA: [(usize, f64); 35] = [
+    (1, 2.29e-16),
+    (2, 2.58e-8),
+    (3, 1.39e-5),
+    (4, 3.40e-4),
+    (5, 2.40e-3),
+    (6, 9.07e-3),
+    (7, 2.38e-2),
+    (8, 5.00e-2),
+    (9, 8.96e-2),
+    (10, 1.44e-1),
+    (11, 2.14e-1),
+    (12, 3.00e-1),
+    (13, 4.00e-1),
+    (14, 5.14e-1),
+    (15, 6.41e-1),
+    (16, 7.81e-1),
+    (17, 9.31e-1),
+    (18, 1.09),
+    (19, 1.26),
+    (20, 1.44),
+    (21, 1.62),
+    (22, 1.82),
+    (23, 2.01),
+    (24, 2.22),
+    (25, 2.43),
+    (26, 2.64),
+    (27, 2.86),
+    (28, 3.08),
+    (29, 3.31),
+    (30, 3.54),
+    (35, 4.7),
+    (40, 6.0),
+    (45, 7.2),
+    (50, 8.5),
+    (55, 9.9),
+];
+
+/// Compute `exp(t A) v` for a square sparse matrix `A` and a vector `v`.
+///
+/// The matrix can be stored in either CSR or CSC format.
+///
+/// # Panics
+///
+/// * if `mat` is not square
+/// * if the dimension of `v` does not match `mat`
+pub fn expm_multiply<I>(
+    mat: CsMatViewI<f64, I>,
+    t: f64,
+    v: ArrayView1<f64>,
+) -> Array<f64, Ix1>
+where
+    I: SpIndex,
+{
+    let block = v.insert_axis(Axis(1));
+    expm_multiply_block(mat, t, block).into_subview(Axis(1), 0)
+}
+
+/// Compute `exp(t A) B` for a square sparse matrix `A` and a block of
+/// vectors `B`, stored as the columns of an array.
+///
+/// The matrix can be stored in either CSR or CSC format.
+///
+/// # Panics
+///
+/// * if `mat` is not square
+/// * if the number of rows of `b` does not match `mat`
+pub fn expm_multiply_block<I>(
+    mat: CsMatViewI<f64, I>,
+    t: f64,
+    b: ArrayView2<f64>,
+) -> Array2<f64>
+where
+    I: SpIndex,
+{
+    check_dimensions(&mat, &b);
+    let mu = trace_mean(&mat);
+    expm_multiply_shifted(&mat, mu, t, b.to_owned())
+}
+
+/// Compute `exp(t A) B` at each of the time points `t` in `times`, for
+/// a square sparse matrix `A` and a block of vectors `B`, stored as the
+/// columns of an array.
+///
+/// The solution at each time point is obtained from the solution at the
+/// previous one, which is cheaper than computing each of them from `B`.
+///
+/// The matrix can be stored in either CSR or CSC format.
+///
+/// # Panics
+///
+/// * if `mat` is not square
+/// * if the number of rows of `b` does not match `mat`
+/// * if `times` is not sorted in increasing order
+pub fn expm_multiply_times<I>(
+    mat: CsMatViewI<f64, I>,
+    times: &[f64],
+    b: ArrayView2<f64>,
+) -> Vec<Array2<f64>>
+where
+    I: SpIndex,
+{
+    check_dimensions(&mat, &b);
+    assert!(
+        times.windows(2).all(|w| w[0] <= w[1]),
+        "time points should be sorted"
+    );
+    let mu = trace_mean(&mat);
+    let mut res: Vec<Array2<f64>> = Vec::with_capacity(times.len());
+    let mut prev_time = 0.;
+    for &time in times {
+        let start = match res.last() {
+            Some(prev) => prev.clone(),
+            None => b.to_owned(),
+        };
+        res.push(expm_multiply_shifted(&mat, mu, time - prev_time, start));
+        prev_time = time;
+    }
+    res
+}
+
+fn check_dimensions<I: SpIndex>(mat: &CsMatViewI<f64, I>, b: &ArrayView2<f64>) {
+    assert!(mat.rows() == mat.cols(), "matrix should be square");
+    assert!(mat.rows() == b.rows(), "Dimension mismatch");
+}
+
+/// The mean of the diagonal entries of a square matrix
+fn trace_mean<I: SpIndex>(mat: &CsMatViewI<f64, I>) -> f64 {
+    let n = mat.rows();
+    if n == 0 {
+        return 0.;
+    }
+    let trace = mat
+        .iter()
+        .filter(|&(_, (row, col))| row == col)
+        .fold(0., |acc, (&val, _)| acc + val);
+    trace / n as f64
+}
+
+/// The 1-norm of `A - mu I`
+fn shifted_one_norm<I: SpIndex>(mat: &CsMatViewI<f64, I>, mu: f64) -> f64 {
+    let mut col_sums = vec![0.; mat.cols()];
+    let mut has_diag = vec![false; mat.cols()];
+    for (&val, (row, col)) in mat.iter() {
+        let (row, col) = (row.index(), col.index());
+        if row == col {
+            col_sums[col] += (val - mu).abs();
+            has_diag[col] = true;
+        } else {
+            col_sums[col] += val.abs();
+        }
+    }
+    col_sums
+        .iter()
+        .zip(&has_diag)
+        .map(|(&sum, &diag)| if diag { sum } else { sum + mu.abs() })
+        .fold(0., f64::max)
+}
+
+/// The infinity norm of a block of vectors
+fn inf_norm(b: &Array2<f64>) -> f64 {
+    b.genrows()
+        .into_iter()
+        .map(|row| row.iter().fold(0., |acc, x| acc + x.abs()))
+        .fold(0., f64::max)
+}
+
+/// The degree `m` of the Taylor series and the number of scaling steps `s`
+/// which minimize the number of products `m s`, for a matrix of 1-norm
+/// `norm`.
+fn taylor_parameters(norm: f64) -> (usize, usize) {
+    if norm == 0. {
+        return (0, 1);
+    }
+    THETA
+        .iter()
+        .map(|&(m, theta)| (m, (norm / theta).ceil().max(1.) as usize))
+        .min_by_key(|&(m, s)| m * s)
+        .expect("non empty table")
+}
+
+/// Compute `exp(t A) B` as `exp(t mu) exp(t (A - mu I)) B`, where shifting
+/// by the mean of the eigenvalues `mu` reduces the norm of the matrix.
+fn expm_multiply_shifted<I: SpIndex>(
+    mat: &CsMatViewI<f64, I>,
+    mu: f64,
+    t: f64,
+    mut b: Array2<f64>,
+) -> Array2<f64> {
+    let tol = ::std::f64::EPSILON / 2.;
+    let norm = t.abs() * shifted_one_norm(mat, mu);
+    let (m, s) = taylor_parameters(norm);
+    let eta = (t * mu / s as f64).exp();
+    let mut f = b.clone();
+    for _ in 0..s {
+        let mut c1 = inf_norm(&b);
+        for j in 1..(m + 1) {
+            let coeff = t / (s * j) as f64;
+            let mut prod = mat * &b;
+            prod.scaled_add(-mu, &b);
+            prod.mapv_inplace(|x| x * coeff);
+            b = prod;
+            let c2 = inf_norm(&b);
+            f = f + &b;
+            if c1 + c2 <= tol * inf_norm(&f) {
+                break;
+            }
+            c1 = c2;
+        }
+        f.mapv_inplace(|x| x * eta);
+        b = f.clone();
+    }
+    f
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::{Array, Array2};
+    use sparse::{CsMat, TriMat};
+
+    /// Block diagonal matrix made of 2x2 blocks `| -d -w |`, whose
+    ///                                           `|  w -d |`
+    /// exponential is a damped rotation
+    fn damped_rotations(blocks: &[(f64, f64)]) -> CsMat<f64> {
+        let n = 2 * blocks.len();
+        let mut tri = TriMat::new((n, n));
+        for (k, &(d, w)) in blocks.iter().enumerate() {
+            tri.add_triplet(2 * k, 2 * k, -d);
+            tri.add_triplet(2 * k, 2 * k + 1, -w);
+            tri.add_triplet(2 * k + 1, 2 * k, w);
+            tri.add_triplet(2 * k + 1, 2 * k + 1, -d);
+        }
+        tri.to_csr()
+    }
+
+    fn damped_rotations_exp(
+        blocks: &[(f64, f64)],
+        t: f64,
+        v: &Array<f64, ::ndarray::Ix1>,
+    ) -> Array<f64, ::ndarray::Ix1> {
+        let mut res = Array::zeros(v.len());
+        for (k, &(d, w)) in blocks.iter().enumerate() {
+            let (c, s) = ((w * t).cos(), (w * t).sin());
+            let damp = (-d * t).exp();
+            res[2 * k] = damp * (c * v[2 * k] - s * v[2 * k + 1]);
+            res[2 * k + 1] = damp * (s * v[2 * k] + c * v[2 * k + 1]);
+        }
+        res
+    }
+
+    #[test]
+    fn expm_multiply_rotations() {
+        // large frequencies require several scaling steps
+        let blocks = [(0.5, 1.), (0.1, 50.), (3., 0.), (0., 7.)];
+        let mat = damped_rotations(&blocks);
+        let v = Array::from_vec(vec![1., 2., -1., 0.5, 3., -2., 0.3, 0.7]);
+        for mat in &[mat.to_owned(), mat.to_csc()] {
+            for &t in &[0., 0.3, 1., -0.5] {
+                let res = super::expm_multiply(mat.view(), t, v.view());
+                let expected = damped_rotations_exp(&blocks, t, &v);
+                for (x, y) in res.iter().zip(expected.iter()) {
+                    assert!((x - y).abs() < 1e-12 * (1. + y.abs()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn expm_multiply_markov_generator() {
+        // birth-death process, with generator columns summing to zero, so
+        // that probability is conserved
+        let n = 40;
+        let mut tri = TriMat::new((n, n));
+        for i in 0..n {
+            let up = if i + 1 < n { 2. } else { 0. };
+            let down = if i > 0 { 1. } else { 0. };
+            tri.add_triplet(i, i, -up - down);
+            if i + 1 < n {
+                tri.add_triplet(i + 1, i, up);
+            }
+            if i > 0 {
+                tri.add_triplet(i - 1, i, down);
+            }
+        }
+        let gen = tri.to_csc();
+        let mut p0 = Array2::zeros((n, 2));
+        p0[[0, 0]] = 1.;
+        p0[[n / 2, 1]] = 1.;
+        let times = [0.1, 0.5, 2., 2., 10.];
+        let res = super::expm_multiply_times(gen.view(), &times, p0.view());
+        assert_eq!(res.len(), times.len());
+        for (sol, &t) in res.iter().zip(&times) {
+            for col in sol.gencolumns() {
+                let mass = col.iter().fold(0., |acc, x| acc + x);
+                assert!((mass - 1.).abs() < 1e-12);
+                assert!(col.iter().all(|&p| p > -1e-12));
+            }
+            let direct = super::expm_multiply_block(gen.view(), t, p0.view());
+            for (x, y) in sol.iter().zip(direct.iter()) {
+                assert!((x - y).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn taylor_parameters() {
+        assert_eq!(super::taylor_parameters(0.), (0, 1));
+        let (m, s) = super::taylor_parameters(1e-3);
+        assert!(s == 1 && m <= 5);
+        let (m, s) = super::taylor_parameters(100.);
+        assert!(m as f64 * s as f64 >= 100.);
+        assert!(100. / s as f64 <= super::THETA[34].1);
+    }
+}
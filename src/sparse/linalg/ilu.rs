@@ -0,0 +1,455 @@
+///! Incomplete LU factorizations
+///!
+///! An incomplete LU factorization computes sparse triangular factors such
+///! that `L U` approximates `A`, while controlling the number of non-zeros
+///! in `L` and `U`. Solving with these factors is cheap, which makes them
+///! good preconditioners for the solvers in `linalg::iterative`.
+///!
+///! Two variants are available:
+///!
+///! - ILU(0), where `L + U` have the same sparsity pattern as `A`.
+///! - ILUT, where entries below a threshold are dropped, and only the
+///!   largest entries of each row are kept.
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use ndarray::{ArrayView1, ArrayViewMut1};
+use num_traits::{Float, Num};
+
+use errors::SprsError;
+use indexing::SpIndex;
+use sparse::linalg::iterative::Preconditioner;
+use sparse::linalg::trisolve::{lsolve_csr_dense_rhs, usolve_csr_dense_rhs};
+use sparse::{CsMatI, CsMatViewI};
+
+/// Incomplete LU factorization `L U ~= A`, where `L` is a unit lower
+/// triangular matrix and `U` is an upper triangular matrix.
+///
+/// Both factors are stored in CSR format, and the unit diagonal of `L` is
+/// stored explicitly.
+#[derive(Debug)]
+pub struct Ilu<N, I: SpIndex> {
+    l: CsMatI<N, I>,
+    u: CsMatI<N, I>,
+}
+
+/// Accumulates the rows of a triangular factor in CSR format
+struct CsrRows<N, I> {
+    indptr: Vec<I>,
+    indices: Vec<I>,
+    data: Vec<N>,
+}
+
+impl<N, I: SpIndex> CsrRows<N, I> {
+    fn with_capacity(n: usize, nnz: usize) -> CsrRows<N, I> {
+        let mut indptr = Vec::with_capacity(n + 1);
+        indptr.push(I::zero());
+        CsrRows {
+            indptr: indptr,
+            indices: Vec::with_capacity(nnz),
+            data: Vec::with_capacity(nnz),
+        }
+    }
+
+    fn push(&mut self, ind: usize, val: N) {
+        self.indices.push(I::from_usize(ind));
+        self.data.push(val);
+    }
+
+    fn end_row(&mut self) {
+        self.indptr.push(I::from_usize(self.indices.len()));
+    }
+
+    /// Non-zero range of the given row, which should be complete
+    fn row_range(&self, row: usize) -> (usize, usize) {
+        (self.indptr[row].index(), self.indptr[row + 1].index())
+    }
+
+    fn into_csr(self, n: usize) -> CsMatI<N, I>
+    where
+        N: Copy,
+    {
+        CsMatI::new((n, n), self.indptr, self.indices, self.data)
+    }
+}
+
+impl<N, I> Ilu<N, I>
+where
+    N: Copy + Num + Default,
+    I: SpIndex,
+{
+    /// Compute the ILU(0) factorization of a square matrix, in CSR or CSC
+    /// format. The non-zero pattern of `L + U` is the one of `mat`.
+    ///
+    /// Returns `SprsError::SingularMatrix` if a diagonal element is missing
+    /// from `mat`, or if a zero pivot is encountered.
+    ///
+    /// # Panics
+    ///
+    /// * if `mat` is not square
+    pub fn ilu0(mat: CsMatViewI<N, I>) -> Result<Ilu<N, I>, SprsError> {
+        assert_eq!(mat.rows(), mat.cols(), "matrix should be square");
+        let n = mat.rows();
+        let csr = mat.to_csr();
+        let indptr = csr.indptr();
+        let indices = csr.indices();
+        let mut data = csr.data().to_vec();
+
+        let mut diag_ptr = Vec::with_capacity(n);
+        for i in 0..n {
+            match csr.nnz_index(i, i) {
+                Some(ind) => diag_ptr.push(ind.0),
+                None => return Err(SprsError::SingularMatrix),
+            }
+        }
+
+        // Gaussian elimination in the IKJ order, restricted to the
+        // non-zero pattern of the matrix
+        let mut position = vec![None; n];
+        for i in 0..n {
+            let (start, stop) = (indptr[i].index(), indptr[i + 1].index());
+            for p in start..stop {
+                position[indices[p].index()] = Some(p);
+            }
+            // indices are sorted, so this range contains the (i, k), k < i
+            for p in start..diag_ptr[i] {
+                let k = indices[p].index();
+                let pivot = data[p] / data[diag_ptr[k]];
+                data[p] = pivot;
+                for q in (diag_ptr[k] + 1)..indptr[k + 1].index() {
+                    if let Some(pos) = position[indices[q].index()] {
+                        let u_kj = data[q];
+                        data[pos] = data[pos] - pivot * u_kj;
+                    }
+                }
+            }
+            if data[diag_ptr[i]] == N::zero() {
+                return Err(SprsError::SingularMatrix);
+            }
+            for p in start..stop {
+                position[indices[p].index()] = None;
+            }
+        }
+
+        let mut l = CsrRows::with_capacity(n, csr.nnz() + n);
+        let mut u = CsrRows::with_capacity(n, csr.nnz());
+        for i in 0..n {
+            let (start, stop) = (indptr[i].index(), indptr[i + 1].index());
+            for p in start..diag_ptr[i] {
+                l.push(indices[p].index(), data[p]);
+            }
+            l.push(i, N::one());
+            l.end_row();
+            for p in diag_ptr[i]..stop {
+                u.push(indices[p].index(), data[p]);
+            }
+            u.end_row();
+        }
+        Ok(Ilu {
+            l: l.into_csr(n),
+            u: u.into_csr(n),
+        })
+    }
+
+    /// The unit lower triangular factor
+    pub fn l(&self) -> CsMatViewI<N, I> {
+        self.l.view()
+    }
+
+    /// The upper triangular factor
+    pub fn u(&self) -> CsMatViewI<N, I> {
+        self.u.view()
+    }
+
+    /// The number of non-zero entries in `L` and `U`, the unit diagonal of
+    /// `L` being excluded.
+    pub fn nnz(&self) -> usize {
+        self.l.nnz() - self.l.rows() + self.u.nnz()
+    }
+
+    /// Solve the system `L U x = rhs`, with the result written into `rhs`.
+    ///
+    /// # Panics
+    ///
+    /// * if the length of `rhs` does not match the factorization
+    pub fn solve_in_place(&self, rhs: &mut [N]) {
+        // the factors are triangular with non-zero diagonals by construction
+        lsolve_csr_dense_rhs(self.l.view(), rhs).expect("non-singular L");
+        usolve_csr_dense_rhs(self.u.view(), rhs).expect("non-singular U");
+    }
+}
+
+impl<N, I> Ilu<N, I>
+where
+    N: Float + Default,
+    I: SpIndex,
+{
+    /// Compute the ILUT factorization of a square matrix, in CSR or CSC
+    /// format.
+    ///
+    /// During the elimination of row `i`, any entry whose magnitude is lower
+    /// than `drop_tol` times the euclidean norm of row `i` of `mat` is
+    /// dropped. Among the remaining entries, at most `max_fill` entries
+    /// of largest magnitude are kept in the strictly lower part of the row,
+    /// and at most `max_fill` in its strictly upper part. The diagonal is
+    /// always kept.
+    ///
+    /// Returns `SprsError::SingularMatrix` if a zero pivot is encountered.
+    ///
+    /// # Panics
+    ///
+    /// * if `mat` is not square
+    /// * if `drop_tol` is negative
+    pub fn ilut(
+        mat: CsMatViewI<N, I>,
+        drop_tol: N,
+        max_fill: usize,
+    ) -> Result<Ilu<N, I>, SprsError> {
+        assert_eq!(mat.rows(), mat.cols(), "matrix should be square");
+        assert!(drop_tol >= N::zero(), "drop_tol should be non-negative");
+        let n = mat.rows();
+        let csr = mat.to_csr();
+        let fill_estimate = csr.nnz() + n;
+        let mut l = CsrRows::with_capacity(n, fill_estimate);
+        let mut u: CsrRows<N, I> = CsrRows::with_capacity(n, fill_estimate);
+        let mut u_diag = Vec::with_capacity(n);
+
+        // dense work row, along with its non-zero pattern
+        let mut work = vec![N::zero(); n];
+        let mut in_pattern = vec![false; n];
+        let mut pattern = Vec::with_capacity(n);
+        let mut lower_queue = BinaryHeap::new();
+
+        for (i, row) in csr.outer_iterator().enumerate() {
+            let row_norm = row
+                .iter()
+                .fold(N::zero(), |acc, (_, &v)| acc + v * v)
+                .sqrt();
+            let tau = drop_tol * row_norm;
+            for (j, &val) in row.iter() {
+                work[j] = val;
+                in_pattern[j] = true;
+                pattern.push(j);
+                if j < i {
+                    lower_queue.push(Reverse(j));
+                }
+            }
+
+            // eliminate the lower part in increasing column order, fill-in
+            // in the lower part being processed as it appears
+            while let Some(Reverse(k)) = lower_queue.pop() {
+                let pivot = work[k] / u_diag[k];
+                if pivot.abs() < tau {
+                    work[k] = N::zero();
+                    continue;
+                }
+                work[k] = pivot;
+                let (start, stop) = u.row_range(k);
+                // the diagonal of U is the first element of its row
+                for p in (start + 1)..stop {
+                    let j = u.indices[p].index();
+                    work[j] = work[j] - pivot * u.data[p];
+                    if !in_pattern[j] {
+                        in_pattern[j] = true;
+                        pattern.push(j);
+                        if j < i {
+                            lower_queue.push(Reverse(j));
+                        }
+                    }
+                }
+            }
+
+            let diag = work[i];
+            if diag == N::zero() {
+                return Err(SprsError::SingularMatrix);
+            }
+            let mut lower = Vec::new();
+            let mut upper = Vec::new();
+            for &j in &pattern {
+                let val = work[j];
+                if j != i && val != N::zero() && val.abs() >= tau {
+                    if j < i {
+                        lower.push((j, val));
+                    } else {
+                        upper.push((j, val));
+                    }
+                }
+                work[j] = N::zero();
+                in_pattern[j] = false;
+            }
+            pattern.clear();
+
+            for &(j, val) in keep_largest(&mut lower, max_fill).iter() {
+                l.push(j, val);
+            }
+            l.push(i, N::one());
+            l.end_row();
+            u.push(i, diag);
+            for &(j, val) in keep_largest(&mut upper, max_fill).iter() {
+                u.push(j, val);
+            }
+            u.end_row();
+            u_diag.push(diag);
+        }
+
+        Ok(Ilu {
+            l: l.into_csr(n),
+            u: u.into_csr(n),
+        })
+    }
+}
+
+/// Keep at most `count` entries of largest magnitude, and sort them by index
+fn keep_largest<N: Float>(
+    entries: &mut Vec<(usize, N)>,
+    count: usize,
+) -> &[(usize, N)] {
+    if entries.len() > count {
+        entries.sort_by(|&(_, a), &(_, b)| {
+            b.abs()
+                .partial_cmp(&a.abs())
+                .expect("no NaN in factorization")
+        });
+        entries.truncate(count);
+    }
+    entries.sort_by_key(|&(j, _)| j);
+    &entries[..]
+}
+
+impl<N, I> Preconditioner<N> for Ilu<N, I>
+where
+    N: Copy + Num + Default,
+    I: SpIndex,
+{
+    fn apply(&self, rhs: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        let mut x = rhs.to_vec();
+        self.solve_in_place(&mut x);
+        for (o, xi) in out.iter_mut().zip(x.into_iter()) {
+            *o = xi;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Ilu;
+    use errors::SprsError;
+    use ndarray::Array;
+    use sparse::linalg::iterative::{
+        pgmres, PreconditionerSide, StoppingCriteria,
+    };
+    use sparse::CsMat;
+    use test_data::{grid_convection_diffusion, grid_laplacian};
+
+    #[test]
+    fn ilu0_tridiagonal_is_exact() {
+        // a tridiagonal matrix has no fill-in, so ILU(0) is an exact LU
+        // |2 1    |
+        // |1 3 1  |
+        // |  1 3 1|
+        // |    1 2|
+        let mat = CsMat::new_csc(
+            (4, 4),
+            vec![0, 2, 5, 8, 10],
+            vec![0, 1, 0, 1, 2, 1, 2, 3, 2, 3],
+            vec![2., 1., 1., 3., 1., 1., 3., 1., 1., 2.],
+        );
+        let ilu = Ilu::ilu0(mat.view()).unwrap();
+        let lu = &ilu.l() * &ilu.u();
+        let diff = (&lu - &mat.to_csr()).to_dense();
+        assert!(diff.iter().all(|d: &f64| d.abs() < 1e-12));
+        assert_eq!(ilu.l().indptr(), &[0, 1, 3, 5, 7]);
+        assert_eq!(ilu.u().indptr(), &[0, 2, 4, 6, 7]);
+
+        let mut x = vec![3., 5., 5., 3.];
+        ilu.solve_in_place(&mut x);
+        for &xi in &x {
+            assert!((xi - 1.).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn ilu0_pattern() {
+        let mat = grid_laplacian(5, 6);
+        let ilu = Ilu::ilu0(mat.view()).unwrap();
+        assert_eq!(ilu.nnz(), mat.nnz());
+        // ILU(0) is exact on the pattern of A
+        let lu = &ilu.l() * &ilu.u();
+        for (&val, (row, col)) in mat.iter() {
+            assert!((lu.get(row, col).unwrap() - val).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn ilu_singular() {
+        let missing_diag =
+            CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0], vec![1., 1.]);
+        assert_eq!(
+            Ilu::ilu0(missing_diag.view()).unwrap_err(),
+            SprsError::SingularMatrix
+        );
+        let singular = CsMat::new(
+            (2, 2),
+            vec![0, 2, 4],
+            vec![0, 1, 0, 1],
+            vec![1., 2., 2., 4.],
+        );
+        assert_eq!(
+            Ilu::ilut(singular.view(), 0., 2).unwrap_err(),
+            SprsError::SingularMatrix
+        );
+    }
+
+    #[test]
+    fn ilut_no_dropping_is_exact() {
+        let mat = grid_convection_diffusion(4, 5, 2.);
+        let n = mat.rows();
+        let ilu = Ilu::ilut(mat.view(), 0., n).unwrap();
+        let lu = &ilu.l() * &ilu.u();
+        let diff = (&lu - &mat).to_dense();
+        assert!(diff.iter().all(|d: &f64| d.abs() < 1e-12));
+        // complete LU has fill-in
+        assert!(ilu.nnz() > mat.nnz());
+    }
+
+    #[test]
+    fn ilut_dropping_and_preconditioning() {
+        let mat = grid_convection_diffusion(12, 12, 3.);
+        let n = mat.rows();
+        let ilut = Ilu::ilut(mat.view(), 1e-2, 4).unwrap();
+        for row in ilut.l().outer_iterator().chain(ilut.u().outer_iterator()) {
+            assert!(row.nnz() <= 5);
+        }
+        let ilu0 = Ilu::ilu0(mat.view()).unwrap();
+
+        let x0 = Array::from_shape_fn(n, |i| (i as f64).sqrt());
+        let b = &mat * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 300);
+        let mut iterations = Vec::new();
+        for precond in &[ilu0, ilut] {
+            let mut x = Array::zeros(n);
+            let report = pgmres(
+                mat.view(),
+                b.view(),
+                x.view_mut(),
+                20,
+                precond,
+                PreconditionerSide::Right,
+                &criteria,
+            );
+            assert!(report.converged());
+            iterations.push(report.iterations());
+        }
+        let mut x = Array::zeros(n);
+        let report = pgmres(
+            mat.view(),
+            b.view(),
+            x.view_mut(),
+            20,
+            &super::super::iterative::IdentityPreconditioner,
+            PreconditionerSide::Right,
+            &criteria,
+        );
+        assert!(iterations.iter().all(|&it| it < report.iterations()));
+    }
+}
@@ -0,0 +1,203 @@
+///! BiConjugate Gradient Stabilized method
+use ndarray::{Array, ArrayView1, ArrayViewMut1};
+use num_traits::Float;
+
+use super::{
+    dot, norm2, solve_preconditioned, IdentityPreconditioner, PreconditionedOp,
+    Preconditioner, PreconditionerSide, SolveReport, StoppingCriteria,
+};
+use sparse::linalg::operator::LinearOperator;
+
+/// Solve the system `A x = b` using the BiCGSTAB method.
+///
+/// `x` holds the initial guess on entry, and the approximate solution on exit.
+///
+/// Each iteration requires two matrix vector products.
+///
+/// # Panics
+///
+/// * if `op` is not square
+/// * if the dimensions of `rhs` or `x` do not match `op`
+pub fn bicgstab<N, Op>(
+    op: Op,
+    rhs: ArrayView1<N>,
+    x: ArrayViewMut1<N>,
+    criteria: &StoppingCriteria<N>,
+) -> SolveReport<N>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+{
+    pbicgstab(
+        op,
+        rhs,
+        x,
+        &IdentityPreconditioner,
+        PreconditionerSide::Left,
+        criteria,
+    )
+}
+
+/// Solve the system `A x = b` using the preconditioned BiCGSTAB method.
+///
+/// `x` holds the initial guess on entry, and the approximate solution on exit.
+/// See `PreconditionerSide` for the meaning of the reported residuals.
+///
+/// The solve stops early, without convergence, if the method breaks down,
+/// which can happen if the shadow residual becomes orthogonal to the
+/// residual.
+///
+/// # Panics
+///
+/// * if `op` is not square
+/// * if the dimensions of `rhs` or `x` do not match `op`
+pub fn pbicgstab<N, Op, P>(
+    op: Op,
+    rhs: ArrayView1<N>,
+    x: ArrayViewMut1<N>,
+    precond: &P,
+    side: PreconditionerSide,
+    criteria: &StoppingCriteria<N>,
+) -> SolveReport<N>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+    P: Preconditioner<N>,
+{
+    let max_iter = criteria.max_iter();
+    solve_preconditioned(
+        op,
+        rhs,
+        x,
+        precond,
+        side,
+        criteria,
+        |op, rhs, x, threshold| bicgstab_impl(op, rhs, x, threshold, max_iter),
+    )
+}
+
+fn bicgstab_impl<N, Op, P>(
+    op: &PreconditionedOp<Op, P>,
+    rhs: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    threshold: N,
+    max_iter: usize,
+) -> SolveReport<N>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+    P: Preconditioner<N>,
+{
+    let n = rhs.len();
+    let mut r = &rhs - &op.apply(x.view());
+    let mut report = SolveReport::new(norm2(&r.view()));
+    if report.residual_norm() <= threshold {
+        report.converged = true;
+        return report;
+    }
+
+    let r_shadow = r.clone();
+    let mut rho = N::one();
+    let mut alpha = N::one();
+    let mut omega = N::one();
+    let mut p = Array::zeros(n);
+    let mut v = Array::zeros(n);
+
+    while report.iterations() < max_iter {
+        let rho_next = dot(&r_shadow.view(), &r.view());
+        if rho_next == N::zero() {
+            break;
+        }
+        let beta = (rho_next / rho) * (alpha / omega);
+        rho = rho_next;
+        // p = r + beta * (p - omega * v)
+        p.scaled_add(-omega, &v);
+        p.zip_mut_with(&r, |pi, &ri| *pi = ri + beta * *pi);
+
+        v = op.apply(p.view());
+        let shadow_v = dot(&r_shadow.view(), &v.view());
+        if shadow_v == N::zero() {
+            break;
+        }
+        alpha = rho / shadow_v;
+        // r now holds s = r - alpha * v
+        r.scaled_add(-alpha, &v);
+        x.scaled_add(alpha, &p);
+        let s_norm = norm2(&r.view());
+        if s_norm <= threshold {
+            report.push_iteration(s_norm);
+            report.converged = true;
+            break;
+        }
+
+        let t = op.apply(r.view());
+        let tt = dot(&t.view(), &t.view());
+        if tt == N::zero() {
+            report.push_iteration(s_norm);
+            break;
+        }
+        omega = dot(&t.view(), &r.view()) / tt;
+        x.scaled_add(omega, &r);
+        r.scaled_add(-omega, &t);
+        let res_norm = norm2(&r.view());
+        report.push_iteration(res_norm);
+        if res_norm <= threshold {
+            report.converged = true;
+            break;
+        }
+        if omega == N::zero() {
+            break;
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::Jacobi;
+    use super::super::{PreconditionerSide, StoppingCriteria};
+    use ndarray::Array;
+    use test_data::{grid_convection_diffusion, max_abs_diff};
+
+    #[test]
+    fn bicgstab_convection_diffusion() {
+        let mat = grid_convection_diffusion(8, 9, 1.5);
+        let n = mat.rows();
+        let x0 = Array::from_shape_fn(n, |i| (i as f64 * 0.3).cos());
+        let b = &mat * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 200);
+        for m in &[mat.to_owned(), mat.to_csc()] {
+            let mut x = Array::zeros(n);
+            let report =
+                super::bicgstab(m.view(), b.view(), x.view_mut(), &criteria);
+            assert!(report.converged());
+            let residual = &b - &(&mat * &x);
+            let res_norm = residual.dot(&residual).sqrt();
+            assert!(res_norm <= 1e-10 * b.dot(&b).sqrt() * 10.);
+        }
+    }
+
+    #[test]
+    fn pbicgstab_left_right() {
+        let mat = grid_convection_diffusion(10, 10, 3.);
+        let n = mat.rows();
+        let x0 = Array::from_shape_fn(n, |i| 1. + (i % 7) as f64);
+        let b = &mat * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 200);
+        let jacobi = Jacobi::new(mat.view());
+        for &side in &[PreconditionerSide::Left, PreconditionerSide::Right] {
+            let mut x = Array::from_elem(n, 1.);
+            let report = super::pbicgstab(
+                mat.view(),
+                b.view(),
+                x.view_mut(),
+                &jacobi,
+                side,
+                &criteria,
+            );
+            assert!(report.converged());
+            let err = max_abs_diff(&x, &x0);
+            assert!(err < 1e-7);
+        }
+    }
+}
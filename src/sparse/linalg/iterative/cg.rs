@@ -0,0 +1,189 @@
+///! Conjugate Gradient method
+use ndarray::{Array, ArrayView1, ArrayViewMut1};
+use num_traits::Float;
+
+use super::{
+    apply_op, check_system_dimensions, dot, norm2, IdentityPreconditioner,
+    Preconditioner, SolveReport, StoppingCriteria,
+};
+use sparse::linalg::operator::LinearOperator;
+
+/// Solve the symmetric positive definite system `A x = b` using the
+/// Conjugate Gradient method.
+///
+/// `x` holds the initial guess on entry, and the approximate solution on exit.
+///
+/// # Panics
+///
+/// * if `op` is not square
+/// * if the dimensions of `rhs` or `x` do not match `op`
+pub fn cg<N, Op>(
+    op: Op,
+    rhs: ArrayView1<N>,
+    x: ArrayViewMut1<N>,
+    criteria: &StoppingCriteria<N>,
+) -> SolveReport<N>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+{
+    pcg(op, rhs, x, &IdentityPreconditioner, criteria)
+}
+
+/// Solve the symmetric positive definite system `A x = b` using the
+/// preconditioned Conjugate Gradient method.
+///
+/// The preconditioner should be symmetric positive definite as well.
+/// `x` holds the initial guess on entry, and the approximate solution on exit.
+///
+/// The solve stops early, without convergence, if a non-positive curvature
+/// `p^T A p` is encountered, which means the matrix is not positive definite.
+///
+/// # Panics
+///
+/// * if `op` is not square
+/// * if the dimensions of `rhs` or `x` do not match `op`
+pub fn pcg<N, Op, P>(
+    op: Op,
+    rhs: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    precond: &P,
+    criteria: &StoppingCriteria<N>,
+) -> SolveReport<N>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+    P: Preconditioner<N>,
+{
+    check_system_dimensions(&op, &rhs, &x);
+    let n = rhs.len();
+
+    let mut r = &rhs - &apply_op(&op, x.view());
+    let mut res_norm = norm2(&r.view());
+    let mut report = SolveReport::new(res_norm);
+    let threshold = criteria.tol() * norm2(&rhs);
+    if res_norm <= threshold {
+        report.converged = true;
+        return report;
+    }
+
+    let mut z = Array::zeros(n);
+    precond.apply(r.view(), z.view_mut());
+    let mut p = z.clone();
+    let mut rz = dot(&r.view(), &z.view());
+
+    while report.iterations < criteria.max_iter() {
+        let ap = apply_op(&op, p.view());
+        let curvature = dot(&p.view(), &ap.view());
+        if curvature <= N::zero() {
+            break;
+        }
+        let alpha = rz / curvature;
+        x.scaled_add(alpha, &p);
+        r.scaled_add(-alpha, &ap);
+        res_norm = norm2(&r.view());
+        report.push_iteration(res_norm);
+        if res_norm <= threshold {
+            report.converged = true;
+            break;
+        }
+
+        precond.apply(r.view(), z.view_mut());
+        let rz_next = dot(&r.view(), &z.view());
+        let beta = rz_next / rz;
+        rz = rz_next;
+        p.zip_mut_with(&z, |pi, &zi| *pi = zi + beta * *pi);
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::Jacobi;
+    use super::super::StoppingCriteria;
+    use ndarray::{arr1, Array};
+    use sparse::linalg::operator::{DiagonalOperator, SumOperator};
+    use sparse::CsMat;
+    use test_data::{grid_laplacian, max_abs_diff};
+
+    #[test]
+    fn cg_small_spd() {
+        // |4 1 0| |1|   |6|
+        // |1 3 1| |2| = |10|
+        // |0 1 2| |3|   |8|
+        let mat = CsMat::new(
+            (3, 3),
+            vec![0, 2, 5, 7],
+            vec![0, 1, 0, 1, 2, 1, 2],
+            vec![4., 1., 1., 3., 1., 1., 2.],
+        );
+        let b = arr1(&[6., 10., 8.]);
+        let mut x = Array::zeros(3);
+        let criteria = StoppingCriteria::new(1e-12_f64, 10);
+        let report = super::cg(mat.view(), b.view(), x.view_mut(), &criteria);
+        assert!(report.converged());
+        assert!(report.iterations() <= 3);
+        for (&xi, &ei) in x.iter().zip(&[1., 2., 3.]) {
+            assert!((xi - ei).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn pcg_laplacian_csr_csc() {
+        let lap = grid_laplacian(10, 12);
+        let n = lap.rows();
+        let x0 = Array::from_shape_fn(n, |i| (i as f64).sin());
+        let b = &lap * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 200);
+        let jacobi = Jacobi::new(lap.view());
+        for mat in &[lap.to_owned(), lap.to_csc()] {
+            let mut x = Array::zeros(n);
+            let report = super::pcg(
+                mat.view(),
+                b.view(),
+                x.view_mut(),
+                &jacobi,
+                &criteria,
+            );
+            assert!(report.converged());
+            assert_eq!(report.residual_norms().len(), report.iterations() + 1);
+            let err = max_abs_diff(&x, &x0);
+            assert!(err < 1e-8);
+        }
+    }
+
+    #[test]
+    fn cg_operator() {
+        // L + D, applied without forming the sum
+        let lap = grid_laplacian(8, 8);
+        let n = lap.rows();
+        let diag: Vec<f64> = (0..n).map(|i| 1. + (i % 3) as f64).collect();
+        let op = SumOperator::new(&lap, DiagonalOperator::new(diag.clone()));
+        let d =
+            CsMat::new((n, n), (0..n + 1).collect(), (0..n).collect(), diag);
+        let mat = &lap + &d;
+        let x0 = Array::from_shape_fn(n, |i| (i as f64).cos());
+        let b = &mat * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 200);
+        let mut x = Array::zeros(n);
+        let report = super::cg(&op, b.view(), x.view_mut(), &criteria);
+        assert!(report.converged());
+        let mut y = Array::zeros(n);
+        let expected = super::cg(mat.view(), b.view(), y.view_mut(), &criteria);
+        assert_eq!(report.iterations(), expected.iterations());
+        let err = max_abs_diff(&x, &x0);
+        assert!(err < 1e-8);
+    }
+
+    #[test]
+    fn cg_max_iter() {
+        let lap = grid_laplacian(10, 10);
+        let b = Array::from_elem(100, 1.);
+        let mut x = Array::zeros(100);
+        let criteria = StoppingCriteria::new(1e-14, 3);
+        let report = super::cg(lap.view(), b.view(), x.view_mut(), &criteria);
+        assert!(!report.converged());
+        assert_eq!(report.iterations(), 3);
+        assert!(report.residual_norm() < report.residual_norms()[0]);
+    }
+}
@@ -0,0 +1,302 @@
+///! Restarted Generalized Minimal RESidual method
+use ndarray::{Array, ArrayView1, ArrayViewMut1, Ix1};
+use num_traits::Float;
+
+use super::{
+    dot, norm2, solve_preconditioned, IdentityPreconditioner, PreconditionedOp,
+    Preconditioner, PreconditionerSide, SolveReport, StoppingCriteria,
+};
+use sparse::linalg::operator::LinearOperator;
+
+/// Solve the system `A x = b` using the restarted GMRES(m) method, where
+/// `m` is given by `restart`.
+///
+/// `x` holds the initial guess on entry, and the approximate solution on exit.
+///
+/// Each iteration requires one matrix vector product, and the memory usage
+/// grows linearly with `restart`.
+///
+/// # Panics
+///
+/// * if `op` is not square
+/// * if the dimensions of `rhs` or `x` do not match `op`
+/// * if `restart` is zero
+pub fn gmres<N, Op>(
+    op: Op,
+    rhs: ArrayView1<N>,
+    x: ArrayViewMut1<N>,
+    restart: usize,
+    criteria: &StoppingCriteria<N>,
+) -> SolveReport<N>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+{
+    pgmres(
+        op,
+        rhs,
+        x,
+        restart,
+        &IdentityPreconditioner,
+        PreconditionerSide::Left,
+        criteria,
+    )
+}
+
+/// Solve the system `A x = b` using the preconditioned restarted GMRES(m)
+/// method, where `m` is given by `restart`.
+///
+/// `x` holds the initial guess on entry, and the approximate solution on exit.
+/// See `PreconditionerSide` for the meaning of the reported residuals. Inside
+/// a restart cycle, the reported residual norms are the ones minimized
+/// by GMRES, which are computed without forming the residual. The norm
+/// reported for the last iteration of a cycle is the one of the residual
+/// recomputed from the updated solution.
+///
+/// # Panics
+///
+/// * if `op` is not square
+/// * if the dimensions of `rhs` or `x` do not match `op`
+/// * if `restart` is zero
+pub fn pgmres<N, Op, P>(
+    op: Op,
+    rhs: ArrayView1<N>,
+    x: ArrayViewMut1<N>,
+    restart: usize,
+    precond: &P,
+    side: PreconditionerSide,
+    criteria: &StoppingCriteria<N>,
+) -> SolveReport<N>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+    P: Preconditioner<N>,
+{
+    assert!(restart > 0, "restart should be positive");
+    let max_iter = criteria.max_iter();
+    solve_preconditioned(
+        op,
+        rhs,
+        x,
+        precond,
+        side,
+        criteria,
+        |op, rhs, x, threshold| {
+            gmres_impl(op, rhs, x, restart, threshold, max_iter)
+        },
+    )
+}
+
+/// Compute the Givens rotation `(c, s)` such that
+/// `[c s; -s c] [a; b] = [r; 0]`, returning `(c, s, r)`
+fn givens<N: Float>(a: N, b: N) -> (N, N, N) {
+    if b == N::zero() {
+        (N::one(), N::zero(), a)
+    } else {
+        let r = a.hypot(b);
+        (a / r, b / r, r)
+    }
+}
+
+fn gmres_impl<N, Op, P>(
+    op: &PreconditionedOp<Op, P>,
+    rhs: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    restart: usize,
+    threshold: N,
+    max_iter: usize,
+) -> SolveReport<N>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+    P: Preconditioner<N>,
+{
+    let mut r = &rhs - &op.apply(x.view());
+    let mut beta = norm2(&r.view());
+    let mut report = SolveReport::new(beta);
+    // Hessenberg matrix, stored by columns, reduced to upper triangular
+    // form by Givens rotations as the Arnoldi process goes
+    let mut hessenberg = vec![vec![N::zero(); restart + 1]; restart];
+    let mut rotations = Vec::with_capacity(restart);
+    let mut g = vec![N::zero(); restart + 1];
+    let mut basis: Vec<Array<N, Ix1>> = Vec::with_capacity(restart + 1);
+
+    while beta > threshold && report.iterations() < max_iter {
+        basis.clear();
+        rotations.clear();
+        for gi in g.iter_mut() {
+            *gi = N::zero();
+        }
+        g[0] = beta;
+        r.mapv_inplace(|ri| ri / beta);
+        basis.push(r);
+
+        let mut krylov_dim = 0;
+        let mut breakdown = false;
+        while krylov_dim < restart && report.iterations() < max_iter {
+            let j = krylov_dim;
+            let mut w = op.apply(basis[j].view());
+            let h = &mut hessenberg[j];
+            // modified Gram-Schmidt orthogonalization
+            for (hij, vi) in h.iter_mut().zip(basis.iter()) {
+                *hij = dot(&w.view(), &vi.view());
+                w.scaled_add(-*hij, vi);
+            }
+            let w_norm = norm2(&w.view());
+            h[j + 1] = w_norm;
+            for (i, &(c, s)) in rotations.iter().enumerate() {
+                let (hi, hi1) = (h[i], h[i + 1]);
+                h[i] = c * hi + s * hi1;
+                h[i + 1] = c * hi1 - s * hi;
+            }
+            let (c, s, diag) = givens(h[j], h[j + 1]);
+            h[j] = diag;
+            h[j + 1] = N::zero();
+            rotations.push((c, s));
+            g[j + 1] = -s * g[j];
+            g[j] = c * g[j];
+
+            krylov_dim += 1;
+            report.push_iteration(g[j + 1].abs());
+            if diag == N::zero() {
+                // the Hessenberg matrix is singular, no progress is possible
+                breakdown = true;
+                break;
+            }
+            if g[j + 1].abs() <= threshold || w_norm == N::zero() {
+                break;
+            }
+            w.mapv_inplace(|wi| wi / w_norm);
+            basis.push(w);
+        }
+
+        // solve the triangular system H y = g and update x
+        let mut y = g[..krylov_dim].to_vec();
+        for j in (0..krylov_dim).rev() {
+            if hessenberg[j][j] == N::zero() {
+                y[j] = N::zero();
+                continue;
+            }
+            y[j] = y[j] / hessenberg[j][j];
+            for i in 0..j {
+                y[i] = y[i] - hessenberg[j][i] * y[j];
+            }
+        }
+        for (&yj, vj) in y.iter().zip(basis.iter()) {
+            x.scaled_add(yj, vj);
+        }
+
+        r = &rhs - &op.apply(x.view());
+        beta = norm2(&r.view());
+        // the estimate of the last iteration can differ from the residual
+        // of the updated solution because of rounding errors
+        report.set_residual_norm(beta);
+        if breakdown {
+            break;
+        }
+    }
+    report.converged = beta <= threshold;
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::Jacobi;
+    use super::super::{PreconditionerSide, StoppingCriteria};
+    use ndarray::Array;
+    use sparse::linalg::operator::TransposeOperator;
+    use test_data::{grid_convection_diffusion, max_abs_diff};
+
+    #[test]
+    fn gmres_full_and_restarted() {
+        let mat = grid_convection_diffusion(8, 8, 2.);
+        let n = mat.rows();
+        let x0 = Array::from_shape_fn(n, |i| (i as f64 * 0.7).sin());
+        let b = &mat * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 500);
+        for &restart in &[n, 20, 5] {
+            let mut x = Array::zeros(n);
+            let report = super::gmres(
+                mat.to_csc().view(),
+                b.view(),
+                x.view_mut(),
+                restart,
+                &criteria,
+            );
+            assert!(report.converged());
+            let err = max_abs_diff(&x, &x0);
+            assert!(err < 1e-8);
+        }
+        // full GMRES is exact after at most n iterations, and its
+        // residual is monotonically decreasing
+        let mut x = Array::zeros(n);
+        let report =
+            super::gmres(mat.view(), b.view(), x.view_mut(), n, &criteria);
+        assert!(report.iterations() <= n);
+        for res in report.residual_norms().windows(2) {
+            assert!(res[1] <= res[0]);
+        }
+    }
+
+    #[test]
+    fn pgmres_left_right() {
+        let mat = grid_convection_diffusion(10, 10, 3.);
+        let n = mat.rows();
+        let x0 = Array::from_shape_fn(n, |i| 1. + (i % 5) as f64);
+        let b = &mat * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 300);
+        let jacobi = Jacobi::new(mat.view());
+        for &side in &[PreconditionerSide::Left, PreconditionerSide::Right] {
+            let mut x = Array::from_elem(n, 2.);
+            let report = super::pgmres(
+                mat.view(),
+                b.view(),
+                x.view_mut(),
+                30,
+                &jacobi,
+                side,
+                &criteria,
+            );
+            assert!(report.converged());
+            let err = max_abs_diff(&x, &x0);
+            assert!(err < 1e-7);
+        }
+    }
+
+    #[test]
+    fn gmres_transpose_operator() {
+        // solve A^T x = b without forming A^T
+        let mat = grid_convection_diffusion(8, 8, 2.);
+        let n = mat.rows();
+        let x0 = Array::from_shape_fn(n, |i| (i as f64 * 0.3).cos());
+        let b = &mat.transpose_view().to_owned() * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 500);
+        let mut x = Array::zeros(n);
+        let report = super::gmres(
+            TransposeOperator::new(&mat),
+            b.view(),
+            x.view_mut(),
+            20,
+            &criteria,
+        );
+        assert!(report.converged());
+        let err = max_abs_diff(&x, &x0);
+        assert!(err < 1e-8);
+    }
+
+    #[test]
+    fn gmres_max_iter() {
+        let mat = grid_convection_diffusion(10, 10, 1.);
+        let b = Array::from_elem(100, 1.);
+        let mut x = Array::zeros(100);
+        let criteria = StoppingCriteria::new(1e-14, 7);
+        let report =
+            super::gmres(mat.view(), b.view(), x.view_mut(), 3, &criteria);
+        assert!(!report.converged());
+        assert_eq!(report.iterations(), 7);
+        // the last reported norm describes the returned solution
+        let residual = &b - &(&mat * &x);
+        let res_norm = residual.dot(&residual).sqrt();
+        assert!((report.residual_norm() - res_norm).abs() < 1e-12 * res_norm);
+    }
+}
@@ -0,0 +1,311 @@
+///! LSMR method for sparse least squares problems
+///!
+///! Reference: D. C.-L. Fong and M. A. Saunders, LSMR: An iterative algorithm
+///! for sparse least-squares problems, SIAM J. Sci. Comput. 33(5),
+///! 2950-2971 (2011).
+use ndarray::{Array, ArrayView1, ArrayViewMut1};
+use num_traits::Float;
+
+use super::{
+    apply_op, apply_op_transpose, check_least_squares_dimensions, norm2,
+    sym_ortho, LeastSquaresCriteria, LeastSquaresReport, LeastSquaresStop,
+};
+use sparse::linalg::operator::LinearOperator;
+
+/// Solve the damped least squares problem
+/// `min ||A x - b||^2 + damp^2 ||x||^2` using the LSMR method.
+///
+/// LSMR is analytically equivalent to the MINRES method applied to the
+/// normal equations `(A^T A + damp^2 I) x = A^T b`. Contrary to LSQR, the
+/// norm of `A^T r - damp^2 x` decreases monotonically, which makes it
+/// safer to stop early. Only products with `A` and `A^T` are required, and
+/// `A` can have any shape.
+///
+/// `x` holds the initial guess `x0` on entry, and the approximate solution
+/// on exit. When the initial guess is non-zero and `damp` is positive, the
+/// damping applies to the correction `x - x0` rather than to `x`.
+///
+/// # Panics
+///
+/// * if the length of `rhs` is not the number of rows of `op`
+/// * if the length of `x` is not the number of columns of `op`
+pub fn lsmr<N, Op>(
+    op: Op,
+    rhs: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    damp: N,
+    criteria: &LeastSquaresCriteria<N>,
+) -> LeastSquaresReport<N>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+{
+    check_least_squares_dimensions(&op, &rhs, &x);
+    let eps = N::epsilon();
+    let atol = criteria.atol().max(eps);
+    let btol = criteria.btol().max(eps);
+    let ctol = if criteria.conlim() > N::zero() {
+        N::one() / criteria.conlim()
+    } else {
+        N::zero()
+    };
+
+    // Golub-Kahan bidiagonalization, started with beta u = b - A x0 and
+    // alpha v = A^T u
+    let bnorm = norm2(&rhs);
+    if bnorm == N::zero() {
+        x.fill(N::zero());
+        let mut report = LeastSquaresReport::new(N::zero(), N::zero());
+        report.stop = LeastSquaresStop::ZeroGradient;
+        return report;
+    }
+    let mut u = &rhs - &apply_op(&op, x.view());
+    let mut beta = norm2(&u.view());
+    let mut report = LeastSquaresReport::new(beta, N::zero());
+    let mut v;
+    let mut alpha = N::zero();
+    if beta > N::zero() {
+        u.mapv_inplace(|ui| ui / beta);
+        v = apply_op_transpose(&op, u.view());
+        alpha = norm2(&v.view());
+    } else {
+        v = Array::zeros(x.len());
+    }
+    if alpha > N::zero() {
+        v.mapv_inplace(|vi| vi / alpha);
+    }
+    report.normal_residual_norm = alpha * beta;
+    if report.normal_residual_norm == N::zero() {
+        report.stop = LeastSquaresStop::ZeroGradient;
+        return report;
+    }
+
+    let mut h = v.clone();
+    let mut hbar = Array::zeros(x.len());
+    let mut zetabar = alpha * beta;
+    let mut alphabar = alpha;
+    let mut rho = N::one();
+    let mut rhobar = N::one();
+    let mut cbar = N::one();
+    let mut sbar = N::zero();
+    let mut zeta = N::zero();
+
+    // variables for the estimation of ||r||
+    let mut betadd = beta;
+    let mut betad = N::zero();
+    let mut rhodold = N::one();
+    let mut tautildeold = N::zero();
+    let mut thetatilde = N::zero();
+    let mut d = N::zero();
+
+    // variables for the estimation of ||A|| and cond(A)
+    let mut norm_a2 = alpha * alpha;
+    let mut maxrbar = N::zero();
+    let mut minrbar = N::max_value();
+
+    while report.iterations < criteria.max_iter() {
+        // continue the bidiagonalization
+        u.mapv_inplace(|ui| -alpha * ui);
+        u = u + &apply_op(&op, v.view());
+        beta = norm2(&u.view());
+        if beta > N::zero() {
+            u.mapv_inplace(|ui| ui / beta);
+            v.mapv_inplace(|vi| -beta * vi);
+            v = v + &apply_op_transpose(&op, u.view());
+            alpha = norm2(&v.view());
+            if alpha > N::zero() {
+                v.mapv_inplace(|vi| vi / alpha);
+            }
+        }
+
+        // rotation eliminating the damping parameter
+        let (chat, shat, alphahat) = sym_ortho(alphabar, damp);
+
+        // plane rotation P_k, turning B_k into R_k
+        let rhoold = rho;
+        let (c, s, rho_next) = sym_ortho(alphahat, beta);
+        rho = rho_next;
+        let thetanew = s * alpha;
+        alphabar = c * alpha;
+
+        // plane rotation Pbar_k, turning R_k^T into Rbar_k
+        let rhobarold = rhobar;
+        let zetaold = zeta;
+        let thetabar = sbar * rho;
+        let rhotemp = cbar * rho;
+        let (cbar_next, sbar_next, rhobar_next) = sym_ortho(rhotemp, thetanew);
+        cbar = cbar_next;
+        sbar = sbar_next;
+        rhobar = rhobar_next;
+        zeta = cbar * zetabar;
+        zetabar = -sbar * zetabar;
+
+        // update h, hbar and x
+        let hbar_scale = -thetabar * rho / (rhoold * rhobarold);
+        hbar.zip_mut_with(&h, |hbi, &hi| *hbi = hi + hbar_scale * *hbi);
+        x.scaled_add(zeta / (rho * rhobar), &hbar);
+        let h_scale = -thetanew / rho;
+        h.zip_mut_with(&v, |hi, &vi| *hi = vi + h_scale * *hi);
+
+        // estimate ||r||, applying the rotations to the right hand side
+        let betaacute = chat * betadd;
+        let betacheck = -shat * betadd;
+        let betahat = c * betaacute;
+        betadd = -s * betaacute;
+
+        let thetatildeold = thetatilde;
+        let (ctildeold, stildeold, rhotildeold) = sym_ortho(rhodold, thetabar);
+        thetatilde = stildeold * rhobar;
+        rhodold = ctildeold * rhobar;
+        betad = -stildeold * betad + ctildeold * betahat;
+
+        tautildeold = (zetaold - thetatildeold * tautildeold) / rhotildeold;
+        let taud = (zeta - thetatilde * tautildeold) / rhodold;
+        d = d + betacheck * betacheck;
+        let rnorm =
+            (d + (betad - taud) * (betad - taud) + betadd * betadd).sqrt();
+
+        // estimate ||A|| and cond(A), where A stands for [A; damp I]
+        norm_a2 = norm_a2 + beta * beta + damp * damp;
+        let anorm = norm_a2.sqrt();
+        norm_a2 = norm_a2 + alpha * alpha;
+        maxrbar = maxrbar.max(rhobarold);
+        if report.iterations > 0 {
+            minrbar = minrbar.min(rhobarold);
+        }
+        let acond = maxrbar.max(rhotemp) / minrbar.min(rhotemp);
+
+        report.push_iteration(rnorm);
+        report.normal_residual_norm = zetabar.abs();
+        report.mat_norm = anorm;
+        report.cond = acond;
+        report.solution_norm = norm2(&x.view());
+
+        if let Some(stop) = report.least_squares_stop(bnorm, atol, btol, ctol) {
+            report.stop = stop;
+            break;
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::damped_least_squares;
+    use super::super::{LeastSquaresCriteria, LeastSquaresStop};
+    use ndarray::Array;
+    use test_data::{max_abs_diff, overdetermined};
+
+    #[test]
+    fn lsmr_compatible() {
+        let mat = overdetermined(20);
+        let x0 = Array::from_shape_fn(20, |i| (i as f64 * 0.7).cos());
+        let b = &mat * &x0;
+        let criteria = LeastSquaresCriteria::new(1e-12, 1e-12, 1e8, 100);
+        for mat in &[mat.to_owned(), mat.to_csc()] {
+            let mut x = Array::zeros(20);
+            let report =
+                super::lsmr(mat.view(), b.view(), x.view_mut(), 0., &criteria);
+            assert!(report.converged());
+            assert_eq!(report.stop_reason(), LeastSquaresStop::Compatible);
+            assert_eq!(report.residual_norms().len(), report.iterations() + 1);
+            let err = max_abs_diff(&x, &x0);
+            assert!(err < 1e-9);
+        }
+    }
+
+    #[test]
+    fn lsmr_damped_least_squares() {
+        let mat = overdetermined(15);
+        let b = Array::from_shape_fn(45, |i| 1. + (i % 4) as f64);
+        let criteria = LeastSquaresCriteria::new(1e-12, 1e-12, 1e8, 200);
+        for &damp in &[0., 0.5] {
+            let mut x = Array::zeros(15);
+            let report = super::lsmr(
+                mat.view(),
+                b.view(),
+                x.view_mut(),
+                damp,
+                &criteria,
+            );
+            assert_eq!(report.stop_reason(), LeastSquaresStop::LeastSquares);
+            let expected = damped_least_squares(mat.view(), &b, damp);
+            let err = max_abs_diff(&x, &expected);
+            assert!(err < 1e-8);
+            // the residual estimate is accurate
+            let r = &b - &(&mat * &x);
+            let xnorm2 = x.iter().fold(0., |acc, v| acc + v * v);
+            let rnorm =
+                (r.iter().fold(damp * damp * xnorm2, |acc, v| acc + v * v))
+                    .sqrt();
+            assert!((report.residual_norm() - rnorm).abs() < 1e-8);
+            let frob = mat
+                .data()
+                .iter()
+                .fold(15. * damp * damp, |acc, v| acc + v * v)
+                .sqrt();
+            assert!(report.mat_norm_estimate() <= frob * (1. + 1e-12));
+            assert!(report.cond_estimate() >= 1.);
+        }
+    }
+
+    #[test]
+    fn lsmr_damped_mat_norm() {
+        // after n iterations the bidiagonalization of the n columns of A is
+        // complete, and the estimate is exactly ||[A; damp I]||_F
+        let n = 5;
+        let mat = overdetermined(n);
+        let b = Array::from_shape_fn(3 * n, |i| 1. + (i % 4) as f64);
+        let damp = 0.5;
+        let frob = mat
+            .data()
+            .iter()
+            .fold(n as f64 * damp * damp, |acc, v| acc + v * v)
+            .sqrt();
+        let criteria = LeastSquaresCriteria::new(0., 0., 0., n);
+        let mut x = Array::zeros(n);
+        let report =
+            super::lsmr(mat.view(), b.view(), x.view_mut(), damp, &criteria);
+        assert_eq!(report.iterations(), n);
+        assert!((report.mat_norm_estimate() - frob).abs() < 1e-12 * frob);
+
+        // LSQR estimates the same norm
+        let criteria = LeastSquaresCriteria::new(0., 0., 0., 3);
+        let mut x = Array::zeros(n);
+        let report =
+            super::lsmr(mat.view(), b.view(), x.view_mut(), damp, &criteria);
+        let mut x = Array::zeros(n);
+        let expected = ::sparse::linalg::iterative::lsqr(
+            mat.view(),
+            b.view(),
+            x.view_mut(),
+            damp,
+            &criteria,
+        );
+        let diff = report.mat_norm_estimate() - expected.mat_norm_estimate();
+        assert!(diff.abs() < 1e-12 * expected.mat_norm_estimate());
+    }
+
+    #[test]
+    fn lsmr_initial_guess() {
+        let mat = overdetermined(10);
+        let x0 = Array::from_shape_fn(10, |i| i as f64);
+        let b = &mat * &x0;
+        // an exact initial guess needs no iteration
+        let mut x = x0.clone();
+        let criteria = LeastSquaresCriteria::default();
+        let report =
+            super::lsmr(mat.view(), b.view(), x.view_mut(), 0., &criteria);
+        assert_eq!(report.stop_reason(), LeastSquaresStop::ZeroGradient);
+        assert_eq!(report.iterations(), 0);
+        assert_eq!(x, x0);
+
+        let mut x = Array::from_elem(10, 1.);
+        let criteria = LeastSquaresCriteria::new(1e-12, 1e-12, 1e8, 3);
+        let report =
+            super::lsmr(mat.view(), b.view(), x.view_mut(), 0., &criteria);
+        assert!(!report.converged());
+        assert_eq!(report.stop_reason(), LeastSquaresStop::MaxIterations);
+        assert_eq!(report.iterations(), 3);
+    }
+}
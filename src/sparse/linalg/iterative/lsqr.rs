@@ -0,0 +1,234 @@
+///! LSQR method for sparse least squares problems
+///!
+///! Reference: C. C. Paige and M. A. Saunders, LSQR: An algorithm for sparse
+///! linear equations and sparse least squares, TOMS 8(1), 43-71 (1982).
+use ndarray::{ArrayView1, ArrayViewMut1};
+use num_traits::Float;
+
+use super::{
+    apply_op, apply_op_transpose, check_least_squares_dimensions, norm2,
+    LeastSquaresCriteria, LeastSquaresReport, LeastSquaresStop,
+};
+use sparse::linalg::operator::LinearOperator;
+
+/// Solve the damped least squares problem
+/// `min ||A x - b||^2 + damp^2 ||x||^2` using the LSQR method.
+///
+/// LSQR is analytically equivalent to the Conjugate Gradient method applied
+/// to the normal equations `(A^T A + damp^2 I) x = A^T b`, but has better
+/// numerical properties. Only products with `A` and `A^T` are required, and
+/// `A` can have any shape.
+///
+/// `x` holds the initial guess `x0` on entry, and the approximate solution
+/// on exit. When the initial guess is non-zero and `damp` is positive, the
+/// damping applies to the correction `x - x0` rather than to `x`.
+///
+/// # Panics
+///
+/// * if the length of `rhs` is not the number of rows of `op`
+/// * if the length of `x` is not the number of columns of `op`
+pub fn lsqr<N, Op>(
+    op: Op,
+    rhs: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    damp: N,
+    criteria: &LeastSquaresCriteria<N>,
+) -> LeastSquaresReport<N>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+{
+    check_least_squares_dimensions(&op, &rhs, &x);
+    let eps = N::epsilon();
+    let atol = criteria.atol().max(eps);
+    let btol = criteria.btol().max(eps);
+    let ctol = if criteria.conlim() > N::zero() {
+        N::one() / criteria.conlim()
+    } else {
+        N::zero()
+    };
+    let dampsq = damp * damp;
+
+    // Golub-Kahan bidiagonalization, started with beta u = b - A x0 and
+    // alpha v = A^T u
+    let bnorm = norm2(&rhs);
+    if bnorm == N::zero() {
+        x.fill(N::zero());
+        let mut report = LeastSquaresReport::new(N::zero(), N::zero());
+        report.stop = LeastSquaresStop::ZeroGradient;
+        return report;
+    }
+    let mut u = &rhs - &apply_op(&op, x.view());
+    let mut beta = norm2(&u.view());
+    let mut report = LeastSquaresReport::new(beta, N::zero());
+    let mut v;
+    let mut alpha = N::zero();
+    if beta > N::zero() {
+        u.mapv_inplace(|ui| ui / beta);
+        v = apply_op_transpose(&op, u.view());
+        alpha = norm2(&v.view());
+    } else {
+        v = x.to_owned();
+        v.fill(N::zero());
+    }
+    if alpha > N::zero() {
+        v.mapv_inplace(|vi| vi / alpha);
+    }
+    let mut w = v.clone();
+
+    let mut anorm = N::zero();
+    let mut ddnorm = N::zero();
+    let mut res2 = N::zero();
+    let mut xxnorm = N::zero();
+    let mut z = N::zero();
+    let mut cs2 = -N::one();
+    let mut sn2 = N::zero();
+    let mut rhobar = alpha;
+    let mut phibar = beta;
+    report.normal_residual_norm = alpha * beta;
+    if report.normal_residual_norm == N::zero() {
+        report.stop = LeastSquaresStop::ZeroGradient;
+        return report;
+    }
+
+    while report.iterations < criteria.max_iter() {
+        // continue the bidiagonalization
+        u.mapv_inplace(|ui| -alpha * ui);
+        u = u + &apply_op(&op, v.view());
+        beta = norm2(&u.view());
+        if beta > N::zero() {
+            u.mapv_inplace(|ui| ui / beta);
+            anorm =
+                (anorm * anorm + alpha * alpha + beta * beta + dampsq).sqrt();
+            v.mapv_inplace(|vi| -beta * vi);
+            v = v + &apply_op_transpose(&op, u.view());
+            alpha = norm2(&v.view());
+            if alpha > N::zero() {
+                v.mapv_inplace(|vi| vi / alpha);
+            }
+        }
+
+        // eliminate the damping parameter
+        let rhobar1 = (rhobar * rhobar + dampsq).sqrt();
+        let cs1 = rhobar / rhobar1;
+        let sn1 = damp / rhobar1;
+        let psi = sn1 * phibar;
+        phibar = cs1 * phibar;
+
+        // eliminate the subdiagonal element of the bidiagonal matrix
+        let rho = (rhobar1 * rhobar1 + beta * beta).sqrt();
+        let cs = rhobar1 / rho;
+        let sn = beta / rho;
+        let theta = sn * alpha;
+        rhobar = -cs * alpha;
+        let phi = cs * phibar;
+        phibar = sn * phibar;
+        let tau = sn * phi;
+
+        // update x and w
+        let t1 = phi / rho;
+        let t2 = -theta / rho;
+        ddnorm = ddnorm + norm2(&w.view()).powi(2) / (rho * rho);
+        x.scaled_add(t1, &w);
+        w.zip_mut_with(&v, |wi, &vi| *wi = vi + t2 * *wi);
+
+        // estimate the norm of x, using a plane rotation on the right
+        let delta = sn2 * rho;
+        let gambar = -cs2 * rho;
+        let rhs_z = phi - delta * z;
+        let zbar = rhs_z / gambar;
+        let xnorm = (xxnorm + zbar * zbar).sqrt();
+        let gamma = (gambar * gambar + theta * theta).sqrt();
+        cs2 = gambar / gamma;
+        sn2 = theta / gamma;
+        z = rhs_z / gamma;
+        xxnorm = xxnorm + z * z;
+
+        let acond = anorm * ddnorm.sqrt();
+        res2 = res2 + psi * psi;
+        let rnorm = (phibar * phibar + res2).sqrt();
+        let arnorm = alpha * tau.abs();
+        report.push_iteration(rnorm);
+        report.normal_residual_norm = arnorm;
+        report.mat_norm = anorm;
+        report.cond = acond;
+        report.solution_norm = xnorm;
+
+        if let Some(stop) = report.least_squares_stop(bnorm, atol, btol, ctol) {
+            report.stop = stop;
+            break;
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::damped_least_squares;
+    use super::super::{LeastSquaresCriteria, LeastSquaresStop};
+    use ndarray::Array;
+    use test_data::{max_abs_diff, overdetermined};
+
+    #[test]
+    fn lsqr_compatible() {
+        let mat = overdetermined(20);
+        let x0 = Array::from_shape_fn(20, |i| (i as f64 * 0.7).cos());
+        let b = &mat * &x0;
+        let criteria = LeastSquaresCriteria::new(1e-12, 1e-12, 1e8, 100);
+        for mat in &[mat.to_owned(), mat.to_csc()] {
+            let mut x = Array::zeros(20);
+            let report =
+                super::lsqr(mat.view(), b.view(), x.view_mut(), 0., &criteria);
+            assert!(report.converged());
+            assert_eq!(report.stop_reason(), LeastSquaresStop::Compatible);
+            assert_eq!(report.residual_norms().len(), report.iterations() + 1);
+            let err = max_abs_diff(&x, &x0);
+            assert!(err < 1e-9);
+        }
+    }
+
+    #[test]
+    fn lsqr_damped_least_squares() {
+        let mat = overdetermined(15);
+        let b = Array::from_shape_fn(45, |i| 1. + (i % 4) as f64);
+        let criteria = LeastSquaresCriteria::new(1e-12, 1e-12, 1e8, 200);
+        for &damp in &[0., 0.5] {
+            let mut x = Array::zeros(15);
+            let report = super::lsqr(
+                mat.view(),
+                b.view(),
+                x.view_mut(),
+                damp,
+                &criteria,
+            );
+            assert_eq!(report.stop_reason(), LeastSquaresStop::LeastSquares);
+            let expected = damped_least_squares(mat.view(), &b, damp);
+            let err = max_abs_diff(&x, &expected);
+            assert!(err < 1e-8);
+            // the norm estimate is bounded by the Frobenius norm
+            let frob = mat
+                .data()
+                .iter()
+                .fold(15. * damp * damp, |acc, v| acc + v * v)
+                .sqrt();
+            assert!(report.mat_norm_estimate() <= frob * (1. + 1e-12));
+            assert!(report.mat_norm_estimate() > 0.5 * frob);
+            assert!(report.cond_estimate() >= 1.);
+            let true_xnorm = x.iter().fold(0., |acc: f64, v| acc.hypot(*v));
+            assert!((report.solution_norm() - true_xnorm).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn lsqr_trivial() {
+        let mat = overdetermined(5);
+        let b = Array::zeros(15);
+        let mut x = Array::zeros(5);
+        let criteria = LeastSquaresCriteria::default();
+        let report =
+            super::lsqr(mat.view(), b.view(), x.view_mut(), 0., &criteria);
+        assert_eq!(report.stop_reason(), LeastSquaresStop::ZeroGradient);
+        assert_eq!(report.iterations(), 0);
+        assert!(x.iter().all(|&v| v == 0.));
+    }
+}
@@ -0,0 +1,300 @@
+///! Minimum Residual method
+///!
+///! Reference: C. C. Paige and M. A. Saunders, Solution of sparse indefinite
+///! systems of linear equations, SIAM J. Numer. Anal. 12(4), 617-629 (1975).
+use ndarray::{Array, ArrayView1, ArrayViewMut1};
+use num_traits::Float;
+
+use super::{
+    apply_op, check_system_dimensions, dot, IdentityPreconditioner,
+    Preconditioner, SolveReport, StoppingCriteria,
+};
+use errors::SprsError;
+use sparse::linalg::operator::LinearOperator;
+
+/// Solve the symmetric system `A x = b` using the MINRES method.
+///
+/// Contrary to the Conjugate Gradient method, the matrix can be indefinite,
+/// which makes MINRES suitable for saddle point problems. The norm of the
+/// residual decreases monotonically.
+///
+/// `x` holds the initial guess on entry, and the approximate solution on exit.
+/// The symmetry of the operator is not checked.
+///
+/// # Panics
+///
+/// * if `op` is not square
+/// * if the dimensions of `rhs` or `x` do not match `op`
+pub fn minres<N, Op>(
+    op: Op,
+    rhs: ArrayView1<N>,
+    x: ArrayViewMut1<N>,
+    criteria: &StoppingCriteria<N>,
+) -> SolveReport<N>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+{
+    pminres(op, rhs, x, &IdentityPreconditioner, criteria)
+        .expect("the identity preconditioner is positive definite")
+}
+
+/// Solve the symmetric system `A x = b` using the preconditioned MINRES
+/// method.
+///
+/// The matrix can be indefinite, but the preconditioner must be symmetric
+/// positive definite. The residuals are then measured in the norm
+/// `||r||_M = sqrt(r^T M^-1 r)`, and the solve is considered converged once
+/// `||b - A x||_M <= tol * ||b||_M`. These norms are the ones reported.
+///
+/// `x` holds the initial guess on entry, and the approximate solution on exit.
+/// The symmetry of the operator is not checked.
+///
+/// # Errors
+///
+/// * `NotPositiveDefinite` if the preconditioner is found not to be positive
+///   definite, in which case `x` holds the last iterate.
+///
+/// # Panics
+///
+/// * if `op` is not square
+/// * if the dimensions of `rhs` or `x` do not match `op`
+pub fn pminres<N, Op, P>(
+    op: Op,
+    rhs: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    precond: &P,
+    criteria: &StoppingCriteria<N>,
+) -> Result<SolveReport<N>, SprsError>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+    P: Preconditioner<N>,
+{
+    check_system_dimensions(&op, &rhs, &x);
+    let n = rhs.len();
+
+    let mut y = Array::zeros(n);
+    precond.apply(rhs, y.view_mut());
+    let rhs_norm2 = dot(&rhs, &y.view());
+
+    // Lanczos process on M^-1 A, started with r1 = b - A x
+    let mut r1 = &rhs - &apply_op(&op, x.view());
+    precond.apply(r1.view(), y.view_mut());
+    let beta1 = dot(&r1.view(), &y.view());
+    if beta1 < N::zero() || rhs_norm2 < N::zero() {
+        return Err(SprsError::NotPositiveDefinite);
+    }
+    let beta1 = beta1.sqrt();
+    let mut report = SolveReport::new(beta1);
+    let threshold = criteria.tol() * rhs_norm2.sqrt();
+    if beta1 <= threshold {
+        report.converged = true;
+        return Ok(report);
+    }
+
+    let mut r2 = r1.clone();
+    let mut beta = beta1;
+    let mut oldb = N::zero();
+    let mut dbar = N::zero();
+    let mut epsln = N::zero();
+    let mut phibar = beta1;
+    let mut cs = -N::one();
+    let mut sn = N::zero();
+    let mut w = Array::zeros(n);
+    let mut w2 = Array::zeros(n);
+    let mut w1 = Array::zeros(n);
+
+    while report.iterations < criteria.max_iter() {
+        let v = y.mapv(|yi| yi / beta);
+        y = apply_op(&op, v.view());
+        if report.iterations > 0 {
+            y.scaled_add(-beta / oldb, &r1);
+        }
+        let alpha = dot(&v.view(), &y.view());
+        y.scaled_add(-alpha / beta, &r2);
+        ::std::mem::swap(&mut r1, &mut r2);
+        r2.assign(&y);
+        precond.apply(r2.view(), y.view_mut());
+        oldb = beta;
+        let beta2 = dot(&r2.view(), &y.view());
+        if beta2 < N::zero() {
+            return Err(SprsError::NotPositiveDefinite);
+        }
+        beta = beta2.sqrt();
+
+        // apply the previous rotation, and compute the next one, to
+        // eliminate the subdiagonal of the tridiagonal Lanczos matrix
+        let oldeps = epsln;
+        let delta = cs * dbar + sn * alpha;
+        let gbar = sn * dbar - cs * alpha;
+        epsln = sn * beta;
+        dbar = -cs * beta;
+        let gamma = gbar.hypot(beta).max(N::epsilon());
+        cs = gbar / gamma;
+        sn = beta / gamma;
+        let phi = cs * phibar;
+        phibar = sn * phibar;
+
+        // update the search directions and the solution
+        ::std::mem::swap(&mut w1, &mut w2);
+        ::std::mem::swap(&mut w2, &mut w);
+        w.assign(&v);
+        w.scaled_add(-oldeps, &w1);
+        w.scaled_add(-delta, &w2);
+        w.mapv_inplace(|wi| wi / gamma);
+        x.scaled_add(phi, &w);
+
+        let res_norm = phibar.abs();
+        report.push_iteration(res_norm);
+        if res_norm <= threshold {
+            report.converged = true;
+            break;
+        }
+        if beta == N::zero() {
+            // invariant Krylov subspace, the system is inconsistent
+            break;
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{Preconditioner, StoppingCriteria};
+    use errors::SprsError;
+    use ndarray::{Array, ArrayView1, ArrayViewMut1};
+    use sparse::{CsMat, TriMat};
+    use test_data::{grid_laplacian, max_abs_diff};
+
+    /// The saddle point matrix `| K B^T |`
+    ///                          `| B  0  |`
+    /// where `K` is a laplacian and `B` averages pairs of unknowns.
+    fn kkt(rows: usize, cols: usize) -> CsMat<f64> {
+        let lap = grid_laplacian(rows, cols);
+        let n = lap.rows();
+        let nb_constraints = n / 2;
+        let mut tri = TriMat::new((n + nb_constraints, n + nb_constraints));
+        for (&val, (row, col)) in lap.iter() {
+            tri.add_triplet(row, col, val);
+        }
+        for k in 0..nb_constraints {
+            for &j in &[2 * k, 2 * k + 1] {
+                tri.add_triplet(n + k, j, 0.5);
+                tri.add_triplet(j, n + k, 0.5);
+            }
+        }
+        tri.to_csr()
+    }
+
+    /// Inverse of the absolute value of the diagonal of a matrix, replacing
+    /// zero diagonal entries by one.
+    struct AbsJacobi(Vec<f64>);
+
+    impl Preconditioner<f64> for AbsJacobi {
+        fn apply(&self, rhs: ArrayView1<f64>, mut out: ArrayViewMut1<f64>) {
+            for ((o, &r), &d) in out.iter_mut().zip(rhs.iter()).zip(&self.0) {
+                *o = r * d;
+            }
+        }
+    }
+
+    #[test]
+    fn minres_saddle_point() {
+        let mat = kkt(8, 6);
+        let n = mat.rows();
+        let x0 = Array::from_shape_fn(n, |i| (i as f64 * 0.3).sin());
+        let b = &mat * &x0;
+        let criteria = StoppingCriteria::new(1e-12, 500);
+        for mat in &[mat.to_owned(), mat.to_csc()] {
+            let mut x = Array::zeros(n);
+            let report =
+                super::minres(mat.view(), b.view(), x.view_mut(), &criteria);
+            assert!(report.converged());
+            assert_eq!(report.residual_norms().len(), report.iterations() + 1);
+            // the residual norm is non increasing
+            for pair in report.residual_norms().windows(2) {
+                assert!(pair[1] <= pair[0] * (1. + 1e-12));
+            }
+            let true_res = (&b - &(mat * &x))
+                .iter()
+                .fold(0., |acc: f64, r| acc.hypot(*r));
+            assert!((true_res - report.residual_norm()).abs() < 1e-8);
+            let err = max_abs_diff(&x, &x0);
+            assert!(err < 1e-8);
+        }
+    }
+
+    #[test]
+    fn pminres_saddle_point() {
+        let mat = kkt(10, 10);
+        let n = mat.rows();
+        let precond = AbsJacobi(
+            (0..n)
+                .map(|i| match mat.get(i, i) {
+                    Some(&d) if d != 0. => 1. / d.abs(),
+                    _ => 1.,
+                })
+                .collect(),
+        );
+        let x0 = Array::from_shape_fn(n, |i| (i % 7) as f64);
+        let b = &mat * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 1000);
+        let mut x = Array::zeros(n);
+        let report = super::pminres(
+            mat.view(),
+            b.view(),
+            x.view_mut(),
+            &precond,
+            &criteria,
+        )
+        .unwrap();
+        assert!(report.converged());
+        let err = max_abs_diff(&x, &x0);
+        assert!(err < 1e-6);
+
+        // an indefinite preconditioner is detected on the right hand side
+        let bad_precond = AbsJacobi(vec![-1.; n]);
+        let mut x = Array::zeros(n);
+        let res = super::pminres(
+            mat.view(),
+            b.view(),
+            x.view_mut(),
+            &bad_precond,
+            &criteria,
+        );
+        assert_eq!(res.unwrap_err(), SprsError::NotPositiveDefinite);
+        assert!(x.iter().all(|&xi| xi == 0.));
+
+        // or during the iterations, when the Lanczos vectors reach the
+        // unknown where the preconditioner is negative
+        let mut diag = vec![1.; n];
+        diag[1] = -1e6;
+        let bad_precond = AbsJacobi(diag);
+        let mut e0 = Array::zeros(n);
+        e0[0] = 1.;
+        let mut x = Array::zeros(n);
+        let res = super::pminres(
+            mat.view(),
+            e0.view(),
+            x.view_mut(),
+            &bad_precond,
+            &criteria,
+        );
+        assert_eq!(res.unwrap_err(), SprsError::NotPositiveDefinite);
+    }
+
+    #[test]
+    fn minres_max_iter() {
+        let mat = kkt(10, 10);
+        let n = mat.rows();
+        let b = Array::from_elem(n, 1.);
+        let mut x = Array::zeros(n);
+        let criteria = StoppingCriteria::new(1e-14, 4);
+        let report =
+            super::minres(mat.view(), b.view(), x.view_mut(), &criteria);
+        assert!(!report.converged());
+        assert_eq!(report.iterations(), 4);
+        assert!(report.residual_norm() < report.residual_norms()[0]);
+    }
+}
@@ -0,0 +1,658 @@
+///! Iterative solvers for sparse linear systems
+///!
+///! Iterative solvers only require matrix vector products with the system
+///! matrix, which makes them suitable for very large systems where a direct
+///! factorization would be too expensive, either in time or in memory.
+///!
+///! The available solvers are:
+///!
+///! - the Conjugate Gradient method (`cg`, `pcg`) for symmetric positive
+///!   definite systems.
+///! - the BiCGSTAB method (`bicgstab`, `pbicgstab`) for general systems.
+///! - the restarted GMRES method (`gmres`, `pgmres`) for general systems.
+///! - the MINRES method (`minres`, `pminres`) for symmetric indefinite
+///!   systems.
+///! - stationary methods (`stationary`), repeating the relaxation sweeps of
+///!   `linalg::relaxation`.
+///!
+///! All solvers share the same `StoppingCriteria` and return a `SolveReport`.
+///!
+///! Least squares problems `min ||A x - b||^2 + damp^2 ||x||^2`, with a
+///! possibly rectangular `A`, can be solved using the LSQR (`lsqr`) or LSMR
+///! (`lsmr`) methods, which only need products with `A` and `A^T`. They use
+///! their own `LeastSquaresCriteria` and return a `LeastSquaresReport`.
+///!
+///! The solvers accept any `LinearOperator` as system matrix, in
+///! particular sparse matrices stored in either CSR or CSC format, or
+///! operators combining them without forming the resulting matrix.
+///!
+///! The convergence of these methods can be greatly improved by using a
+///! preconditioner, ie an operator approximating the inverse of the system
+///! matrix, described by the `Preconditioner` trait. A sparse matrix, such
+///! as a sparse approximate inverse from `linalg::spai`, is a preconditioner
+///! applied by a matrix-vector product.
+use std::ops::Deref;
+
+use ndarray::{Array, ArrayView1, ArrayViewMut1, Ix1};
+use num_traits::{Float, Num};
+
+use indexing::SpIndex;
+use sparse::linalg::operator::LinearOperator;
+use sparse::CsMatBase;
+
+mod bicgstab;
+mod cg;
+mod gmres;
+mod lsmr;
+mod lsqr;
+mod minres;
+mod stationary;
+
+pub use self::bicgstab::{bicgstab, pbicgstab};
+pub use self::cg::{cg, pcg};
+pub use self::gmres::{gmres, pgmres};
+pub use self::lsmr::lsmr;
+pub use self::lsqr::lsqr;
+pub use self::minres::{minres, pminres};
+pub use self::stationary::stationary;
+
+/// A preconditioner, ie an operator `M^-1` approximating the inverse of
+/// a matrix `A`.
+pub trait Preconditioner<N> {
+    /// Compute `out = M^-1 rhs`
+    ///
+    /// # Panics
+    ///
+    /// * if `rhs` and `out` do not have the dimension of the preconditioner
+    fn apply(&self, rhs: ArrayView1<N>, out: ArrayViewMut1<N>);
+}
+
+/// The identity preconditioner, for solves without preconditioning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdentityPreconditioner;
+
+impl<N: Copy> Preconditioner<N> for IdentityPreconditioner {
+    fn apply(&self, rhs: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        out.assign(&rhs);
+    }
+}
+
+/// A sparse matrix `M` approximating `A^-1`, applied by a matrix-vector
+/// product. The matrix can be stored in either CSR or CSC format.
+impl<N, I, IpStorage, IStorage, DStorage> Preconditioner<N>
+    for CsMatBase<N, I, IpStorage, IStorage, DStorage>
+where
+    N: Copy + Num,
+    I: SpIndex,
+    IpStorage: Deref<Target = [I]>,
+    IStorage: Deref<Target = [I]>,
+    DStorage: Deref<Target = [N]>,
+{
+    fn apply(&self, rhs: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        assert!(
+            rhs.len() == self.cols() && out.len() == self.rows(),
+            "Dimension mismatch"
+        );
+        out.fill(N::zero());
+        for (&val, (row, col)) in self.iter() {
+            let (row, col) = (row.index(), col.index());
+            out[row] = out[row] + val * rhs[col];
+        }
+    }
+}
+
+/// Describe how a preconditioner is applied to a non-symmetric system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreconditionerSide {
+    /// Solve `M^-1 A x = M^-1 b`. The residual norms reported are
+    /// the ones of the preconditioned residual `M^-1 (b - A x)`, and the
+    /// tolerance is relative to `||M^-1 b||`.
+    Left,
+    /// Solve `A M^-1 u = b`, with `x = M^-1 u`. The residual norms reported
+    /// are the ones of the true residual `b - A x`.
+    Right,
+}
+
+/// Stopping criteria for an iterative solve.
+///
+/// The solve is considered converged once `||b - A x|| <= tol * ||b||`.
+/// It is stopped without convergence after `max_iter` iterations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StoppingCriteria<N> {
+    tol: N,
+    max_iter: usize,
+}
+
+impl<N: Float> StoppingCriteria<N> {
+    /// Create stopping criteria with the given relative tolerance and
+    /// maximum number of iterations.
+    ///
+    /// # Panics
+    ///
+    /// * if `tol` is negative
+    pub fn new(tol: N, max_iter: usize) -> StoppingCriteria<N> {
+        assert!(tol >= N::zero(), "tolerance should be non-negative");
+        StoppingCriteria {
+            tol: tol,
+            max_iter: max_iter,
+        }
+    }
+
+    /// The relative tolerance on the residual norm
+    pub fn tol(&self) -> N {
+        self.tol
+    }
+
+    /// The maximum number of iterations
+    pub fn max_iter(&self) -> usize {
+        self.max_iter
+    }
+}
+
+impl<N: Float> Default for StoppingCriteria<N> {
+    /// A relative tolerance of `sqrt(eps)` and at most 1000 iterations
+    fn default() -> StoppingCriteria<N> {
+        StoppingCriteria::new(N::epsilon().sqrt(), 1000)
+    }
+}
+
+/// Summary of an iterative solve
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveReport<N> {
+    iterations: usize,
+    residual_norms: Vec<N>,
+    converged: bool,
+}
+
+impl<N: Copy> SolveReport<N> {
+    fn new(initial_residual_norm: N) -> SolveReport<N> {
+        SolveReport {
+            iterations: 0,
+            residual_norms: vec![initial_residual_norm],
+            converged: false,
+        }
+    }
+
+    /// Record the residual norm after an iteration
+    fn push_iteration(&mut self, residual_norm: N) {
+        self.iterations += 1;
+        self.residual_norms.push(residual_norm);
+    }
+
+    /// Replace the residual norm recorded for the last iteration, eg by the
+    /// norm of a recomputed residual
+    fn set_residual_norm(&mut self, residual_norm: N) {
+        *self
+            .residual_norms
+            .last_mut()
+            .expect("at least the initial residual") = residual_norm;
+    }
+
+    /// The number of iterations performed
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// The norm of the residual `b - A x` at the start of the solve and
+    /// after each iteration
+    pub fn residual_norms(&self) -> &[N] {
+        &self.residual_norms[..]
+    }
+
+    /// The norm of the residual for the returned solution
+    pub fn residual_norm(&self) -> N {
+        *self
+            .residual_norms
+            .last()
+            .expect("at least the initial residual")
+    }
+
+    /// Whether the stopping tolerance was reached
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+}
+
+/// Stopping criteria for the least squares solvers `lsqr` and `lsmr`,
+/// following the reference implementations of Paige and Saunders, and
+/// Fong and Saunders.
+///
+/// With `r = b - A x`, the solve stops when:
+///
+/// * `||r|| <= btol * ||b|| + atol * ||A|| ||x||`, meaning `A x = b` is
+///   compatible and has been solved, or
+/// * `||A^T r - damp^2 x|| <= atol * ||A|| ||r||`, meaning a least squares
+///   solution has been found, or
+/// * the estimate of `cond(A)` exceeds `conlim`, or
+/// * `max_iter` iterations have been performed.
+///
+/// The norms of `A` and `r` used in these tests are those of the damped
+/// problem. Tolerances below the machine precision are replaced by it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LeastSquaresCriteria<N> {
+    atol: N,
+    btol: N,
+    conlim: N,
+    max_iter: usize,
+}
+
+impl<N: Float> LeastSquaresCriteria<N> {
+    /// Create least squares stopping criteria.
+    ///
+    /// `atol` is the relative accuracy of the entries of `A`, and `btol` is
+    /// the relative accuracy of the entries of `b`. A value of `0` for
+    /// `conlim` disables the test on the condition number.
+    ///
+    /// # Panics
+    ///
+    /// * if `atol`, `btol` or `conlim` is negative
+    pub fn new(
+        atol: N,
+        btol: N,
+        conlim: N,
+        max_iter: usize,
+    ) -> LeastSquaresCriteria<N> {
+        assert!(atol >= N::zero(), "tolerance should be non-negative");
+        assert!(btol >= N::zero(), "tolerance should be non-negative");
+        assert!(conlim >= N::zero(), "conlim should be non-negative");
+        LeastSquaresCriteria {
+            atol: atol,
+            btol: btol,
+            conlim: conlim,
+            max_iter: max_iter,
+        }
+    }
+
+    /// The relative accuracy of `A`
+    pub fn atol(&self) -> N {
+        self.atol
+    }
+
+    /// The relative accuracy of `b`
+    pub fn btol(&self) -> N {
+        self.btol
+    }
+
+    /// The limit on the estimated condition number of `A`
+    pub fn conlim(&self) -> N {
+        self.conlim
+    }
+
+    /// The maximum number of iterations
+    pub fn max_iter(&self) -> usize {
+        self.max_iter
+    }
+}
+
+impl<N: Float> Default for LeastSquaresCriteria<N> {
+    /// Tolerances of `sqrt(eps)`, a condition limit of `1e8` and at most
+    /// 1000 iterations
+    fn default() -> LeastSquaresCriteria<N> {
+        let tol = N::epsilon().sqrt();
+        let conlim = N::from(1e8).expect("representable condition limit");
+        LeastSquaresCriteria::new(tol, tol, conlim, 1000)
+    }
+}
+
+/// The reason why a least squares solve stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeastSquaresStop {
+    /// `A^T (b - A x0)` is zero, so the initial guess `x0` is already a
+    /// solution, or `b` is zero and the solution is zero.
+    ZeroGradient,
+    /// The system `A x = b` is compatible and has been solved up to the
+    /// tolerances.
+    Compatible,
+    /// A least squares solution has been found up to the tolerances.
+    LeastSquares,
+    /// The estimated condition number of `A` exceeded the limit.
+    ConditionLimit,
+    /// The maximum number of iterations was reached.
+    MaxIterations,
+}
+
+/// Summary of a least squares solve
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeastSquaresReport<N> {
+    iterations: usize,
+    stop: LeastSquaresStop,
+    residual_norms: Vec<N>,
+    normal_residual_norm: N,
+    mat_norm: N,
+    cond: N,
+    solution_norm: N,
+}
+
+impl<N: Copy> LeastSquaresReport<N> {
+    fn new(initial_residual_norm: N, zero: N) -> LeastSquaresReport<N> {
+        LeastSquaresReport {
+            iterations: 0,
+            stop: LeastSquaresStop::MaxIterations,
+            residual_norms: vec![initial_residual_norm],
+            normal_residual_norm: zero,
+            mat_norm: zero,
+            cond: zero,
+            solution_norm: zero,
+        }
+    }
+
+    /// Record the residual norm after an iteration
+    fn push_iteration(&mut self, residual_norm: N) {
+        self.iterations += 1;
+        self.residual_norms.push(residual_norm);
+    }
+
+    /// Check the stopping tests shared by LSQR and LSMR, using the current
+    /// estimates
+    fn least_squares_stop(
+        &self,
+        bnorm: N,
+        atol: N,
+        btol: N,
+        ctol: N,
+    ) -> Option<LeastSquaresStop>
+    where
+        N: Float,
+    {
+        let one = N::one();
+        let eps = N::epsilon();
+        let rnorm = self.residual_norm();
+        let anorm = self.mat_norm;
+        let xnorm = self.solution_norm;
+        let test1 = rnorm / bnorm;
+        let test2 = self.normal_residual_norm / (anorm * rnorm + eps);
+        let test3 = one / (self.cond + eps);
+        let scaled_test1 = test1 / (one + anorm * xnorm / bnorm);
+        let rtol = btol + atol * anorm * xnorm / bnorm;
+        // the tests on `one + test <= one` detect tolerances below the
+        // machine precision
+        if test1 <= rtol || one + scaled_test1 <= one {
+            Some(LeastSquaresStop::Compatible)
+        } else if test2 <= atol || one + test2 <= one {
+            Some(LeastSquaresStop::LeastSquares)
+        } else if test3 <= ctol || one + test3 <= one {
+            Some(LeastSquaresStop::ConditionLimit)
+        } else {
+            None
+        }
+    }
+
+    /// The number of iterations performed
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// The reason why the solve stopped
+    pub fn stop_reason(&self) -> LeastSquaresStop {
+        self.stop
+    }
+
+    /// Whether a solution was found up to the tolerances
+    pub fn converged(&self) -> bool {
+        match self.stop {
+            LeastSquaresStop::ZeroGradient
+            | LeastSquaresStop::Compatible
+            | LeastSquaresStop::LeastSquares => true,
+            LeastSquaresStop::ConditionLimit
+            | LeastSquaresStop::MaxIterations => false,
+        }
+    }
+
+    /// The estimated norm `sqrt(||b - A x||^2 + damp^2 ||x||^2)` of the
+    /// residual of the damped problem, at the start of the solve and after
+    /// each iteration
+    pub fn residual_norms(&self) -> &[N] {
+        &self.residual_norms[..]
+    }
+
+    /// The estimated norm of the residual of the damped problem for the
+    /// returned solution
+    pub fn residual_norm(&self) -> N {
+        *self
+            .residual_norms
+            .last()
+            .expect("at least the initial residual")
+    }
+
+    /// The estimated norm of `A^T (b - A x) - damp^2 x`, which vanishes
+    /// at the solution of the least squares problem
+    pub fn normal_residual_norm(&self) -> N {
+        self.normal_residual_norm
+    }
+
+    /// An estimate of the Frobenius norm of `[A; damp I]`
+    pub fn mat_norm_estimate(&self) -> N {
+        self.mat_norm
+    }
+
+    /// An estimate of the condition number of `[A; damp I]`
+    pub fn cond_estimate(&self) -> N {
+        self.cond
+    }
+
+    /// An estimate of the norm of the returned solution
+    pub fn solution_norm(&self) -> N {
+        self.solution_norm
+    }
+}
+
+fn dot<N: Float>(x: &ArrayView1<N>, y: &ArrayView1<N>) -> N {
+    x.iter()
+        .zip(y.iter())
+        .fold(N::zero(), |acc, (&xi, &yi)| acc + xi * yi)
+}
+
+fn norm2<N: Float>(x: &ArrayView1<N>) -> N {
+    dot(x, x).sqrt()
+}
+
+/// Compute `A x` into a new array
+fn apply_op<N, Op>(op: &Op, x: ArrayView1<N>) -> Array<N, Ix1>
+where
+    N: Float,
+    Op: LinearOperator<N>,
+{
+    let mut out = Array::zeros(op.shape().0);
+    op.apply(x, out.view_mut());
+    out
+}
+
+/// Compute `A^T x` into a new array
+fn apply_op_transpose<N, Op>(op: &Op, x: ArrayView1<N>) -> Array<N, Ix1>
+where
+    N: Float,
+    Op: LinearOperator<N>,
+{
+    let mut out = Array::zeros(op.shape().1);
+    op.apply_transpose(x, out.view_mut());
+    out
+}
+
+fn check_system_dimensions<N, Op>(
+    op: &Op,
+    rhs: &ArrayView1<N>,
+    x: &ArrayViewMut1<N>,
+) where
+    Op: LinearOperator<N>,
+{
+    let (rows, cols) = op.shape();
+    if rows != cols {
+        panic!("Non square matrix passed to solver");
+    }
+    if cols != rhs.len() || cols != x.len() {
+        panic!("Dimension mismatch");
+    }
+}
+
+fn check_least_squares_dimensions<N, Op>(
+    op: &Op,
+    rhs: &ArrayView1<N>,
+    x: &ArrayViewMut1<N>,
+) where
+    Op: LinearOperator<N>,
+{
+    let (rows, cols) = op.shape();
+    if rows != rhs.len() || cols != x.len() {
+        panic!("Dimension mismatch");
+    }
+}
+
+/// Compute a stable Givens rotation `(c, s, r)` such that
+/// `[c s; -s c] [a; b] = [r; 0]`.
+fn sym_ortho<N: Float>(a: N, b: N) -> (N, N, N) {
+    if b == N::zero() {
+        (sign(a), N::zero(), a.abs())
+    } else if a == N::zero() {
+        (N::zero(), sign(b), b.abs())
+    } else if b.abs() > a.abs() {
+        let tau = a / b;
+        let s = sign(b) / (N::one() + tau * tau).sqrt();
+        let c = s * tau;
+        (c, s, b / s)
+    } else {
+        let tau = b / a;
+        let c = sign(a) / (N::one() + tau * tau).sqrt();
+        let s = c * tau;
+        (c, s, a / c)
+    }
+}
+
+/// The sign of a number, with `sign(0) = 0`
+fn sign<N: Float>(a: N) -> N {
+    if a > N::zero() {
+        N::one()
+    } else if a < N::zero() {
+        -N::one()
+    } else {
+        N::zero()
+    }
+}
+
+/// The operator actually seen by a Krylov method when solving a
+/// preconditioned system: `M^-1 A` for left preconditioning and `A M^-1`
+/// for right preconditioning.
+struct PreconditionedOp<'p, Op, P: 'p> {
+    op: Op,
+    precond: &'p P,
+    side: PreconditionerSide,
+}
+
+impl<'p, Op, P> PreconditionedOp<'p, Op, P> {
+    fn apply<N>(&self, v: ArrayView1<N>) -> Array<N, Ix1>
+    where
+        N: Float,
+        Op: LinearOperator<N>,
+        P: Preconditioner<N>,
+    {
+        let mut res = Array::zeros(v.len());
+        match self.side {
+            PreconditionerSide::Left => {
+                let av = apply_op(&self.op, v);
+                self.precond.apply(av.view(), res.view_mut());
+                res
+            }
+            PreconditionerSide::Right => {
+                self.precond.apply(v, res.view_mut());
+                apply_op(&self.op, res.view())
+            }
+        }
+    }
+}
+
+/// Solve a preconditioned system using a Krylov method which is not aware of
+/// the preconditioning.
+///
+/// `solver` receives the preconditioned operator, a right hand side, an
+/// initial guess that it should update, and the absolute residual norm
+/// below which convergence is reached.
+fn solve_preconditioned<N, Op, P, F>(
+    op: Op,
+    rhs: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    precond: &P,
+    side: PreconditionerSide,
+    criteria: &StoppingCriteria<N>,
+    solver: F,
+) -> SolveReport<N>
+where
+    N: 'static + Float + Default,
+    Op: LinearOperator<N>,
+    P: Preconditioner<N>,
+    F: FnOnce(
+        &PreconditionedOp<Op, P>,
+        ArrayView1<N>,
+        ArrayViewMut1<N>,
+        N,
+    ) -> SolveReport<N>,
+{
+    check_system_dimensions(&op, &rhs, &x);
+    let n = rhs.len();
+    let op = PreconditionedOp {
+        op: op,
+        precond: precond,
+        side: side,
+    };
+    match side {
+        PreconditionerSide::Left => {
+            let mut precond_rhs = Array::zeros(n);
+            precond.apply(rhs, precond_rhs.view_mut());
+            let threshold = criteria.tol() * norm2(&precond_rhs.view());
+            solver(&op, precond_rhs.view(), x, threshold)
+        }
+        PreconditionerSide::Right => {
+            // Solve A M^-1 u = b - A x0, starting from u = 0, then
+            // x = x0 + M^-1 u.
+            let threshold = criteria.tol() * norm2(&rhs);
+            let r0 = &rhs - &apply_op(&op.op, x.view());
+            let mut u = Array::zeros(n);
+            let report = solver(&op, r0.view(), u.view_mut(), threshold);
+            let mut dx = Array::zeros(n);
+            precond.apply(u.view(), dx.view_mut());
+            x.zip_mut_with(&dx, |xi, &dxi| *xi = *xi + dxi);
+            report
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Preconditioner;
+    use ndarray::{Array, ArrayView1, ArrayViewMut1, Ix1};
+    use sparse::linalg::qr::SparseQr;
+    use sparse::{CsMat, CsMatView};
+
+    /// Inverse of the diagonal of a matrix
+    pub struct Jacobi(Vec<f64>);
+
+    impl Jacobi {
+        pub fn new(mat: CsMatView<f64>) -> Jacobi {
+            let n = mat.rows();
+            Jacobi((0..n).map(|i| 1. / mat.get(i, i).unwrap()).collect())
+        }
+    }
+
+    impl Preconditioner<f64> for Jacobi {
+        fn apply(&self, rhs: ArrayView1<f64>, mut out: ArrayViewMut1<f64>) {
+            for ((o, &r), &d) in out.iter_mut().zip(rhs.iter()).zip(&self.0) {
+                *o = r * d;
+            }
+        }
+    }
+
+    /// Solution of the damped least squares problem
+    /// `min ||A x - b||^2 + damp^2 ||x||^2`, computed by a QR factorization
+    /// of `[A; damp I]`
+    pub fn damped_least_squares(
+        mat: CsMatView<f64>,
+        rhs: &Array<f64, Ix1>,
+        damp: f64,
+    ) -> Array<f64, Ix1> {
+        let (m, n) = mat.shape();
+        let damp_eye = &CsMat::eye(n) * damp;
+        let augmented = ::sparse::construct::vstack(&[mat, damp_eye.view()]);
+        let mut aug_rhs = rhs.to_vec();
+        aug_rhs.resize(m + n, 0.);
+        let qr = SparseQr::new(augmented.view());
+        Array::from_vec(qr.solve_least_squares(&aug_rhs))
+    }
+}
@@ -0,0 +1,108 @@
+///! Stationary iterative methods
+use ndarray::{ArrayView1, ArrayViewMut1};
+use num_traits::Float;
+
+use super::{check_system_dimensions, norm2, SolveReport, StoppingCriteria};
+use indexing::SpIndex;
+use sparse::linalg::relaxation::Relaxation;
+use sparse::CsMatViewI;
+
+/// Solve the system `A x = b` by repeated sweeps of a relaxation method,
+/// such as Jacobi, Gauss-Seidel or SOR.
+///
+/// These methods converge for diagonally dominant matrices, and Gauss-Seidel
+/// and SOR with a weight in `(0, 2)` converge for symmetric positive
+/// definite matrices, but convergence is usually much slower than with
+/// Krylov methods. The residual norm is computed after each sweep.
+///
+/// `x` holds the initial guess on entry, and the approximate solution on exit.
+/// The matrix must be stored in CSR format.
+///
+/// # Panics
+///
+/// * if `mat` is not square, or not stored in CSR format
+/// * if the dimensions of `rhs` or `x` do not match `mat`
+/// * if a diagonal entry of `mat` is zero or missing
+pub fn stationary<N, I>(
+    mat: CsMatViewI<N, I>,
+    rhs: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    relaxation: &Relaxation<N>,
+    criteria: &StoppingCriteria<N>,
+) -> SolveReport<N>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+{
+    check_system_dimensions(&mat, &rhs, &x);
+    let threshold = criteria.tol() * norm2(&rhs);
+    let res = &rhs - &(&mat * &x);
+    let mut report = SolveReport::new(norm2(&res.view()));
+    if report.residual_norm() <= threshold {
+        report.converged = true;
+        return report;
+    }
+    while report.iterations < criteria.max_iter() {
+        relaxation.sweep(mat.view(), rhs.view(), x.view_mut());
+        let res = &rhs - &(&mat * &x);
+        let res_norm = norm2(&res.view());
+        report.push_iteration(res_norm);
+        if res_norm <= threshold {
+            report.converged = true;
+            break;
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::StoppingCriteria;
+    use ndarray::Array;
+    use sparse::linalg::relaxation::{Relaxation, SweepDirection};
+    use test_data::{grid_laplacian, max_abs_diff};
+
+    #[test]
+    fn stationary_methods() {
+        let mat = grid_laplacian(6, 7);
+        let n = mat.rows();
+        let x0 = Array::from_shape_fn(n, |i| (i as f64 * 0.4).cos());
+        let rhs = &mat * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 2000);
+        let mut iterations = Vec::new();
+        for method in &[
+            Relaxation::jacobi(1.),
+            Relaxation::gauss_seidel(SweepDirection::Forward),
+            Relaxation::sor(1.6, SweepDirection::Forward),
+        ] {
+            let mut x = Array::zeros(n);
+            let report = super::stationary(
+                mat.view(),
+                rhs.view(),
+                x.view_mut(),
+                method,
+                &criteria,
+            );
+            assert!(report.converged());
+            let err = max_abs_diff(&x, &x0);
+            assert!(err < 1e-8);
+            iterations.push(report.iterations());
+        }
+        // Gauss-Seidel converges about twice as fast as Jacobi, and SOR
+        // with a good weight is much faster
+        assert!(iterations[1] < iterations[0]);
+        assert!(iterations[2] < iterations[1] / 2);
+
+        let criteria = StoppingCriteria::new(1e-10, 5);
+        let mut x = Array::zeros(n);
+        let report = super::stationary(
+            mat.view(),
+            rhs.view(),
+            x.view_mut(),
+            &Relaxation::ssor(1.),
+            &criteria,
+        );
+        assert!(!report.converged());
+        assert_eq!(report.iterations(), 5);
+    }
+}
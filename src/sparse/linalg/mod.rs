@@ -1,11 +1,26 @@
 ///! Sparse linear algebra
 ///!
 ///! This module contains solvers for sparse linear systems. Currently
-///! there are solver for sparse triangular systems and symmetric systems.
+///! there are solver for sparse triangular systems and symmetric systems,
+///! as well as iterative solvers, algebraic multigrid preconditioners,
+///! eigensolvers and truncated singular value decompositions.
 use num_traits::Num;
 use std::iter::IntoIterator;
 
+pub mod amg;
+mod dense;
+pub mod eigen;
 pub mod etree;
+pub mod expm;
+pub mod ilu;
+pub mod iterative;
+pub mod normest;
+pub mod operator;
+pub mod ordering;
+pub mod qr;
+pub mod relaxation;
+pub mod spai;
+pub mod svd;
 pub mod trisolve;
 
 /// Diagonal solve
@@ -0,0 +1,164 @@
+///! Estimation of matrix norms
+///!
+///! The norm of a matrix inverse is needed to estimate condition numbers,
+///! but computing the inverse of a sparse matrix is prohibitive. The
+///! estimators of this module only need to solve a few linear systems,
+///! typically using an existing factorization.
+///!
+///! Reference: N. J. Higham, FORTRAN codes for estimating the one-norm of
+///! a real or complex matrix, with applications to condition estimation,
+///! ACM Trans. Math. Softw. 14(4), 381-396 (1988).
+use num_traits::Float;
+
+/// Maximum number of iterations of the norm estimator
+const MAX_ITER: usize = 5;
+
+/// Estimate the 1-norm of the inverse of a square matrix of dimension `n`,
+/// using the Hager-Higham algorithm.
+///
+/// `solve` should return the solution `x` of `A x = b`, and `solve_transpose`
+/// the solution of `A^T x = b`. Both closures are usually backed by
+/// a factorization of `A`, and are called at most a dozen times. For
+/// a symmetric matrix, the same solver can be used for both.
+///
+/// The estimate is a lower bound of `||A^-1||_1`, which is exact in most
+/// cases, and is rarely more than a small factor away from the true norm.
+/// Multiplying it by `A.one_norm()` gives an estimate of the condition
+/// number of `A` in the 1-norm.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::linalg::normest::onenormest;
+/// use sprs::CsMat;
+/// let diag = [2., -0.5, 4.];
+/// let mat = CsMat::new((3, 3), vec![0, 1, 2, 3], vec![0, 1, 2], diag.to_vec());
+/// let solve = |b: &[f64]| -> Vec<f64> {
+///     b.iter().zip(&diag).map(|(bi, di)| bi / di).collect()
+/// };
+/// let inv_norm = onenormest(3, &solve, &solve);
+/// assert_eq!(inv_norm, 2.);
+/// assert_eq!(mat.one_norm() * inv_norm, 8.);
+/// ```
+pub fn onenormest<N, F, G>(n: usize, mut solve: F, mut solve_transpose: G) -> N
+where
+    N: Float,
+    F: FnMut(&[N]) -> Vec<N>,
+    G: FnMut(&[N]) -> Vec<N>,
+{
+    if n == 0 {
+        return N::zero();
+    }
+    let one_norm = |y: &[N]| y.iter().fold(N::zero(), |acc, &x| acc + x.abs());
+    let sign = |y: &[N]| -> Vec<N> {
+        y.iter()
+            .map(|&x| if x >= N::zero() { N::one() } else { -N::one() })
+            .collect()
+    };
+    let argmax_abs = |z: &[N]| {
+        let mut best = 0;
+        for (i, &zi) in z.iter().enumerate() {
+            if zi.abs() > z[best].abs() {
+                best = i;
+            }
+        }
+        best
+    };
+
+    let n_float = N::from(n).expect("representable dimension");
+    let x = vec![N::one() / n_float; n];
+    let y = solve(&x);
+    let mut est = one_norm(&y);
+    if n == 1 {
+        return est;
+    }
+    let mut xi = sign(&y);
+    let mut z = solve_transpose(&xi);
+    let mut j = argmax_abs(&z);
+    let mut iter = 2;
+    loop {
+        // the column of the inverse which maximizes the gradient
+        let mut x = vec![N::zero(); n];
+        x[j] = N::one();
+        let y = solve(&x);
+        let est_old = est;
+        est = one_norm(&y);
+        let new_xi = sign(&y);
+        if new_xi == xi || est <= est_old {
+            est = est.max(est_old);
+            break;
+        }
+        xi = new_xi;
+        z = solve_transpose(&xi);
+        let j_old = j;
+        j = argmax_abs(&z);
+        if z[j_old].abs() == z[j].abs() || iter >= MAX_ITER {
+            break;
+        }
+        iter += 1;
+    }
+
+    // an alternating vector catches the matrices for which the iteration
+    // above underestimates the norm
+    let x: Vec<N> = (0..n)
+        .map(|i| {
+            let sign = if i % 2 == 0 { N::one() } else { -N::one() };
+            let i = N::from(i).expect("representable index");
+            sign * (N::one() + i / (n_float - N::one()))
+        })
+        .collect();
+    let y = solve(&x);
+    let three = N::from(3.).expect("representable");
+    let alt_est = (N::one() + N::one()) * one_norm(&y) / (three * n_float);
+    est.max(alt_est)
+}
+
+#[cfg(test)]
+mod test {
+    use sparse::linalg::qr::SparseQr;
+    use sparse::CsMat;
+    use test_data::grid_convection_diffusion;
+
+    /// Compute the 1-norm of the inverse column by column
+    fn exact_inverse_norm<F>(n: usize, solve: F) -> f64
+    where
+        F: Fn(&[f64]) -> Vec<f64>,
+    {
+        (0..n)
+            .map(|j| {
+                let mut e = vec![0.; n];
+                e[j] = 1.;
+                solve(&e).iter().fold(0., |acc, x| acc + x.abs())
+            })
+            .fold(0., f64::max)
+    }
+
+    #[test]
+    fn onenormest_non_symmetric() {
+        let mat = grid_convection_diffusion(8, 7, 3.);
+        let n = mat.rows();
+        let qr = SparseQr::new(mat.view());
+        let qr_t = SparseQr::new(mat.transpose_view());
+        let solve = |b: &[f64]| qr.solve_least_squares(b);
+        let solve_t = |b: &[f64]| qr_t.solve_least_squares(b);
+        let exact = exact_inverse_norm(n, &solve);
+        let est = super::onenormest(n, &solve, &solve_t);
+        assert!(est <= exact * (1. + 1e-10));
+        assert!(est >= exact / 3.);
+    }
+
+    #[test]
+    fn onenormest_small() {
+        let mat = CsMat::new((1, 1), vec![0, 1], vec![0], vec![-4.]);
+        let solve = |b: &[f64]| vec![b[0] / mat.data()[0]];
+        assert_eq!(super::onenormest(1, &solve, &solve), 0.25);
+        let solve = |_: &[f64]| Vec::new();
+        assert_eq!(super::onenormest(0, &solve, &solve), 0.);
+
+        // the inverse of | 1 -1 | is | 1 1 |
+        //                | 0  1 |    | 0 1 |
+        let solve = |b: &[f64]| vec![b[0] + b[1], b[1]];
+        let solve_t = |b: &[f64]| vec![b[0], b[0] + b[1]];
+        assert_eq!(super::onenormest(2, &solve, &solve_t), 2.);
+    }
+}
@@ -0,0 +1,416 @@
+///! Linear operators
+///!
+///! Iterative solvers and eigensolvers only need the action of a matrix on
+///! vectors, which does not require the matrix to be stored. The
+///! `LinearOperator` trait describes this action, and is implemented by
+///! sparse matrices, permutations and diagonal operators.
+///!
+///! Operators can be combined without forming the resulting matrix, using
+///! `SumOperator`, `ProductOperator` and `TransposeOperator`.
+use std::ops::Deref;
+
+use ndarray::{Array, ArrayView1, ArrayViewMut1};
+use num_traits::Num;
+
+use indexing::SpIndex;
+use sparse::permutation::Permutation;
+use sparse::CsMatBase;
+
+/// A linear operator `A`, described by its action on vectors
+///
+/// The solvers of `linalg::iterative` accept any linear operator as system
+/// matrix, in particular sparse matrices stored in either CSR or CSC
+/// format.
+///
+/// # Example
+///
+/// The operator `B B^T + D` can be applied without forming `B B^T`:
+///
+/// ```rust
+/// # extern crate ndarray;
+/// # extern crate sprs;
+/// use ndarray::{arr1, Array};
+/// use sprs::linalg::operator::{
+///     DiagonalOperator, LinearOperator, ProductOperator, SumOperator,
+///     TransposeOperator,
+/// };
+/// use sprs::CsMat;
+/// # fn main() {
+/// let b = CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1], vec![1., 2., 3.]);
+/// let d = DiagonalOperator::new(vec![1., 1.]);
+/// let bbt = ProductOperator::new(&b, TransposeOperator::new(&b));
+/// let op = SumOperator::new(bbt, d);
+/// assert_eq!(op.shape(), (2, 2));
+/// let mut y = Array::zeros(2);
+/// op.apply(arr1(&[1., 1.]).view(), y.view_mut());
+/// assert_eq!(y, arr1(&[6., 10.]));
+/// # }
+/// ```
+pub trait LinearOperator<N> {
+    /// The shape `(rows, cols)` of the operator
+    fn shape(&self) -> (usize, usize);
+
+    /// Compute `out = A x`
+    ///
+    /// # Panics
+    ///
+    /// * if the dimensions of `x` or `out` do not match the operator
+    fn apply(&self, x: ArrayView1<N>, out: ArrayViewMut1<N>);
+
+    /// Compute `out = A^T x`
+    ///
+    /// # Panics
+    ///
+    /// * if the dimensions of `x` or `out` do not match the transposed
+    ///   operator
+    fn apply_transpose(&self, x: ArrayView1<N>, out: ArrayViewMut1<N>);
+}
+
+fn check_dimensions<N>(
+    shape: (usize, usize),
+    x: &ArrayView1<N>,
+    out: &ArrayViewMut1<N>,
+) {
+    assert!(
+        x.len() == shape.1 && out.len() == shape.0,
+        "Dimension mismatch"
+    );
+}
+
+impl<'a, N, T> LinearOperator<N> for &'a T
+where
+    T: 'a + LinearOperator<N> + ?Sized,
+{
+    fn shape(&self) -> (usize, usize) {
+        (**self).shape()
+    }
+
+    fn apply(&self, x: ArrayView1<N>, out: ArrayViewMut1<N>) {
+        (**self).apply(x, out)
+    }
+
+    fn apply_transpose(&self, x: ArrayView1<N>, out: ArrayViewMut1<N>) {
+        (**self).apply_transpose(x, out)
+    }
+}
+
+/// A sparse matrix, stored in either CSR or CSC format
+impl<N, I, IpStorage, IStorage, DStorage> LinearOperator<N>
+    for CsMatBase<N, I, IpStorage, IStorage, DStorage>
+where
+    N: Copy + Num,
+    I: SpIndex,
+    IpStorage: Deref<Target = [I]>,
+    IStorage: Deref<Target = [I]>,
+    DStorage: Deref<Target = [N]>,
+{
+    fn shape(&self) -> (usize, usize) {
+        (self.rows(), self.cols())
+    }
+
+    fn apply(&self, x: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        check_dimensions(self.shape(), &x, &out);
+        out.fill(N::zero());
+        for (&val, (row, col)) in self.iter() {
+            let (row, col) = (row.index(), col.index());
+            out[row] = out[row] + val * x[col];
+        }
+    }
+
+    fn apply_transpose(&self, x: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        check_dimensions((self.cols(), self.rows()), &x, &out);
+        out.fill(N::zero());
+        for (&val, (row, col)) in self.iter() {
+            let (row, col) = (row.index(), col.index());
+            out[col] = out[col] + val * x[row];
+        }
+    }
+}
+
+/// The permutation matrix `P` such that `(P x)[k] = x[p[k]]`, whose
+/// transpose is the inverse permutation
+impl<N, I, IndStorage> LinearOperator<N> for Permutation<I, IndStorage>
+where
+    N: Copy,
+    I: SpIndex,
+    IndStorage: Deref<Target = [I]>,
+{
+    fn shape(&self) -> (usize, usize) {
+        (self.dim(), self.dim())
+    }
+
+    fn apply(&self, x: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        check_dimensions((self.dim(), self.dim()), &x, &out);
+        for (k, o) in out.iter_mut().enumerate() {
+            *o = x[self.at(k)];
+        }
+    }
+
+    fn apply_transpose(&self, x: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        check_dimensions((self.dim(), self.dim()), &x, &out);
+        for (k, o) in out.iter_mut().enumerate() {
+            *o = x[self.at_inv(k)];
+        }
+    }
+}
+
+/// A diagonal operator
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagonalOperator<N> {
+    diag: Vec<N>,
+}
+
+impl<N> DiagonalOperator<N> {
+    /// Create the diagonal operator with the given diagonal entries
+    pub fn new(diag: Vec<N>) -> DiagonalOperator<N> {
+        DiagonalOperator { diag: diag }
+    }
+
+    /// The diagonal entries of the operator
+    pub fn diag(&self) -> &[N] {
+        &self.diag
+    }
+}
+
+impl<N: Copy + Num> LinearOperator<N> for DiagonalOperator<N> {
+    fn shape(&self) -> (usize, usize) {
+        (self.diag.len(), self.diag.len())
+    }
+
+    fn apply(&self, x: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        check_dimensions(self.shape(), &x, &out);
+        for ((o, &xi), &d) in out.iter_mut().zip(x.iter()).zip(&self.diag) {
+            *o = d * xi;
+        }
+    }
+
+    fn apply_transpose(&self, x: ArrayView1<N>, out: ArrayViewMut1<N>) {
+        self.apply(x, out);
+    }
+}
+
+/// The sum `A + B` of two operators of the same shape
+#[derive(Debug, Clone)]
+pub struct SumOperator<A, B> {
+    lhs: A,
+    rhs: B,
+}
+
+impl<A, B> SumOperator<A, B> {
+    /// Create the sum `lhs + rhs` of two operators
+    ///
+    /// # Panics
+    ///
+    /// * if the shapes of the operators differ
+    pub fn new<N>(lhs: A, rhs: B) -> SumOperator<A, B>
+    where
+        A: LinearOperator<N>,
+        B: LinearOperator<N>,
+    {
+        assert!(lhs.shape() == rhs.shape(), "Dimension mismatch");
+        SumOperator { lhs: lhs, rhs: rhs }
+    }
+}
+
+impl<N, A, B> LinearOperator<N> for SumOperator<A, B>
+where
+    N: Copy + Num,
+    A: LinearOperator<N>,
+    B: LinearOperator<N>,
+{
+    fn shape(&self) -> (usize, usize) {
+        self.lhs.shape()
+    }
+
+    fn apply(&self, x: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        self.lhs.apply(x.view(), out.view_mut());
+        let mut tmp = Array::from_elem(out.len(), N::zero());
+        self.rhs.apply(x, tmp.view_mut());
+        out.zip_mut_with(&tmp, |o, &t| *o = *o + t);
+    }
+
+    fn apply_transpose(&self, x: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        self.lhs.apply_transpose(x.view(), out.view_mut());
+        let mut tmp = Array::from_elem(out.len(), N::zero());
+        self.rhs.apply_transpose(x, tmp.view_mut());
+        out.zip_mut_with(&tmp, |o, &t| *o = *o + t);
+    }
+}
+
+/// The product `A B` of two operators
+#[derive(Debug, Clone)]
+pub struct ProductOperator<A, B> {
+    lhs: A,
+    rhs: B,
+}
+
+impl<A, B> ProductOperator<A, B> {
+    /// Create the product `lhs rhs` of two operators
+    ///
+    /// # Panics
+    ///
+    /// * if the number of columns of `lhs` differs from the number of rows
+    ///   of `rhs`
+    pub fn new<N>(lhs: A, rhs: B) -> ProductOperator<A, B>
+    where
+        A: LinearOperator<N>,
+        B: LinearOperator<N>,
+    {
+        assert!(lhs.shape().1 == rhs.shape().0, "Dimension mismatch");
+        ProductOperator { lhs: lhs, rhs: rhs }
+    }
+}
+
+impl<N, A, B> LinearOperator<N> for ProductOperator<A, B>
+where
+    N: Copy + Num,
+    A: LinearOperator<N>,
+    B: LinearOperator<N>,
+{
+    fn shape(&self) -> (usize, usize) {
+        (self.lhs.shape().0, self.rhs.shape().1)
+    }
+
+    fn apply(&self, x: ArrayView1<N>, out: ArrayViewMut1<N>) {
+        let mut tmp = Array::from_elem(self.rhs.shape().0, N::zero());
+        self.rhs.apply(x, tmp.view_mut());
+        self.lhs.apply(tmp.view(), out);
+    }
+
+    fn apply_transpose(&self, x: ArrayView1<N>, out: ArrayViewMut1<N>) {
+        let mut tmp = Array::from_elem(self.lhs.shape().1, N::zero());
+        self.lhs.apply_transpose(x, tmp.view_mut());
+        self.rhs.apply_transpose(tmp.view(), out);
+    }
+}
+
+/// The transpose `A^T` of an operator
+#[derive(Debug, Clone)]
+pub struct TransposeOperator<A> {
+    inner: A,
+}
+
+impl<A> TransposeOperator<A> {
+    /// Create the transpose of an operator
+    pub fn new(inner: A) -> TransposeOperator<A> {
+        TransposeOperator { inner: inner }
+    }
+}
+
+impl<N, A> LinearOperator<N> for TransposeOperator<A>
+where
+    A: LinearOperator<N>,
+{
+    fn shape(&self) -> (usize, usize) {
+        let (rows, cols) = self.inner.shape();
+        (cols, rows)
+    }
+
+    fn apply(&self, x: ArrayView1<N>, out: ArrayViewMut1<N>) {
+        self.inner.apply_transpose(x, out);
+    }
+
+    fn apply_transpose(&self, x: ArrayView1<N>, out: ArrayViewMut1<N>) {
+        self.inner.apply(x, out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        DiagonalOperator, LinearOperator, ProductOperator, SumOperator,
+        TransposeOperator,
+    };
+    use ndarray::{Array, Ix1};
+    use sparse::permutation::PermOwned;
+    use sparse::{CsMat, TriMat};
+    use test_data::{mat1, mat3};
+
+    fn apply<Op: LinearOperator<f64>>(
+        op: &Op,
+        x: &Array<f64, Ix1>,
+    ) -> Array<f64, Ix1> {
+        let mut out = Array::zeros(op.shape().0);
+        op.apply(x.view(), out.view_mut());
+        out
+    }
+
+    fn apply_transpose<Op: LinearOperator<f64>>(
+        op: &Op,
+        x: &Array<f64, Ix1>,
+    ) -> Array<f64, Ix1> {
+        let mut out = Array::zeros(op.shape().1);
+        op.apply_transpose(x.view(), out.view_mut());
+        out
+    }
+
+    fn assert_close(a: &Array<f64, Ix1>, b: &Array<f64, Ix1>) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn sparse_matrix_operator() {
+        let mat = mat3();
+        let (rows, cols) = (mat.rows(), mat.cols());
+        let x = Array::from_shape_fn(cols, |i| i as f64 - 1.5);
+        let y = Array::from_shape_fn(rows, |i| (i as f64).sin());
+        let expected = &mat * &x;
+        let expected_t = &mat.transpose_view().to_owned() * &y;
+        for mat in &[mat.to_owned(), mat.to_csc()] {
+            assert_eq!(LinearOperator::<f64>::shape(mat), (rows, cols));
+            assert_close(&apply(mat, &x), &expected);
+            assert_close(&apply_transpose(&mat.view(), &y), &expected_t);
+            let t = TransposeOperator::new(mat.view());
+            assert_eq!(t.shape(), (cols, rows));
+            assert_close(&apply(&t, &y), &expected_t);
+        }
+    }
+
+    #[test]
+    fn permutation_operator() {
+        let perm = PermOwned::new(vec![2, 0, 3, 1, 4]);
+        let x = Array::from_vec(vec![1., 2., 3., 4., 0.]);
+        let px = apply(&perm, &x);
+        assert_eq!(px.to_vec(), &perm * x.as_slice().unwrap());
+        assert_eq!(px.to_vec(), vec![3., 1., 4., 2., 0.]);
+        assert_close(&apply_transpose(&perm, &px), &x);
+
+        // a matrix with permuted columns
+        let mat = mat1();
+        let ap = ProductOperator::new(&mat, &perm);
+        assert_eq!(ap.shape(), (5, 5));
+        assert_close(&apply(&ap, &x), &(&mat * &px));
+    }
+
+    #[test]
+    fn composed_operator() {
+        // A = B B^T + D, with a rectangular B
+        let mut tri = TriMat::new((4, 6));
+        for i in 0..4 {
+            tri.add_triplet(i, i, 2.);
+            tri.add_triplet(i, i + 2, -1.);
+        }
+        let b: CsMat<f64> = tri.to_csr();
+        let diag = vec![1., 2., 3., 4.];
+        let op = SumOperator::new(
+            ProductOperator::new(&b, TransposeOperator::new(&b)),
+            DiagonalOperator::new(diag.clone()),
+        );
+        assert_eq!(op.shape(), (4, 4));
+        let d = CsMat::new((4, 4), vec![0, 1, 2, 3, 4], vec![0, 1, 2, 3], diag);
+        let mat = &(&b * &b.transpose_view()) + &d;
+        let x = Array::from_vec(vec![0.5, -1., 2., 3.]);
+        assert_close(&apply(&op, &x), &(&mat * &x));
+        assert_close(&apply_transpose(&op, &x), &(&mat * &x));
+    }
+
+    #[test]
+    #[should_panic]
+    fn sum_dimension_mismatch() {
+        let mat = mat3();
+        let _ = SumOperator::new(&mat, DiagonalOperator::new(vec![1.; 5]));
+    }
+}
@@ -0,0 +1,166 @@
+///! Reordering algorithms for sparse matrices
+///!
+///! The orderings are described by a permutation `P`, where the `k`-th node
+///! of the reordered matrix `P A P^T` is the node `P.at(k)` of `A`.
+use std::collections::VecDeque;
+
+use graph::LevelStructure;
+use indexing::SpIndex;
+use sparse::permutation::PermOwnedI;
+use sparse::CsMatViewI;
+
+/// Compute the Reverse Cuthill-McKee ordering of a structurally symmetric
+/// matrix.
+///
+/// This ordering tends to reduce the bandwidth and the profile of the matrix,
+/// which is helpful for banded solvers and improves the cache locality of
+/// matrix vector products.
+///
+/// Each connected component of the graph of the matrix is traversed in
+/// breadth first order, starting from a pseudo-peripheral node found by
+/// the algorithm of George and Liu. Only the non-zero pattern of the matrix
+/// is used, and its structural symmetry is not checked.
+///
+/// # Panics
+///
+/// * if mat is not square
+pub fn reverse_cuthill_mckee<N, I>(mat: CsMatViewI<N, I>) -> PermOwnedI<I>
+where
+    I: SpIndex,
+{
+    let n = mat.rows();
+    assert!(mat.cols() == n, "matrix should be square");
+    let degrees: Vec<usize> = mat
+        .outer_iterator()
+        .enumerate()
+        .map(|(outer, vec)| {
+            vec.iter().filter(|&(inner, _)| inner != outer).count()
+        })
+        .collect();
+    // starting with low degree nodes helps finding peripheral nodes
+    let mut by_degree: Vec<usize> = (0..n).collect();
+    by_degree.sort_by_key(|&i| degrees[i]);
+
+    let mut visited = vec![false; n];
+    let mut levels = LevelStructure::new(n);
+    let mut order = Vec::with_capacity(n);
+    let mut queue = VecDeque::new();
+    let mut neighbours = Vec::new();
+    for &start in &by_degree {
+        if visited[start] {
+            continue;
+        }
+        let root = pseudo_peripheral_node(&mat, &degrees, start, &mut levels);
+        // Cuthill-McKee traversal of the connected component
+        visited[root] = true;
+        queue.push_back(root);
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+            neighbours.clear();
+            let vec = mat.outer_view(node).expect("node is in bounds");
+            for (neighbour, _) in vec.iter() {
+                if !visited[neighbour] {
+                    visited[neighbour] = true;
+                    neighbours.push(neighbour);
+                }
+            }
+            neighbours.sort_by_key(|&i| degrees[i]);
+            queue.extend(neighbours.iter());
+        }
+    }
+    order.reverse();
+    PermOwnedI::new(order.into_iter().map(I::from_usize).collect())
+}
+
+/// Find a pseudo-peripheral node in the connected component of `start`,
+/// ie a node whose eccentricity is close to the diameter of the component.
+fn pseudo_peripheral_node<N, I>(
+    mat: &CsMatViewI<N, I>,
+    degrees: &[usize],
+    start: usize,
+    levels: &mut LevelStructure,
+) -> usize
+where
+    I: SpIndex,
+{
+    let mut root = start;
+    levels.build(mat, root);
+    loop {
+        let nb_levels = levels.nb_levels();
+        let candidate = *levels
+            .last_level()
+            .iter()
+            .min_by_key(|&&i| degrees[i])
+            .expect("levels are not empty");
+        levels.build(mat, candidate);
+        if levels.nb_levels() > nb_levels {
+            root = candidate;
+        } else {
+            return root;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sparse::permutation::{transform_mat_papt, PermOwned};
+    use sparse::{CsMat, TriMat};
+    use test_data::grid_laplacian;
+
+    fn is_permutation(perm: &[usize]) -> bool {
+        let mut seen = vec![false; perm.len()];
+        for &i in perm {
+            if i >= perm.len() || seen[i] {
+                return false;
+            }
+            seen[i] = true;
+        }
+        true
+    }
+
+    #[test]
+    fn rcm_grid() {
+        let lap = grid_laplacian(12, 8);
+        let n = lap.rows();
+        // scramble the ordering of the grid
+        let scramble: Vec<usize> = (0..n).map(|i| (i * 37) % n).collect();
+        assert!(is_permutation(&scramble));
+        let scramble = PermOwned::new(scramble);
+        let mat = transform_mat_papt(lap.view(), scramble.view());
+        assert!(mat.bandwidth() > 50);
+
+        let perm = super::reverse_cuthill_mckee(mat.view());
+        assert!(is_permutation(&perm.vec()));
+        let reordered = transform_mat_papt(mat.view(), perm.view());
+        // the natural ordering along the shortest side is optimal
+        assert!(reordered.bandwidth() <= 9);
+        assert!(reordered.profile() <= lap.profile());
+        assert!(reordered.profile() < mat.profile());
+
+        let perm_csc = super::reverse_cuthill_mckee(mat.to_csc().view());
+        assert_eq!(perm_csc.vec(), perm.vec());
+    }
+
+    #[test]
+    fn rcm_disconnected() {
+        // two paths and an isolated node, interleaved
+        let mut tri = TriMat::new((9, 9));
+        for i in 0..9 {
+            tri.add_triplet(i, i, 2.);
+        }
+        for &(i, j) in &[(0, 2), (2, 4), (4, 6), (6, 8), (1, 5), (5, 7)] {
+            tri.add_triplet(i, j, -1.);
+            tri.add_triplet(j, i, -1.);
+        }
+        let mat: CsMat<f64> = tri.to_csr();
+        assert_eq!(mat.bandwidth(), 4);
+        let perm = super::reverse_cuthill_mckee(mat.view());
+        assert!(is_permutation(&perm.vec()));
+        let reordered = transform_mat_papt(mat.view(), perm.view());
+        assert_eq!(reordered.bandwidth(), 1);
+        assert_eq!(reordered.profile(), 6);
+
+        let empty: CsMat<f64> = CsMat::zero((0, 0));
+        assert_eq!(super::reverse_cuthill_mckee(empty.view()).vec().len(), 0);
+    }
+}
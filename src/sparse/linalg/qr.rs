@@ -0,0 +1,559 @@
+///! Sparse QR factorization
+///!
+///! The QR factorization `A Q_c = Q R` of a sparse matrix, where `Q_c` is
+///! a column permutation reducing fill-in, `Q` is orthogonal and `R` is upper
+///! triangular, is computed by applying Householder reflections column by
+///! column. `Q` is never formed explicitly, it is stored as the sequence of
+///! its Householder vectors, followed by a permutation moving the pivot row
+///! of each reflection to the corresponding row of `R`.
+///!
+///! Each reflection only modifies the columns which are ancestors of its
+///! column in the column elimination tree of `A Q_c`, ie the elimination
+///! tree of `Q_c^T A^T A Q_c`. The reflections applied to a column are thus
+///! found by walking up this tree from the first column of each row of
+///! `A Q_c` having a non-zero in this column, which makes the cost of the
+///! factorization proportional to the number of floating point operations,
+///! as in CSparse's `cs_qr`.
+///!
+///! The main use of this factorization is solving least squares problems
+///! `min ||A x - b||`, using `SparseQr::solve_least_squares`.
+///!
+///! The factorization is rank revealing in a simple way: a column whose
+///! remaining norm is below a tolerance after applying the previous
+///! reflections is considered linearly dependent on the previous columns,
+///! and no reflection is created for it. `R` is then upper trapezoidal,
+///! with as many rows as the numerical rank of `A`.
+use num_traits::Float;
+
+use indexing::SpIndex;
+use sparse::linalg::etree::column_etree;
+use sparse::permutation::{
+    transform_mat_aq, PermOwnedI, PermViewI, Permutation,
+};
+use sparse::{CsMatI, CsMatViewI};
+
+/// Householder QR factorization of a sparse matrix
+#[derive(Debug)]
+pub struct SparseQr<N, I: SpIndex> {
+    /// Householder vectors, with a unit first non-zero, in CSC format
+    v: CsMatI<N, I>,
+    /// Householder coefficients
+    beta: Vec<N>,
+    /// Upper trapezoidal factor, in CSC format
+    r: CsMatI<N, I>,
+    /// The column of `A Q_c` which created each Householder reflection
+    pivot_cols: Vec<usize>,
+    /// The pivot rows of the reflections, followed by the other rows
+    row_perm: PermOwnedI<I>,
+    col_perm: PermOwnedI<I>,
+    tol: N,
+}
+
+/// Sparse accumulator of a column, with its non-zero pattern
+struct SparseColumn<N> {
+    values: Vec<N>,
+    in_pattern: Vec<bool>,
+    pattern: Vec<usize>,
+}
+
+impl<N: Float> SparseColumn<N> {
+    fn new(n: usize) -> SparseColumn<N> {
+        SparseColumn {
+            values: vec![N::zero(); n],
+            in_pattern: vec![false; n],
+            pattern: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, ind: usize, val: N) {
+        if !self.in_pattern[ind] {
+            self.in_pattern[ind] = true;
+            self.pattern.push(ind);
+        }
+        self.values[ind] = self.values[ind] + val;
+    }
+
+    fn clear(&mut self) {
+        for &ind in &self.pattern {
+            self.values[ind] = N::zero();
+            self.in_pattern[ind] = false;
+        }
+        self.pattern.clear();
+    }
+}
+
+impl<N, I> SparseQr<N, I>
+where
+    N: Float + Default,
+    I: SpIndex,
+{
+    /// Compute the QR factorization of the given matrix, without column
+    /// reordering.
+    ///
+    /// The matrix can be stored in either CSC or CSR format, though the
+    /// factorization works on its CSC representation.
+    pub fn new(mat: CsMatViewI<N, I>) -> SparseQr<N, I> {
+        let perm = Permutation::identity(mat.cols());
+        SparseQr::new_perm(mat, perm)
+    }
+
+    /// Compute the QR factorization `A Q_c = Q R` of the given matrix, where
+    /// the `k`-th column of `A Q_c` is the column `col_perm.at(k)` of `A`.
+    ///
+    /// The rank tolerance is `20 (m + n) eps max_j ||A_j||`, where `A_j` is
+    /// the `j`-th column of `A`.
+    ///
+    /// # Panics
+    ///
+    /// * if the dimension of `col_perm` does not match the columns of `mat`
+    pub fn new_perm(
+        mat: CsMatViewI<N, I>,
+        col_perm: PermOwnedI<I>,
+    ) -> SparseQr<N, I> {
+        let max_col_norm = column_norms(&mat)
+            .into_iter()
+            .fold(N::zero(), |acc, norm| acc.max(norm));
+        let dims = N::from(mat.rows() + mat.cols()).unwrap();
+        let tol = N::from(20.).unwrap() * dims * N::epsilon() * max_col_norm;
+        SparseQr::new_perm_tol(mat, col_perm, tol)
+    }
+
+    /// Compute the QR factorization `A Q_c = Q R` of the given matrix,
+    /// considering columns whose norm, after applying the previous
+    /// Householder reflections, is at most `rank_tol` as linearly dependent.
+    ///
+    /// # Panics
+    ///
+    /// * if the dimension of `col_perm` does not match the columns of `mat`
+    /// * if `rank_tol` is negative
+    pub fn new_perm_tol(
+        mat: CsMatViewI<N, I>,
+        col_perm: PermOwnedI<I>,
+        rank_tol: N,
+    ) -> SparseQr<N, I> {
+        let (m, n) = mat.shape();
+        assert_eq!(col_perm.dim(), n, "Dimension mismatch");
+        assert!(rank_tol >= N::zero(), "tolerance should be non-negative");
+        let mat = if mat.is_csc() {
+            transform_mat_aq(mat, col_perm.inv().view())
+        } else {
+            transform_mat_aq(mat.to_csc().view(), col_perm.inv().view())
+        };
+        let parents = column_etree(mat.view());
+        // the first column having a non-zero in each row
+        let mut leftmost = vec![n; m];
+        for (k, col) in mat.outer_iterator().enumerate() {
+            for (row, _) in col.iter() {
+                if leftmost[row] == n {
+                    leftmost[row] = k;
+                }
+            }
+        }
+
+        let mut v_indptr = vec![I::zero()];
+        let mut v_indices: Vec<I> = Vec::new();
+        let mut v_data: Vec<N> = Vec::new();
+        let mut beta: Vec<N> = Vec::new();
+        let mut r_indptr = vec![I::zero()];
+        let mut r_indices = Vec::new();
+        let mut r_data = Vec::new();
+        let mut pivot_cols = Vec::new();
+        // the reflection created by each column, and the one pivoting on
+        // each row
+        let mut col_reflections: Vec<Option<usize>> = vec![None; n];
+        let mut row_reflections: Vec<Option<usize>> = vec![None; m];
+        let mut x = SparseColumn::new(m);
+        let mut entries = Vec::new();
+        let mut reach = Vec::new();
+        let mut marks = vec![n; n];
+
+        for k in 0..n {
+            let col = mat.outer_view(k).expect("column is in bounds");
+            // the columns whose reflections can modify this column
+            reach.clear();
+            for (row, &val) in col.iter() {
+                x.add(row, val);
+                let mut node = leftmost[row];
+                while node < k && marks[node] != k {
+                    marks[node] = k;
+                    reach.push(node);
+                    node = parents
+                        .get_parent(node)
+                        .expect("k is an ancestor of the leftmost column");
+                }
+            }
+            // increasing columns are a topological order of the tree
+            reach.sort();
+            // apply the reflections H_j = I - beta_j v_j v_j^T
+            for &j in &reach {
+                let j = match col_reflections[j] {
+                    Some(j) => j,
+                    None => continue,
+                };
+                let range = v_indptr[j].index()..v_indptr[j + 1].index();
+                let dot = v_indices[range.clone()]
+                    .iter()
+                    .zip(&v_data[range.clone()])
+                    .fold(N::zero(), |acc, (i, &v)| {
+                        acc + v * x.values[i.index()]
+                    });
+                if dot == N::zero() {
+                    continue;
+                }
+                let scale = beta[j] * dot;
+                for (i, &v) in
+                    v_indices[range.clone()].iter().zip(&v_data[range])
+                {
+                    x.add(i.index(), -scale * v);
+                }
+            }
+
+            // the pivot rows of the previous reflections hold final entries
+            // of R
+            let rank = beta.len();
+            entries.clear();
+            entries.extend(
+                x.pattern.iter().filter_map(|&i| {
+                    row_reflections[i].map(|j| (j, x.values[i]))
+                }),
+            );
+            entries.sort_by_key(|&(j, _)| j);
+            let mut remaining: Vec<usize> = x
+                .pattern
+                .iter()
+                .cloned()
+                .filter(|&i| row_reflections[i].is_none())
+                .collect();
+            remaining.sort();
+            let norm = remaining
+                .iter()
+                .fold(N::zero(), |acc, &i| acc.hypot(x.values[i]));
+
+            if norm > rank_tol {
+                // Householder reflection mapping the remaining rows to
+                // alpha e_p, where p is the first remaining row
+                let pivot = remaining[0];
+                let x_p = x.values[pivot];
+                let alpha = if x_p > N::zero() { -norm } else { norm };
+                beta.push((alpha - x_p) / alpha);
+                let v_scale = N::one() / (x_p - alpha);
+                v_indices.push(I::from_usize(pivot));
+                v_data.push(N::one());
+                for &i in &remaining[1..] {
+                    v_indices.push(I::from_usize(i));
+                    v_data.push(x.values[i] * v_scale);
+                }
+                v_indptr.push(I::from_usize(v_indices.len()));
+                entries.push((rank, alpha));
+                pivot_cols.push(k);
+                col_reflections[k] = Some(rank);
+                row_reflections[pivot] = Some(rank);
+            }
+            for &(i, val) in &entries {
+                r_indices.push(I::from_usize(i));
+                r_data.push(val);
+            }
+            r_indptr.push(I::from_usize(r_indices.len()));
+            x.clear();
+        }
+
+        // Q^T maps the pivot row of the j-th reflection to the j-th row
+        let mut row_perm: Vec<I> = v_indptr[..beta.len()]
+            .iter()
+            .map(|&start| v_indices[start.index()])
+            .collect();
+        row_perm.extend(
+            (0..m)
+                .filter(|&i| row_reflections[i].is_none())
+                .map(I::from_usize),
+        );
+        let rank = beta.len();
+        SparseQr {
+            v: CsMatI::new_csc((m, rank), v_indptr, v_indices, v_data),
+            beta: beta,
+            r: CsMatI::new_csc((rank, n), r_indptr, r_indices, r_data),
+            pivot_cols: pivot_cols,
+            row_perm: PermOwnedI::new(row_perm),
+            col_perm: col_perm,
+            tol: rank_tol,
+        }
+    }
+
+    /// The numerical rank of the factored matrix
+    pub fn rank(&self) -> usize {
+        self.beta.len()
+    }
+
+    /// Whether the factored matrix does not have full column rank
+    pub fn is_rank_deficient(&self) -> bool {
+        self.rank() < self.r.cols()
+    }
+
+    /// The tolerance used to determine the rank
+    pub fn rank_tol(&self) -> N {
+        self.tol
+    }
+
+    /// The upper trapezoidal factor R, in CSC format, of shape
+    /// `(rank, ncols)`
+    pub fn r(&self) -> CsMatViewI<N, I> {
+        self.r.view()
+    }
+
+    /// The Householder vectors describing Q, in CSC format. The first
+    /// non-zero of each vector is its pivot row.
+    pub fn householder_vectors(&self) -> CsMatViewI<N, I> {
+        self.v.view()
+    }
+
+    /// The column permutation: the `k`-th column of `A Q_c` is the column
+    /// `col_perm().at(k)` of `A`.
+    pub fn col_perm(&self) -> PermViewI<I> {
+        self.col_perm.view()
+    }
+
+    /// Compute `Q^T b` in place
+    ///
+    /// # Panics
+    ///
+    /// * if the length of `b` is not the number of rows of the matrix
+    pub fn apply_qt(&self, b: &mut [N]) {
+        assert_eq!(b.len(), self.v.rows(), "Dimension mismatch");
+        for (vec, &beta) in self.v.outer_iterator().zip(&self.beta) {
+            apply_householder(vec.indices(), vec.data(), beta, b);
+        }
+        let permuted = &self.row_perm * &b[..];
+        b.copy_from_slice(&permuted);
+    }
+
+    /// Compute `Q b` in place
+    ///
+    /// # Panics
+    ///
+    /// * if the length of `b` is not the number of rows of the matrix
+    pub fn apply_q(&self, b: &mut [N]) {
+        assert_eq!(b.len(), self.v.rows(), "Dimension mismatch");
+        let permuted = &self.row_perm.inv() * &b[..];
+        b.copy_from_slice(&permuted);
+        for (vec, &beta) in self.v.outer_iterator().zip(&self.beta).rev() {
+            apply_householder(vec.indices(), vec.data(), beta, b);
+        }
+    }
+
+    /// Solve the least squares problem `min ||A x - b||`.
+    ///
+    /// If the matrix is rank deficient, a basic solution is returned, where
+    /// the entries corresponding to the linearly dependent columns are zero.
+    ///
+    /// # Panics
+    ///
+    /// * if the length of `rhs` is not the number of rows of the matrix
+    pub fn solve_least_squares(&self, rhs: &[N]) -> Vec<N> {
+        let mut c = rhs.to_vec();
+        self.apply_qt(&mut c);
+        let mut y = vec![N::zero(); self.r.cols()];
+        // back substitution on the columns which created a reflection
+        for (j, &k) in self.pivot_cols.iter().enumerate().rev() {
+            let col = self.r.outer_view(k).expect("column is in bounds");
+            let diag = *col.data().last().expect("pivot column is not empty");
+            let y_k = c[j] / diag;
+            y[k] = y_k;
+            for (i, &val) in col.iter() {
+                if i < j {
+                    c[i] = c[i] - val * y_k;
+                }
+            }
+        }
+        let col_perm_inv = self.col_perm.inv();
+        &col_perm_inv * &y[..]
+    }
+}
+
+/// Apply the reflection `I - beta v v^T` to `b`
+fn apply_householder<N, I>(indices: &[I], data: &[N], beta: N, b: &mut [N])
+where
+    N: Float,
+    I: SpIndex,
+{
+    let dot = indices
+        .iter()
+        .zip(data)
+        .fold(N::zero(), |acc, (i, &v)| acc + v * b[i.index()]);
+    let scale = beta * dot;
+    for (i, &v) in indices.iter().zip(data) {
+        b[i.index()] = b[i.index()] - scale * v;
+    }
+}
+
+fn column_norms<N, I>(mat: &CsMatViewI<N, I>) -> Vec<N>
+where
+    N: Float,
+    I: SpIndex,
+{
+    let mut norms = vec![N::zero(); mat.cols()];
+    for (&val, (_, col)) in mat.iter() {
+        norms[col.index()] = norms[col.index()].hypot(val);
+    }
+    norms
+}
+
+#[cfg(test)]
+mod test {
+    use super::SparseQr;
+    use sparse::permutation::Permutation;
+    use sparse::{CsMat, TriMat};
+
+    fn mat_vec(mat: &CsMat<f64>, x: &[f64]) -> Vec<f64> {
+        let mut res = vec![0.; mat.rows()];
+        for (&val, (row, col)) in mat.iter() {
+            res[row] += val * x[col];
+        }
+        res
+    }
+
+    fn mat_t_vec(mat: &CsMat<f64>, x: &[f64]) -> Vec<f64> {
+        mat_vec(&mat.transpose_view().to_owned(), x)
+    }
+
+    /// Sparse over-determined matrix, of shape (3 n, n)
+    fn tall_mat(n: usize) -> CsMat<f64> {
+        let mut tri = TriMat::new((3 * n, n));
+        for j in 0..n {
+            tri.add_triplet(j, j, 2. + (j % 3) as f64);
+            tri.add_triplet(n + j, j, 1.);
+            tri.add_triplet(2 * n + (j * 5) % n, j, -0.5);
+            if j + 1 < n {
+                tri.add_triplet(j + 1, j, 0.7);
+            }
+        }
+        tri.to_csc()
+    }
+
+    #[test]
+    fn qr_consistent_system() {
+        let mat = tall_mat(12);
+        let x0: Vec<f64> = (0..12).map(|i| (i as f64 * 0.9).cos()).collect();
+        let b = mat_vec(&mat, &x0);
+        let qr = SparseQr::new(mat.view());
+        assert_eq!(qr.rank(), 12);
+        assert!(!qr.is_rank_deficient());
+        let x = qr.solve_least_squares(&b);
+        for (xi, ei) in x.iter().zip(&x0) {
+            assert!((xi - ei).abs() < 1e-12);
+        }
+        // R is upper triangular
+        for (col, vec) in qr.r().outer_iterator().enumerate() {
+            assert!(vec.indices().iter().all(|&row| row <= col));
+        }
+    }
+
+    #[test]
+    fn qr_least_squares() {
+        let mat = tall_mat(10);
+        let b: Vec<f64> = (0..30).map(|i| 1. + (i % 4) as f64).collect();
+        let col_perm = Permutation::new((0..10).rev().collect());
+        let qr = SparseQr::new_perm(mat.view(), col_perm);
+        let x = qr.solve_least_squares(&b);
+        // the residual is orthogonal to the range of A
+        let ax = mat_vec(&mat, &x);
+        let res: Vec<f64> = b.iter().zip(&ax).map(|(bi, ai)| bi - ai).collect();
+        let normal = mat_t_vec(&mat, &res);
+        assert!(normal.iter().all(|v| v.abs() < 1e-12));
+        let x_csr = SparseQr::new(mat.to_csr().view()).solve_least_squares(&b);
+        for (xi, ei) in x.iter().zip(&x_csr) {
+            assert!((xi - ei).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn qr_apply_q() {
+        let mat = tall_mat(6);
+        let qr = SparseQr::new(mat.view());
+        let b: Vec<f64> = (0..18).map(|i| (i as f64).sin()).collect();
+        let mut c = b.clone();
+        qr.apply_qt(&mut c);
+        let norm_b = b.iter().fold(0., |acc: f64, v| acc.hypot(*v));
+        let norm_c = c.iter().fold(0., |acc: f64, v| acc.hypot(*v));
+        assert!((norm_b - norm_c).abs() < 1e-12);
+        qr.apply_q(&mut c);
+        for (ci, bi) in c.iter().zip(&b) {
+            assert!((ci - bi).abs() < 1e-12);
+        }
+        // Q^T A = R
+        for (k, col) in mat.outer_iterator().enumerate() {
+            let mut a_k = vec![0.; 18];
+            col.scatter(&mut a_k);
+            qr.apply_qt(&mut a_k);
+            for (i, &val) in a_k.iter().enumerate() {
+                let r_ik = if i < qr.rank() {
+                    qr.r().get(i, k).cloned().unwrap_or(0.)
+                } else {
+                    0.
+                };
+                assert!((val - r_ik).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn qr_large_block_diagonal() {
+        // applying every previous reflection to each column would need
+        // about n^2 / 2 sparse dot products
+        let nb_blocks = 15000;
+        let n = 3 * nb_blocks;
+        let mut tri = TriMat::with_capacity((5 * nb_blocks, n), 11 * nb_blocks);
+        for block in 0..nb_blocks {
+            for i in 0..5 {
+                for j in 0..3 {
+                    if i >= 3 || (i as isize - j as isize).abs() <= 1 {
+                        let val = if i == j { 4. } else { 1. + (i + j) as f64 };
+                        tri.add_triplet(5 * block + i, 3 * block + j, val);
+                    }
+                }
+            }
+        }
+        let mat: CsMat<f64> = tri.to_csc();
+        let qr = SparseQr::new(mat.view());
+        assert_eq!(qr.rank(), n);
+        // the factors do not fill outside of the diagonal blocks
+        assert!(qr.r().nnz() <= 6 * nb_blocks);
+        assert!(qr.householder_vectors().nnz() <= 12 * nb_blocks);
+        let b: Vec<f64> = (0..mat.rows()).map(|i| (i as f64).sin()).collect();
+        let x = qr.solve_least_squares(&b);
+        let ax = mat_vec(&mat, &x);
+        let res: Vec<f64> = b.iter().zip(&ax).map(|(bi, ai)| bi - ai).collect();
+        let normal = mat_t_vec(&mat, &res);
+        assert!(normal.iter().all(|v| v.abs() < 1e-10));
+    }
+
+    #[test]
+    fn qr_rank_deficient() {
+        // | 1 0 1 |
+        // | 0 1 1 |
+        // | 1 1 2 |
+        // | 2 0 2 |
+        let mat = CsMat::new_csc(
+            (4, 3),
+            vec![0, 3, 5, 9],
+            vec![0, 2, 3, 1, 2, 0, 1, 2, 3],
+            vec![1., 1., 2., 1., 1., 1., 1., 2., 2.],
+        );
+        let qr = SparseQr::new(mat.view());
+        assert_eq!(qr.rank(), 2);
+        assert!(qr.is_rank_deficient());
+        assert_eq!(qr.r().shape(), (2, 3));
+        let b = vec![1., 2., 3., 5.];
+        let x = qr.solve_least_squares(&b);
+        // basic solution
+        assert_eq!(x[2], 0.);
+        let ax = mat_vec(&mat, &x);
+        let res: Vec<f64> = b.iter().zip(&ax).map(|(bi, ai)| bi - ai).collect();
+        let normal = mat_t_vec(&mat, &res);
+        assert!(normal.iter().all(|v| v.abs() < 1e-12));
+
+        let zero: CsMat<f64> = CsMat::zero((3, 2)).to_csc();
+        let qr = SparseQr::new(zero.view());
+        assert_eq!(qr.rank(), 0);
+        assert_eq!(qr.solve_least_squares(&[1., 2., 3.]), vec![0., 0.]);
+    }
+}
@@ -0,0 +1,429 @@
+///! Relaxation methods
+///!
+///! Relaxation sweeps update an approximate solution of `A x = b` one
+///! unknown at a time, using the diagonal of `A` and its rows. They converge
+///! slowly on their own, but quickly damp the oscillatory components of the
+///! error, which makes them good smoothers for multigrid methods, and cheap
+///! preconditioners for the solvers in `linalg::iterative`.
+///!
+///! The available sweeps are:
+///!
+///! - weighted Jacobi (`jacobi_sweep`), which updates all unknowns from the
+///!   previous approximation.
+///! - Gauss-Seidel (`gauss_seidel_sweep`) and successive over-relaxation
+///!   (`sor_sweep`), which use the updated unknowns as soon as they are
+///!   available, in forward, backward or symmetric order. A symmetric SOR
+///!   sweep is also called SSOR.
+///!
+///! A `Relaxation` describes one of these methods. It can be used as
+///! a stationary solver with `linalg::iterative::stationary`, or as
+///! a preconditioner with `RelaxationPreconditioner`.
+///!
+///! All sweeps require the matrix to be stored in CSR format, with non-zero
+///! diagonal entries.
+use ndarray::{Array, ArrayView1, ArrayViewMut1};
+use num_traits::Float;
+
+use indexing::SpIndex;
+use sparse::linalg::iterative::Preconditioner;
+use sparse::CsMatViewI;
+
+/// The order in which the unknowns are updated by a sweep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SweepDirection {
+    /// Update the unknowns by increasing index
+    Forward,
+    /// Update the unknowns by decreasing index
+    Backward,
+    /// A forward sweep followed by a backward sweep
+    Symmetric,
+}
+
+/// A relaxation method
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Relaxation<N> {
+    /// Weighted Jacobi, with relaxation weight `omega`
+    Jacobi { omega: N },
+    /// Successive over-relaxation, with relaxation weight `omega`. A weight
+    /// of one gives the Gauss-Seidel method.
+    Sor { omega: N, direction: SweepDirection },
+}
+
+impl<N: Float> Relaxation<N> {
+    /// Weighted Jacobi, with relaxation weight `omega`. A weight of `2 / 3`
+    /// is a common choice for smoothing.
+    pub fn jacobi(omega: N) -> Relaxation<N> {
+        Relaxation::Jacobi { omega: omega }
+    }
+
+    /// Gauss-Seidel, sweeping in the given direction
+    pub fn gauss_seidel(direction: SweepDirection) -> Relaxation<N> {
+        Relaxation::Sor {
+            omega: N::one(),
+            direction: direction,
+        }
+    }
+
+    /// Successive over-relaxation with weight `omega`, which should be in
+    /// `(0, 2)` for convergence on symmetric positive definite matrices.
+    pub fn sor(omega: N, direction: SweepDirection) -> Relaxation<N> {
+        Relaxation::Sor {
+            omega: omega,
+            direction: direction,
+        }
+    }
+
+    /// Symmetric successive over-relaxation with weight `omega`
+    pub fn ssor(omega: N) -> Relaxation<N> {
+        Relaxation::sor(omega, SweepDirection::Symmetric)
+    }
+
+    /// Perform one sweep of this method on the system `A x = b`, where `x`
+    /// holds the current approximation.
+    ///
+    /// # Panics
+    ///
+    /// * if `mat` is not square, or not stored in CSR format
+    /// * if the dimensions of `rhs` or `x` do not match `mat`
+    /// * if a diagonal entry of `mat` is zero or missing
+    pub fn sweep<I>(
+        &self,
+        mat: CsMatViewI<N, I>,
+        rhs: ArrayView1<N>,
+        x: ArrayViewMut1<N>,
+    ) where
+        I: SpIndex,
+    {
+        match *self {
+            Relaxation::Jacobi { omega } => jacobi_sweep(mat, rhs, x, omega),
+            Relaxation::Sor { omega, direction } => {
+                sor_sweep(mat, rhs, x, omega, direction)
+            }
+        }
+    }
+
+    /// Whether this method gives a symmetric preconditioner for a symmetric
+    /// matrix, as required by the Conjugate Gradient and MINRES methods.
+    pub fn is_symmetric(&self) -> bool {
+        match *self {
+            Relaxation::Jacobi { .. } => true,
+            Relaxation::Sor { direction, .. } => {
+                direction == SweepDirection::Symmetric
+            }
+        }
+    }
+}
+
+fn check_dimensions<N, I>(
+    mat: &CsMatViewI<N, I>,
+    rhs: &ArrayView1<N>,
+    x: &ArrayViewMut1<N>,
+) where
+    I: SpIndex,
+{
+    assert!(mat.rows() == mat.cols(), "matrix should be square");
+    assert!(mat.is_csr(), "relaxation sweeps require a CSR matrix");
+    assert!(
+        rhs.len() == mat.rows() && x.len() == mat.rows(),
+        "Dimension mismatch"
+    );
+}
+
+/// Perform one weighted Jacobi sweep on the system `A x = b`, where `x`
+/// holds the current approximation:
+/// `x <- x + omega D^-1 (b - A x)`, with `D` the diagonal of `A`.
+///
+/// # Panics
+///
+/// * if `mat` is not square, or not stored in CSR format
+/// * if the dimensions of `rhs` or `x` do not match `mat`
+/// * if a diagonal entry of `mat` is zero or missing
+pub fn jacobi_sweep<N, I>(
+    mat: CsMatViewI<N, I>,
+    rhs: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    omega: N,
+) where
+    N: Float,
+    I: SpIndex,
+{
+    check_dimensions(&mat, &rhs, &x);
+    let mut update = Array::zeros(x.len());
+    for (row_ind, row) in mat.outer_iterator().enumerate() {
+        let mut res = rhs[row_ind];
+        let mut diag = N::zero();
+        for (col_ind, &val) in row.iter() {
+            if col_ind == row_ind {
+                diag = val;
+            }
+            res = res - val * x[col_ind];
+        }
+        assert!(diag != N::zero(), "zero diagonal entry");
+        update[row_ind] = omega * res / diag;
+    }
+    x.zip_mut_with(&update, |xi, &u| *xi = *xi + u);
+}
+
+/// Perform one Gauss-Seidel sweep on the system `A x = b`, where `x` holds
+/// the current approximation.
+///
+/// # Panics
+///
+/// * if `mat` is not square, or not stored in CSR format
+/// * if the dimensions of `rhs` or `x` do not match `mat`
+/// * if a diagonal entry of `mat` is zero or missing
+pub fn gauss_seidel_sweep<N, I>(
+    mat: CsMatViewI<N, I>,
+    rhs: ArrayView1<N>,
+    x: ArrayViewMut1<N>,
+    direction: SweepDirection,
+) where
+    N: Float,
+    I: SpIndex,
+{
+    sor_sweep(mat, rhs, x, N::one(), direction);
+}
+
+/// Perform one successive over-relaxation sweep with weight `omega` on the
+/// system `A x = b`, where `x` holds the current approximation. Each
+/// unknown is updated as
+/// `x_i <- (1 - omega) x_i + omega (b_i - sum_{j != i} a_ij x_j) / a_ii`.
+///
+/// # Panics
+///
+/// * if `mat` is not square, or not stored in CSR format
+/// * if the dimensions of `rhs` or `x` do not match `mat`
+/// * if a diagonal entry of `mat` is zero or missing
+pub fn sor_sweep<N, I>(
+    mat: CsMatViewI<N, I>,
+    rhs: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    omega: N,
+    direction: SweepDirection,
+) where
+    N: Float,
+    I: SpIndex,
+{
+    check_dimensions(&mat, &rhs, &x);
+    let n = mat.rows();
+    let mut relax_row = |row_ind: usize| {
+        let row = mat.outer_view(row_ind).expect("row in bounds");
+        let mut sigma = N::zero();
+        let mut diag = N::zero();
+        for (col_ind, &val) in row.iter() {
+            if col_ind == row_ind {
+                diag = val;
+            } else {
+                sigma = sigma + val * x[col_ind];
+            }
+        }
+        assert!(diag != N::zero(), "zero diagonal entry");
+        let gs_value = (rhs[row_ind] - sigma) / diag;
+        x[row_ind] = (N::one() - omega) * x[row_ind] + omega * gs_value;
+    };
+    if direction != SweepDirection::Backward {
+        for row_ind in 0..n {
+            relax_row(row_ind);
+        }
+    }
+    if direction != SweepDirection::Forward {
+        for row_ind in (0..n).rev() {
+            relax_row(row_ind);
+        }
+    }
+}
+
+/// A preconditioner applying a few sweeps of a relaxation method to
+/// `A z = r`, starting from `z = 0`.
+///
+/// For a symmetric positive definite matrix, the Conjugate Gradient method
+/// requires a symmetric preconditioner, given by weighted Jacobi or by
+/// SSOR.
+#[derive(Debug)]
+pub struct RelaxationPreconditioner<'a, N: 'a, I: 'a + SpIndex> {
+    mat: CsMatViewI<'a, N, I>,
+    relaxation: Relaxation<N>,
+    sweeps: usize,
+}
+
+impl<'a, N, I> RelaxationPreconditioner<'a, N, I>
+where
+    N: 'a + Float,
+    I: 'a + SpIndex,
+{
+    /// Create a preconditioner applying `sweeps` sweeps of `relaxation`
+    /// to the system matrix `mat`.
+    ///
+    /// # Panics
+    ///
+    /// * if `mat` is not square, or not stored in CSR format
+    /// * if `sweeps` is zero
+    pub fn new(
+        mat: CsMatViewI<'a, N, I>,
+        relaxation: Relaxation<N>,
+        sweeps: usize,
+    ) -> RelaxationPreconditioner<'a, N, I> {
+        assert!(mat.rows() == mat.cols(), "matrix should be square");
+        assert!(mat.is_csr(), "relaxation sweeps require a CSR matrix");
+        assert!(sweeps > 0, "at least one sweep should be performed");
+        RelaxationPreconditioner {
+            mat: mat,
+            relaxation: relaxation,
+            sweeps: sweeps,
+        }
+    }
+
+    /// The relaxation method of this preconditioner
+    pub fn relaxation(&self) -> Relaxation<N> {
+        self.relaxation
+    }
+
+    /// The number of sweeps of this preconditioner
+    pub fn sweeps(&self) -> usize {
+        self.sweeps
+    }
+}
+
+impl<'a, N, I> Preconditioner<N> for RelaxationPreconditioner<'a, N, I>
+where
+    N: 'a + Float,
+    I: 'a + SpIndex,
+{
+    fn apply(&self, rhs: ArrayView1<N>, mut out: ArrayViewMut1<N>) {
+        out.fill(N::zero());
+        for _ in 0..self.sweeps {
+            self.relaxation
+                .sweep(self.mat.view(), rhs.view(), out.view_mut());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Relaxation, RelaxationPreconditioner, SweepDirection};
+    use ndarray::Array;
+    use sparse::linalg::iterative::{pcg, Preconditioner, StoppingCriteria};
+    use test_data::{grid_laplacian, max_abs_diff};
+
+    fn residual_norm(
+        mat: &::sparse::CsMat<f64>,
+        rhs: &Array<f64, ::ndarray::Ix1>,
+        x: &Array<f64, ::ndarray::Ix1>,
+    ) -> f64 {
+        (rhs - &(mat * x))
+            .iter()
+            .fold(0., |acc: f64, r| acc.hypot(*r))
+    }
+
+    #[test]
+    fn sweeps_reduce_residual() {
+        let mat = grid_laplacian(8, 9);
+        let n = mat.rows();
+        let rhs = Array::from_shape_fn(n, |i| (i as f64 * 0.7).sin());
+        let methods = [
+            Relaxation::jacobi(2. / 3.),
+            Relaxation::gauss_seidel(SweepDirection::Forward),
+            Relaxation::gauss_seidel(SweepDirection::Backward),
+            Relaxation::gauss_seidel(SweepDirection::Symmetric),
+            Relaxation::sor(1.5, SweepDirection::Forward),
+            Relaxation::ssor(1.2),
+        ];
+        for method in &methods {
+            let mut x = Array::zeros(n);
+            let mut prev_res = residual_norm(&mat, &rhs, &x);
+            for _ in 0..10 {
+                method.sweep(mat.view(), rhs.view(), x.view_mut());
+                let res = residual_norm(&mat, &rhs, &x);
+                assert!(res < prev_res);
+                prev_res = res;
+            }
+        }
+    }
+
+    #[test]
+    fn gauss_seidel_lower_triangular() {
+        // a forward sweep solves a lower triangular system exactly, while
+        // a backward sweep solves an upper triangular system
+        let lower = ::sparse::CsMat::new(
+            (3, 3),
+            vec![0, 1, 3, 6],
+            vec![0, 0, 1, 0, 1, 2],
+            vec![2., 1., 4., -1., 2., 5.],
+        );
+        let rhs = Array::from_vec(vec![2., 9., 13.]);
+        let mut x = Array::zeros(3);
+        super::gauss_seidel_sweep(
+            lower.view(),
+            rhs.view(),
+            x.view_mut(),
+            SweepDirection::Forward,
+        );
+        assert_eq!(x.to_vec(), vec![1., 2., 2.]);
+
+        let upper = lower.transpose_view().to_csr();
+        let rhs = Array::from_vec(vec![2., 12., 10.]);
+        let mut x = Array::zeros(3);
+        super::gauss_seidel_sweep(
+            upper.view(),
+            rhs.view(),
+            x.view_mut(),
+            SweepDirection::Backward,
+        );
+        assert_eq!(x.to_vec(), vec![1., 2., 2.]);
+
+        // jacobi on a diagonal matrix is exact with unit weight
+        let diag = ::sparse::CsMat::new(
+            (2, 2),
+            vec![0, 1, 2],
+            vec![0, 1],
+            vec![2., 4.],
+        );
+        let mut x = Array::from_vec(vec![5., -3.]);
+        let rhs = Array::from_vec(vec![1., 1.]);
+        super::jacobi_sweep(diag.view(), rhs.view(), x.view_mut(), 1.);
+        assert_eq!(x.to_vec(), vec![0.5, 0.25]);
+    }
+
+    #[test]
+    fn relaxation_preconditioner() {
+        let mat = grid_laplacian(15, 15);
+        let n = mat.rows();
+        let x0 = Array::from_shape_fn(n, |i| (i % 5) as f64);
+        let rhs = &mat * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 500);
+        let mut x = Array::zeros(n);
+        let plain = pcg(
+            mat.view(),
+            rhs.view(),
+            x.view_mut(),
+            &::sparse::linalg::iterative::IdentityPreconditioner,
+            &criteria,
+        );
+        let ssor = Relaxation::ssor(1.5);
+        assert!(ssor.is_symmetric());
+        let precond = RelaxationPreconditioner::new(mat.view(), ssor, 1);
+        let mut x = Array::zeros(n);
+        let report =
+            pcg(mat.view(), rhs.view(), x.view_mut(), &precond, &criteria);
+        assert!(report.converged());
+        assert!(report.iterations() < plain.iterations());
+        let err = max_abs_diff(&x, &x0);
+        assert!(err < 1e-7);
+
+        // the preconditioner does not depend on the output buffer
+        let mut out = Array::from_elem(n, 3.);
+        precond.apply(rhs.view(), out.view_mut());
+        let mut out2 = Array::zeros(n);
+        precond.apply(rhs.view(), out2.view_mut());
+        assert_eq!(out, out2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn sweep_csc_panics() {
+        let mat = grid_laplacian(3, 3).to_csc();
+        let rhs = Array::zeros(9);
+        let mut x = Array::zeros(9);
+        super::jacobi_sweep(mat.view(), rhs.view(), x.view_mut(), 1.);
+    }
+}
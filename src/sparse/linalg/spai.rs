@@ -0,0 +1,231 @@
+///! Sparse approximate inverses
+///!
+///! A sparse approximate inverse `M` of a matrix `A` minimizes the Frobenius
+///! norm `||A M - I||_F` over the matrices with a prescribed sparsity
+///! pattern. This norm decouples into the euclidean norms of the columns
+///! of `A M - I`, so each column of `M` is the solution of a small, dense,
+///! independent least squares problem.
+///!
+///! Unlike incomplete factorizations, applying `M` only requires a sparse
+///! matrix-vector product, and a sparse matrix can be used directly as
+///! a preconditioner for the solvers in `linalg::iterative`. As `M`
+///! approximates `A^-1` when multiplied on the right, it is best used as
+///! a right preconditioner.
+///!
+///! Reference: M. J. Grote and T. Huckle, Parallel preconditioning with
+///! sparse approximate inverses, SIAM J. Sci. Comput. 18(3), 838-853 (1997).
+use ndarray::{Array, Array2};
+use num_traits::Float;
+
+use indexing::SpIndex;
+use sparse::linalg::dense::least_squares;
+use sparse::{CsMatI, CsMatViewI};
+
+/// The sparsity pattern of a sparse approximate inverse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpaiPattern {
+    /// The pattern of `A`, plus its diagonal
+    Matrix,
+    /// The pattern of `A^2`, plus the pattern of `A` and its diagonal,
+    /// which gives a more accurate but denser approximate inverse
+    MatrixSquared,
+}
+
+/// Compute the sparse approximate inverse of a square matrix with the given
+/// sparsity pattern, which minimizes `||A M - I||_F`.
+///
+/// The matrix can be stored in either CSR or CSC format, and the
+/// approximate inverse is returned in CSC format. Its columns are computed
+/// independently of each other.
+///
+/// # Panics
+///
+/// * if `mat` is not square
+pub fn spai<N, I>(mat: CsMatViewI<N, I>, pattern: SpaiPattern) -> CsMatI<N, I>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+{
+    assert!(mat.rows() == mat.cols(), "matrix should be square");
+    let n = mat.rows();
+    let mat_csc = if mat.is_csc() {
+        mat.to_owned()
+    } else {
+        mat.to_csc()
+    };
+    let col_rows = |col: usize| {
+        mat_csc
+            .outer_view(col)
+            .expect("column in bounds")
+            .indices()
+            .iter()
+            .map(|row| row.index())
+            .collect::<Vec<_>>()
+    };
+
+    let mut indptr = Vec::with_capacity(n + 1);
+    let mut indices = Vec::with_capacity(mat.nnz());
+    let mut data = Vec::with_capacity(mat.nnz());
+    indptr.push(I::zero());
+    // position of a column or row in the current subproblem, or n if absent
+    let mut col_pos = vec![n; n];
+    let mut row_pos = vec![n; n];
+    for j in 0..n {
+        // the pattern of the column of M
+        let mut cols = vec![j];
+        col_pos[j] = 0;
+        let mut add_col = |cols: &mut Vec<usize>, col: usize| {
+            if col_pos[col] == n {
+                col_pos[col] = cols.len();
+                cols.push(col);
+            }
+        };
+        let first = col_rows(j);
+        for &k in &first {
+            add_col(&mut cols, k);
+        }
+        if pattern == SpaiPattern::MatrixSquared {
+            for &k in &first {
+                for row in col_rows(k) {
+                    add_col(&mut cols, row);
+                }
+            }
+        }
+        cols.sort();
+
+        // the rows of A reached by these columns
+        let mut rows = Vec::new();
+        for &col in &cols {
+            for row in col_rows(col) {
+                if row_pos[row] == n {
+                    row_pos[row] = rows.len();
+                    rows.push(row);
+                }
+            }
+        }
+        let mut sub = Array2::zeros((rows.len(), cols.len()));
+        for (k, &col) in cols.iter().enumerate() {
+            let col_vec = mat_csc.outer_view(col).expect("column in bounds");
+            for (row, &val) in col_vec.iter() {
+                sub[[row_pos[row], k]] = val;
+            }
+        }
+        let mut unit = Array::zeros(rows.len());
+        if row_pos[j] != n {
+            unit[row_pos[j]] = N::one();
+        }
+        let sol = least_squares(sub, unit);
+
+        for (&col, &val) in cols.iter().zip(sol.iter()) {
+            indices.push(I::from_usize(col));
+            data.push(val);
+        }
+        indptr.push(I::from_usize(indices.len()));
+        for &col in &cols {
+            col_pos[col] = n;
+        }
+        for &row in &rows {
+            row_pos[row] = n;
+        }
+    }
+    CsMatI::new_csc((n, n), indptr, indices, data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::SpaiPattern;
+    use ndarray::Array;
+    use sparse::linalg::iterative::{
+        gmres, pgmres, PreconditionerSide, StoppingCriteria,
+    };
+    use sparse::CsMat;
+    use test_data::{grid_convection_diffusion, max_abs_diff};
+
+    /// The euclidean norms of the columns of `A M - I`
+    fn column_residuals(mat: &CsMat<f64>, inv: &CsMat<f64>) -> Vec<f64> {
+        let n = mat.rows();
+        let prod = &mat.to_csc() * inv;
+        let mut norms = vec![0.; n];
+        for (j, col) in prod.outer_iterator().enumerate() {
+            let mut has_diag = false;
+            for (i, &val) in col.iter() {
+                let res = if i == j { val - 1. } else { val };
+                has_diag |= i == j;
+                norms[j] += res * res;
+            }
+            if !has_diag {
+                norms[j] += 1.;
+            }
+            norms[j] = norms[j].sqrt();
+        }
+        norms
+    }
+
+    #[test]
+    fn spai_diagonal() {
+        let mat = CsMat::new(
+            (3, 3),
+            vec![0, 1, 2, 3],
+            vec![0, 1, 2],
+            vec![2., -4., 5.],
+        );
+        let inv = super::spai(mat.view(), SpaiPattern::Matrix);
+        assert!(inv.is_csc());
+        assert_eq!(inv.indices(), &[0, 1, 2]);
+        assert_eq!(inv.data(), &[0.5, -0.25, 0.2]);
+    }
+
+    #[test]
+    fn spai_convection_diffusion() {
+        let mat = grid_convection_diffusion(12, 10, 2.);
+        let n = mat.rows();
+        let spai_a = super::spai(mat.view(), SpaiPattern::Matrix);
+        let spai_a2 =
+            super::spai(mat.to_csc().view(), SpaiPattern::MatrixSquared);
+        assert_eq!(spai_a.indptr(), mat.to_csc().indptr());
+        assert!(spai_a2.nnz() > spai_a.nnz());
+
+        // the Jacobi preconditioner has the diagonal pattern, which is
+        // included in the patterns of the approximate inverses
+        let diag_inv = (0..n).map(|i| 1. / mat.get(i, i).unwrap()).collect();
+        let jacobi = CsMat::new_csc(
+            (n, n),
+            (0..n + 1).collect(),
+            (0..n).collect(),
+            diag_inv,
+        );
+        let res_jacobi = column_residuals(&mat, &jacobi);
+        let res_a = column_residuals(&mat, &spai_a);
+        let res_a2 = column_residuals(&mat, &spai_a2);
+        for j in 0..n {
+            assert!(res_a[j] <= res_jacobi[j] + 1e-12);
+            assert!(res_a2[j] <= res_a[j] + 1e-12);
+        }
+
+        let x0 = Array::from_shape_fn(n, |i| (i as f64 * 0.3).cos());
+        let rhs = &mat * &x0;
+        let criteria = StoppingCriteria::new(1e-10, 500);
+        let mut x = Array::zeros(n);
+        let report = gmres(mat.view(), rhs.view(), x.view_mut(), 50, &criteria);
+        assert!(report.converged());
+        let mut iterations = vec![report.iterations()];
+        for inv in &[spai_a, spai_a2] {
+            let mut x = Array::zeros(n);
+            let report = pgmres(
+                mat.view(),
+                rhs.view(),
+                x.view_mut(),
+                50,
+                inv,
+                PreconditionerSide::Right,
+                &criteria,
+            );
+            assert!(report.converged());
+            let err = max_abs_diff(&x, &x0);
+            assert!(err < 1e-7);
+            iterations.push(report.iterations());
+        }
+        assert!(iterations[1] < iterations[0]);
+        assert!(iterations[2] < iterations[1]);
+    }
+}
@@ -0,0 +1,419 @@
+///! Truncated singular value decompositions of sparse matrices
+///!
+///! These methods compute the largest singular triplets of a sparse matrix,
+///! only using products of the matrix and of its transpose with dense
+///! vectors or blocks of vectors:
+///!
+///! - `randomized_svd` implements the randomized range finder with power
+///!   iterations, which performs a fixed amount of work and is accurate for
+///!   matrices whose singular values decay quickly.
+///! - `lanczos_svd` implements the Golub-Kahan-Lanczos bidiagonalization,
+///!   with thick restarts, which iterates until the requested tolerance is
+///!   reached.
+///!
+///! References:
+///!
+///! - N. Halko, P. G. Martinsson and J. A. Tropp, Finding structure with
+///!   randomness: probabilistic algorithms for constructing approximate
+///!   matrix decompositions, SIAM Review 53(2), 217-288 (2011).
+///! - J. Baglama and L. Reichel, Augmented implicitly restarted Lanczos
+///!   bidiagonalization methods, SIAM J. Sci. Comput. 27(1), 19-42 (2005).
+use ndarray::{Array, Array2, ArrayView1, ArrayView2, Axis, Ix1, Slice};
+use num_traits::Float;
+
+use indexing::SpIndex;
+use sparse::linalg::dense::{norm2, orthogonalize, thin_svd, XorShift};
+use sparse::linalg::eigen::EigenOptions;
+use sparse::CsMatViewI;
+
+/// A truncated singular value decomposition `A ~ U diag(s) V^T`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Svd<N> {
+    u: Array2<N>,
+    singular_values: Array<N, Ix1>,
+    vt: Array2<N>,
+    residual_norms: Vec<N>,
+    iterations: usize,
+    converged: bool,
+}
+
+impl<N: Copy> Svd<N> {
+    /// The left singular vectors, stored as the columns of an array
+    pub fn u(&self) -> ArrayView2<N> {
+        self.u.view()
+    }
+
+    /// The singular values, in decreasing order
+    pub fn singular_values(&self) -> ArrayView1<N> {
+        self.singular_values.view()
+    }
+
+    /// The right singular vectors, stored as the rows of an array
+    pub fn vt(&self) -> ArrayView2<N> {
+        self.vt.view()
+    }
+
+    /// The norms of the residuals of the singular triplets `(u, s, v)`,
+    /// defined as `sqrt(||A v - s u||^2 + ||A^T u - s v||^2)`
+    pub fn residual_norms(&self) -> &[N] {
+        &self.residual_norms[..]
+    }
+
+    /// The number of restarts or power iterations performed
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Whether all singular triplets reached the tolerance of the solver.
+    /// The randomized SVD has no tolerance, and always reports convergence.
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+
+    /// Get the decomposition as a tuple `(U, s, V^T)`
+    pub fn into_parts(self) -> (Array2<N>, Array<N, Ix1>, Array2<N>) {
+        (self.u, self.singular_values, self.vt)
+    }
+}
+
+/// Compute the `nsv` largest singular triplets of a matrix using the
+/// randomized range finder.
+///
+/// The range of the matrix is sampled by its product with a random block of
+/// `nsv + oversampling` vectors, and `power_iters` products with `A A^T`
+/// refine this sample. Ten vectors of oversampling and a few power
+/// iterations are usually enough for an accurate decomposition, unless the
+/// singular values decay slowly.
+///
+/// The matrix can be stored in either CSR or CSC format.
+///
+/// # Panics
+///
+/// * if `nsv` is zero or larger than the smallest dimension of `mat`
+pub fn randomized_svd<N, I>(
+    mat: CsMatViewI<N, I>,
+    nsv: usize,
+    oversampling: usize,
+    power_iters: usize,
+) -> Svd<N>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+{
+    let (rows, cols) = mat.shape();
+    assert!(nsv > 0, "at least one singular value should be requested");
+    assert!(nsv <= rows.min(cols), "too many singular values requested");
+    let nb_samples = (nsv + oversampling).min(rows.min(cols));
+    let mat_t = mat.transpose_view();
+    let mut rng = XorShift::new((rows + cols) as u64);
+
+    let omega = Array2::from_shape_fn((cols, nb_samples), |_| rng.next_float());
+    let mut range = orthonormal_basis(&mat * &omega, &mut rng);
+    for _ in 0..power_iters {
+        let co_range = orthonormal_basis(&mat_t * &range, &mut rng);
+        range = orthonormal_basis(&mat * &co_range, &mut rng);
+    }
+    // B = Q^T A, whose transpose B^T = A^T Q = U_b diag(s) V_b^T gives
+    // A ~ Q B = (Q V_b) diag(s) U_b^T
+    let (ub, sigma, vb) = thin_svd(&mat_t * &range);
+    let u = range.dot(&vb.slice_axis(Axis(1), Slice::from(0..nsv)));
+    let v = ub.slice_axis(Axis(1), Slice::from(0..nsv)).to_owned();
+    let sigma = sigma.slice_axis(Axis(0), Slice::from(0..nsv)).to_owned();
+    svd_result(&mat, u, sigma, v, power_iters, true)
+}
+
+/// Compute the `nsv` largest singular triplets of a matrix using the
+/// thick-restart Golub-Kahan-Lanczos bidiagonalization.
+///
+/// A singular triplet is considered converged once its residual norm is
+/// below `tol * s_max`, where `s_max` is the largest singular value. The
+/// subspace dimension of `options` is the number of Lanczos vectors, on
+/// each side, before a restart. Its symmetry check is not used. If the
+/// singular triplets do not converge, the best approximations found are
+/// returned, and the result reports the absence of convergence.
+///
+/// The matrix can be stored in either CSR or CSC format.
+///
+/// # Panics
+///
+/// * if `nsv` is zero or larger than the smallest dimension of `mat`
+/// * if the subspace dimension requested in `options` is invalid
+pub fn lanczos_svd<N, I>(
+    mat: CsMatViewI<N, I>,
+    nsv: usize,
+    options: &EigenOptions<N>,
+) -> Svd<N>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+{
+    let (rows, cols) = mat.shape();
+    let m = options.subspace_dim_for(nsv, rows.min(cols));
+    let mat_t = mat.transpose_view();
+    let small = N::from(rows.max(cols)).expect("representable dimension")
+        * N::epsilon();
+    let mut rng = XorShift::new((rows + cols) as u64);
+    // the Lanczos bases, with the next right Lanczos vector in the last
+    // column of v_basis, and the projection U^T A V, which is upper
+    // bidiagonal before the first restart
+    let mut v_basis = Array2::zeros((cols, m + 1));
+    let mut u_basis = Array2::zeros((rows, m));
+    let mut proj = Array2::zeros((m, m));
+    let w: Array<N, Ix1> = Array::from_shape_fn(cols, |_| rng.next_float());
+    let w_norm = norm2(&w);
+    v_basis.column_mut(0).assign(&w.mapv(|wi| wi / w_norm));
+
+    let mut kept = 0;
+    let mut restarts = 0;
+    loop {
+        // extend the bidiagonalization up to m vectors on each side
+        let mut beta = N::zero();
+        for j in kept..m {
+            // the product with a sparse matrix needs a contiguous vector
+            let v = v_basis.column(j).to_owned();
+            let mut u = &mat * &v;
+            let op_norm = norm2(&u);
+            let coeffs = orthogonalize(&u_basis, j, &mut u);
+            for (i, &c) in coeffs.iter().enumerate() {
+                proj[[i, j]] = c;
+            }
+            let mut alpha = norm2(&u);
+            if alpha <= small * op_norm {
+                // A v lies in the span of the previous vectors
+                alpha = N::zero();
+                u = random_orthogonal(&u_basis, j, &mut rng);
+            }
+            proj[[j, j]] = alpha;
+            let u_norm = norm2(&u);
+            u_basis.column_mut(j).assign(&u.mapv(|ui| ui / u_norm));
+
+            let u = u_basis.column(j).to_owned();
+            let mut v = &mat_t * &u;
+            let op_norm = norm2(&v);
+            orthogonalize(&v_basis, j + 1, &mut v);
+            beta = norm2(&v);
+            if beta <= small * op_norm {
+                // invariant subspace, continue with a random vector
+                beta = N::zero();
+                v = random_orthogonal(&v_basis, j + 1, &mut rng);
+            }
+            let v_norm = norm2(&v);
+            if v_norm > N::zero() {
+                v.mapv_inplace(|vi| vi / v_norm);
+            }
+            v_basis.column_mut(j + 1).assign(&v);
+        }
+
+        // singular triplets of the projection, which are exact for A V,
+        // while A^T U has the residual beta v_next e_m^T
+        let (x, sigma, y) = thin_svd(proj.clone());
+        let sigma_max = sigma[0];
+        let converged = (0..nsv)
+            .all(|i| (beta * x[[m - 1, i]]).abs() <= options.tol() * sigma_max);
+        let ritz_vectors = |k: usize| {
+            let u_vecs = u_basis.dot(&x.slice_axis(Axis(1), Slice::from(0..k)));
+            let v_vecs = v_basis
+                .slice_axis(Axis(1), Slice::from(0..m))
+                .dot(&y.slice_axis(Axis(1), Slice::from(0..k)));
+            (u_vecs, v_vecs)
+        };
+        if converged || restarts >= options.max_restarts() {
+            let (u, v) = ritz_vectors(nsv);
+            let sigma =
+                sigma.slice_axis(Axis(0), Slice::from(0..nsv)).to_owned();
+            return svd_result(&mat, u, sigma, v, restarts, converged);
+        }
+
+        // thick restart, keeping the largest singular triplets and the next
+        // right Lanczos vector
+        kept = (nsv + (m - nsv) / 2).min(m - 1);
+        let (kept_u, kept_v) = ritz_vectors(kept);
+        let next = v_basis.column(m).to_owned();
+        u_basis
+            .slice_axis_mut(Axis(1), Slice::from(0..kept))
+            .assign(&kept_u);
+        v_basis
+            .slice_axis_mut(Axis(1), Slice::from(0..kept))
+            .assign(&kept_v);
+        v_basis.column_mut(kept).assign(&next);
+        proj.fill(N::zero());
+        for i in 0..kept {
+            proj[[i, i]] = sigma[i];
+        }
+        restarts += 1;
+    }
+}
+
+/// Orthonormalize the columns of `block`, replacing the numerically
+/// dependent ones by random vectors.
+fn orthonormal_basis<N>(mut block: Array2<N>, rng: &mut XorShift) -> Array2<N>
+where
+    N: 'static + Float,
+{
+    let small =
+        N::from(block.rows()).expect("representable dimension") * N::epsilon();
+    for j in 0..block.cols() {
+        let mut w = block.column(j).to_owned();
+        let orig_norm = norm2(&w);
+        orthogonalize(&block, j, &mut w);
+        if norm2(&w) <= small * orig_norm || orig_norm == N::zero() {
+            w = random_orthogonal(&block, j, rng);
+        }
+        let w_norm = norm2(&w);
+        block.column_mut(j).assign(&w.mapv(|wi| wi / w_norm));
+    }
+    block
+}
+
+/// A random vector orthogonal to the first `dim` columns of `basis`, or
+/// a zero vector if these columns span the whole space.
+fn random_orthogonal<N>(
+    basis: &Array2<N>,
+    dim: usize,
+    rng: &mut XorShift,
+) -> Array<N, Ix1>
+where
+    N: 'static + Float,
+{
+    let n = basis.rows();
+    let mut w = Array::from_shape_fn(n, |_| rng.next_float());
+    let rand_norm = norm2(&w);
+    orthogonalize(basis, dim, &mut w);
+    let small = N::from(n).expect("representable dimension") * N::epsilon();
+    if norm2(&w) <= small * rand_norm {
+        w.fill(N::zero());
+    }
+    w
+}
+
+/// Compute the true residual norms of the singular triplets
+fn svd_result<N, I>(
+    mat: &CsMatViewI<N, I>,
+    u: Array2<N>,
+    singular_values: Array<N, Ix1>,
+    v: Array2<N>,
+    iterations: usize,
+    converged: bool,
+) -> Svd<N>
+where
+    N: 'static + Float + Default,
+    I: SpIndex,
+{
+    let mut res_v = mat * &v;
+    let mut res_u = &mat.transpose_view() * &u;
+    for (k, &s) in singular_values.iter().enumerate() {
+        res_v.column_mut(k).scaled_add(-s, &u.column(k));
+        res_u.column_mut(k).scaled_add(-s, &v.column(k));
+    }
+    let residual_norms = (0..singular_values.len())
+        .map(|k| {
+            res_v
+                .column(k)
+                .iter()
+                .chain(res_u.column(k).iter())
+                .fold(N::zero(), |acc, &r| acc.hypot(r))
+        })
+        .collect();
+    Svd {
+        u: u,
+        singular_values: singular_values,
+        vt: v.reversed_axes(),
+        residual_norms: residual_norms,
+        iterations: iterations,
+        converged: converged,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::Array2;
+    use sparse::linalg::dense::thin_svd;
+    use sparse::linalg::eigen::EigenOptions;
+    use sparse::{CsMat, TriMat};
+
+    /// A sparse `(rows, cols)` matrix whose columns are scaled by `decay^j`
+    fn decaying_mat(rows: usize, cols: usize, decay: f64) -> CsMat<f64> {
+        let mut tri = TriMat::new((rows, cols));
+        for j in 0..cols {
+            let scale = decay.powi(j as i32);
+            for k in 0..3 {
+                let i = (j * 7 + k * 11) % rows;
+                tri.add_triplet(i, j, scale * (1. + (i + k) as f64 * 0.1));
+            }
+        }
+        tri.to_csr()
+    }
+
+    fn dense_singular_values(mat: &CsMat<f64>) -> Vec<f64> {
+        let dense = mat.to_dense();
+        let dense = if dense.rows() >= dense.cols() {
+            dense
+        } else {
+            dense.reversed_axes()
+        };
+        thin_svd(dense).1.to_vec()
+    }
+
+    fn check_orthonormal(vecs: &Array2<f64>) {
+        let gram = vecs.t().dot(vecs);
+        for i in 0..gram.rows() {
+            for j in 0..gram.cols() {
+                let expected = if i == j { 1. } else { 0. };
+                assert!((gram[[i, j]] - expected).abs() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn randomized_svd() {
+        let mat = decaying_mat(150, 60, 0.5);
+        let expected = dense_singular_values(&mat);
+        for mat in &[mat.to_owned(), mat.to_csc()] {
+            let svd = super::randomized_svd(mat.view(), 5, 10, 2);
+            assert!(svd.converged());
+            assert_eq!(svd.u().shape(), &[150, 5]);
+            assert_eq!(svd.vt().shape(), &[5, 60]);
+            for (&s, &exp) in svd.singular_values().iter().zip(&expected) {
+                assert!((s - exp).abs() < 1e-8 * expected[0]);
+            }
+            assert!(svd.residual_norms().iter().all(|&r| r < 1e-6));
+            let (u, _, vt) = svd.into_parts();
+            check_orthonormal(&u);
+            check_orthonormal(&vt.reversed_axes());
+        }
+    }
+
+    #[test]
+    fn lanczos_svd() {
+        // a wide matrix with slowly decaying singular values
+        let mat = decaying_mat(200, 90, 0.99).transpose_into();
+        let expected = dense_singular_values(&mat);
+        let options = EigenOptions::new(1e-10, 300).with_subspace_dim(16);
+        let svd = super::lanczos_svd(mat.view(), 4, &options);
+        assert!(svd.converged());
+        assert!(svd.iterations() > 0);
+        for (&s, &exp) in svd.singular_values().iter().zip(&expected) {
+            assert!((s - exp).abs() < 1e-8 * expected[0]);
+        }
+        assert!(svd.residual_norms().iter().all(|&r| r < 1e-8));
+        check_orthonormal(&svd.u().to_owned());
+        check_orthonormal(&svd.vt().t().to_owned());
+    }
+
+    #[test]
+    fn lanczos_svd_small_rank() {
+        // the rank is smaller than the number of requested triplets
+        let mut tri = TriMat::new((30, 20));
+        tri.add_triplet(3, 4, 2.);
+        tri.add_triplet(10, 7, -1.);
+        tri.add_triplet(10, 8, 1.);
+        let mat = tri.to_csc();
+        let svd = super::lanczos_svd(mat.view(), 4, &EigenOptions::default());
+        assert!(svd.converged());
+        let expected = [2., 2f64.sqrt(), 0., 0.];
+        for (&s, &exp) in svd.singular_values().iter().zip(&expected) {
+            assert!((s - exp).abs() < 1e-10);
+        }
+        check_orthonormal(&svd.u().to_owned());
+    }
+}
@@ -4,6 +4,8 @@ use indexing::SpIndex;
 /// Both the permutation matrices and its inverse are stored
 use std::ops::{Deref, Mul};
 
+use sparse::{CsMatI, CsMatViewI};
+
 #[derive(Debug, Clone)]
 enum PermStorage<I, IndStorage>
 where
@@ -157,6 +159,11 @@ where
         }
     }
 
+    /// The dimension of this permutation
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
     pub fn at(&self, index: usize) -> usize {
         assert!(index < self.dim);
         match &self.storage {
@@ -252,6 +259,120 @@ where
     }
 }
 
+/// Build the compressed matrix whose outer slice `k` is the outer slice
+/// `outer_old_of_new(k)` of `mat`, with its inner indices mapped by
+/// `inner_new_of_old`. The inner indices of the result are sorted.
+fn permute_compressed<N, I, F, G>(
+    mat: CsMatViewI<N, I>,
+    outer_old_of_new: F,
+    inner_new_of_old: G,
+) -> CsMatI<N, I>
+where
+    N: Copy,
+    I: SpIndex,
+    F: Fn(usize) -> usize,
+    G: Fn(usize) -> usize,
+{
+    let mut indptr = Vec::with_capacity(mat.outer_dims() + 1);
+    let mut indices = Vec::with_capacity(mat.nnz());
+    let mut data = Vec::with_capacity(mat.nnz());
+    let mut outer_buf = Vec::new();
+    indptr.push(I::zero());
+    for outer in 0..mat.outer_dims() {
+        let vec = mat
+            .outer_view(outer_old_of_new(outer))
+            .expect("outer index is in bounds");
+        outer_buf.clear();
+        outer_buf.extend(
+            vec.iter()
+                .map(|(inner, &val)| (inner_new_of_old(inner), val)),
+        );
+        outer_buf.sort_by_key(|&(inner, _)| inner);
+        for &(inner, val) in &outer_buf {
+            indices.push(I::from_usize(inner));
+            data.push(val);
+        }
+        indptr.push(I::from_usize(indices.len()));
+    }
+    if mat.is_csr() {
+        CsMatI::new(mat.shape(), indptr, indices, data)
+    } else {
+        CsMatI::new_csc(mat.shape(), indptr, indices, data)
+    }
+}
+
+/// Compute the symmetric permutation `P A P^T` of a square matrix.
+///
+/// The entry `A[i, j]` is moved to `(P^-1.at(i), P^-1.at(j))`, ie the `k`-th
+/// row and column of the result are the row and column `perm.at(k)` of `A`.
+/// The result has the same storage as `mat`, with sorted indices.
+///
+/// # Panics
+///
+/// * if `mat` is not square
+/// * if the dimension of `perm` does not match `mat`
+pub fn transform_mat_papt<N, I>(
+    mat: CsMatViewI<N, I>,
+    perm: PermViewI<I>,
+) -> CsMatI<N, I>
+where
+    N: Copy,
+    I: SpIndex,
+{
+    assert_eq!(mat.rows(), mat.cols(), "matrix should be square");
+    assert_eq!(perm.dim(), mat.rows(), "Dimension mismatch");
+    permute_compressed(mat, |k| perm.at(k), |i| perm.at_inv(i))
+}
+
+/// Compute the row permutation `P A` of a matrix.
+///
+/// The `k`-th row of the result is the row `perm.at(k)` of `A`.
+/// The result has the same storage as `mat`, with sorted indices.
+///
+/// # Panics
+///
+/// * if the dimension of `perm` does not match the rows of `mat`
+pub fn transform_mat_pa<N, I>(
+    mat: CsMatViewI<N, I>,
+    perm: PermViewI<I>,
+) -> CsMatI<N, I>
+where
+    N: Copy,
+    I: SpIndex,
+{
+    assert_eq!(perm.dim(), mat.rows(), "Dimension mismatch");
+    if mat.is_csr() {
+        permute_compressed(mat, |k| perm.at(k), |j| j)
+    } else {
+        permute_compressed(mat, |k| k, |i| perm.at_inv(i))
+    }
+}
+
+/// Compute the column permutation `A Q` of a matrix.
+///
+/// The `k`-th column of the result is the column `perm.at_inv(k)` of `A`,
+/// so that `A Q^T` permutes the columns the same way `P A` permutes the
+/// rows. The result has the same storage as `mat`, with sorted indices.
+///
+/// # Panics
+///
+/// * if the dimension of `perm` does not match the columns of `mat`
+pub fn transform_mat_aq<N, I>(
+    mat: CsMatViewI<N, I>,
+    perm: PermViewI<I>,
+) -> CsMatI<N, I>
+where
+    N: Copy,
+    I: SpIndex,
+{
+    assert_eq!(perm.dim(), mat.cols(), "Dimension mismatch");
+    if mat.is_csr() {
+        permute_compressed(mat, |i| i, |j| perm.at(j))
+    } else {
+        permute_compressed(mat, |k| perm.at_inv(k), |i| i)
+    }
+}
+
 mod test {
 
     #[test]
@@ -267,4 +388,59 @@ mod test {
         let y = &p * &x;
         assert_eq!(&y, &[2, 1, 3, 5, 4]);
     }
+
+    #[test]
+    fn permute_matrices() {
+        use ndarray::arr2;
+        use sparse::CsMat;
+        // | 1 2 . |
+        // | . 3 4 |
+        // | 5 . 6 |
+        let mat = CsMat::new(
+            (3, 3),
+            vec![0, 2, 4, 6],
+            vec![0, 1, 1, 2, 0, 2],
+            vec![1, 2, 3, 4, 5, 6],
+        );
+        let p = super::PermOwned::new(vec![2, 0, 1]);
+        let p_dense = arr2(&[[0, 0, 1], [1, 0, 0], [0, 1, 0]]);
+        let a_dense = mat.to_dense();
+        let papt = p_dense.dot(&a_dense).dot(&p_dense.t());
+        let pa = p_dense.dot(&a_dense);
+        let aq = a_dense.dot(&p_dense);
+        for m in &[mat.to_owned(), mat.to_csc()] {
+            let res = super::transform_mat_papt(m.view(), p.view());
+            assert_eq!(res.storage(), m.storage());
+            assert!(res.check_compressed_structure().is_ok());
+            assert_eq!(res.to_dense(), papt);
+            let res = super::transform_mat_pa(m.view(), p.view());
+            assert!(res.check_compressed_structure().is_ok());
+            assert_eq!(res.to_dense(), pa);
+            let res = super::transform_mat_aq(m.view(), p.view());
+            assert!(res.check_compressed_structure().is_ok());
+            assert_eq!(res.to_dense(), aq);
+        }
+        let id = super::PermOwned::identity(3);
+        let res = super::transform_mat_papt(mat.view(), id.view());
+        assert_eq!(res, mat);
+    }
+
+    #[test]
+    fn permute_rectangular() {
+        use sparse::CsMat;
+        // | 1 . 2 . |
+        // | . 3 . 4 |
+        let mat = CsMat::new(
+            (2, 4),
+            vec![0, 2, 4],
+            vec![0, 2, 1, 3],
+            vec![1., 2., 3., 4.],
+        );
+        let p = super::PermOwned::new(vec![1, 0]);
+        let pa = super::transform_mat_pa(mat.view(), p.view());
+        assert_eq!(pa.indices(), &[1, 3, 0, 2]);
+        let q = super::PermOwned::new(vec![3, 2, 1, 0]);
+        let aq = super::transform_mat_aq(mat.to_csc().view(), q.view());
+        assert_eq!(aq.to_csr().indices(), &[1, 3, 0, 2]);
+    }
 }
@@ -1,7 +1,7 @@
 //! Some matrices used in tests
 
-use ndarray::{arr2, Array, Ix2, ShapeBuilder};
-use sparse::CsMat;
+use ndarray::{arr2, Array, Ix1, Ix2, ShapeBuilder};
+use sparse::{CsMat, TriMat};
 
 pub fn mat1() -> CsMat<f64> {
     let indptr = vec![0, 2, 4, 5, 6, 7];
@@ -122,3 +122,54 @@ pub fn mat_dense2() -> Array<f64, Ix2> {
     ]);
     m.to_owned()
 }
+
+/// The 5-point discrete laplacian on a `rows * cols` grid with Dirichlet
+/// boundary conditions, with its sign flipped to make it symmetric positive
+/// definite. Grid nodes are numbered in C-order.
+pub fn grid_laplacian(rows: usize, cols: usize) -> CsMat<f64> {
+    ::sparse::construct::grid_laplacian(&[rows, cols])
+}
+
+/// A non-symmetric convection-diffusion operator on a `rows * cols` grid,
+/// obtained by adding to `grid_laplacian` an upwind discretization of
+/// a convection along the columns of the grid, with the given velocity.
+pub fn grid_convection_diffusion(
+    rows: usize,
+    cols: usize,
+    velocity: f64,
+) -> CsMat<f64> {
+    let n = rows * cols;
+    let mut tri = TriMat::with_capacity((n, n), 2 * n);
+    for i in 0..rows {
+        for j in 0..cols {
+            let ind = i * cols + j;
+            tri.add_triplet(ind, ind, velocity);
+            if j > 0 {
+                tri.add_triplet(ind, ind - 1, -velocity);
+            }
+        }
+    }
+    &grid_laplacian(rows, cols) + &tri.to_csr()
+}
+
+/// A sparse over-determined matrix of shape `(3 n, n)`, with full column rank
+pub fn overdetermined(n: usize) -> CsMat<f64> {
+    let mut tri = TriMat::with_capacity((3 * n, n), 4 * n);
+    for j in 0..n {
+        tri.add_triplet(j, j, 2. + (j % 3) as f64);
+        tri.add_triplet(n + j, j, 1.);
+        tri.add_triplet(2 * n + (j * 5) % n, j, -0.5);
+        if j + 1 < n {
+            tri.add_triplet(j + 1, j, 0.7);
+        }
+    }
+    tri.to_csr()
+}
+
+/// The largest absolute difference between the entries of two vectors
+pub fn max_abs_diff(a: &Array<f64, Ix1>, b: &Array<f64, Ix1>) -> f64 {
+    assert_eq!(a.len(), b.len());
+    a.iter()
+        .zip(b.iter())
+        .fold(0., |acc: f64, (x, y)| acc.max((x - y).abs()))
+}